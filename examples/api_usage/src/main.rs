@@ -1,7 +1,6 @@
 use {
     dotenvy::dotenv,
     futures::future::join_all,
-    std::env,
     tokio::{
         signal,
         sync::MutexGuard,
@@ -9,8 +8,10 @@ use {
     },
     tracing::info,
     upstox_rust_sdk::{
-        client::{ApiClient, AutomateLoginConfig, LoginConfig, MailProvider, WSConnectConfig},
-        constants::UPLINK_API_KEY_ENV,
+        client::{
+            ApiClient, AutomateLoginConfig, Credentials, InstrumentsConfig, LoginConfig,
+            LoginStrategy, MailProvider, WSConnectConfig,
+        },
         models::{
             ProductType, TransactionType,
             charges::brokerage_details_request::BrokerageDetailsRequest,
@@ -31,22 +32,28 @@ async fn main() {
     tracing_subscriber::fmt::init();
     let _ = dotenv();
 
-    let api_key: String = env::var(UPLINK_API_KEY_ENV).unwrap();
-    let (fetch_instruments, schedule_refresh_instruments) = (true, false);
+    let credentials: Credentials = Credentials::from_env().unwrap();
 
     let (api_client, tasks_vec) = ApiClient::new(
-        &api_key,
+        credentials,
         LoginConfig {
             authorize: true,
+            login_strategy: LoginStrategy::Browser,
             automate_login_config: Some(AutomateLoginConfig {
                 automate_login: true,
                 schedule_login: false,
                 automate_fetching_otp: true,
                 mail_provider: Some(MailProvider::Google),
+                use_totp: false,
+                webdriver_config: None,
             }),
+            token_store: None,
+            on_unauthorized: None,
+        },
+        InstrumentsConfig {
+            fetch_instruments: true,
+            ..InstrumentsConfig::default()
         },
-        fetch_instruments,
-        schedule_refresh_instruments,
         WSConnectConfig {
             connect_portfolio_stream: false,
             connect_market_data_stream_v3: false,
@@ -71,7 +78,7 @@ async fn main() {
         // Charges Endpoints
         let _charges_result = api_client
             .get_brokerage_details(BrokerageDetailsRequest {
-                instrument_token: "NSE_EQ|INE806T01012".to_string(),
+                instrument_token: "NSE_EQ|INE806T01012".parse().unwrap(),
                 quantity: 2,
                 product: ProductType::I,
                 transaction_type: TransactionType::Buy,
@@ -94,7 +101,7 @@ async fn main() {
 
         let charges = api_client
             .get_brokerage_details(BrokerageDetailsRequest {
-                instrument_token: "NSE_EQ|INE806T01012".to_string(),
+                instrument_token: "NSE_EQ|INE806T01012".parse().unwrap(),
                 quantity: 2,
                 product: ProductType::I,
                 transaction_type: TransactionType::Buy,
@@ -1,11 +1,14 @@
 use {
     dotenvy::dotenv,
     futures::future::join_all,
-    std::{env, io::Write},
+    std::{collections::HashSet, io::Write, path::PathBuf, sync::Arc},
     tokio::{signal, sync::MutexGuard},
     upstox_rust_sdk::{
-        client::{ApiClient, AutomateLoginConfig, LoginConfig, MailProvider, WSConnectConfig},
-        constants::UPLINK_API_KEY_ENV,
+        client::{
+            ApiClient, AutomateLoginConfig, CacheRevalidation, Credentials, InstrumentsConfig,
+            LoginConfig, LoginStrategy, MailProvider, WSConnectConfig,
+        },
+        instruments::diff::{ContractField, InstrumentsDiff},
         models::{ExchangeSegment, ws::portfolio_feed_response::PortfolioFeedResponse},
         protos::market_data_feed_v3::FeedResponse as MarketDataFeedV3Response,
     },
@@ -16,25 +19,46 @@ async fn main() {
     tracing_subscriber::fmt::init();
     let _ = dotenv();
 
-    let api_key: String = env::var(UPLINK_API_KEY_ENV).unwrap();
-    let (fetch_instruments, schedule_refresh_instruments) = (true, true);
+    let credentials: Credentials = Credentials::from_env().unwrap();
 
     // ApiClient which fetches instruments, schedules instruments refresh daily and stores it in ApiClient
     let (api_client, tasks_vec) = ApiClient::new(
-        &api_key,
+        credentials,
         LoginConfig {
             authorize: false,
+            login_strategy: LoginStrategy::Browser,
             automate_login_config: Some(AutomateLoginConfig {
                 automate_login: false,
                 schedule_login: false,
                 automate_fetching_otp: false,
                 mail_provider: Some(MailProvider::Google),
+                use_totp: false,
+                webdriver_config: None,
             }),
+            token_store: None,
+            on_unauthorized: None,
         },
         // Fetch all instruments data from UPSTOX and store it in the ApiClient.
-        fetch_instruments,
-        // Refresh instruments data daily at 6:30 AM.
-        schedule_refresh_instruments,
+        InstrumentsConfig {
+            fetch_instruments: true,
+            // Refresh instruments data daily at 6:30 AM.
+            schedule_refresh: true,
+            // Only download the NSE archive and keep its cash and index instruments.
+            segments: Some(HashSet::from([
+                ExchangeSegment::NseEq,
+                ExchangeSegment::NseIndex,
+            ])),
+            // Keep the downloaded instruments across restarts.
+            cache_dir: Some(PathBuf::from("instruments_cache")),
+            cache_revalidation: CacheRevalidation::Daily,
+            // Log lot size changes found by the daily refresh.
+            on_refresh: Some(Arc::new(|diff: &InstrumentsDiff| {
+                diff.contract_changes
+                    .iter()
+                    .filter(|change| change.field == ContractField::LotSize)
+                    .for_each(|change| println!("{:?}", change));
+            })),
+        },
         WSConnectConfig {
             connect_portfolio_stream: false,
             connect_market_data_stream_v3: false,
@@ -57,10 +81,7 @@ async fn main() {
                 .instruments
                 .as_ref()
                 .unwrap()
-                .get(&ExchangeSegment::NseIndex)
-                .unwrap()
-                .get("INDEX")
-                .unwrap()
+                .get_by_segment(ExchangeSegment::NseIndex, "INDEX")
         );
         std::io::stdout().flush().unwrap();
     }
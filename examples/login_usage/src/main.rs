@@ -1,11 +1,13 @@
 use {
     dotenvy::dotenv,
     futures::future::join_all,
-    std::env,
+    std::path::PathBuf,
     tokio::signal,
     upstox_rust_sdk::{
-        client::{ApiClient, AutomateLoginConfig, LoginConfig, MailProvider, WSConnectConfig},
-        constants::UPLINK_API_KEY_ENV,
+        client::{
+            ApiClient, AutomateLoginConfig, Browser, Credentials, InstrumentsConfig, LoginConfig,
+            LoginStrategy, MailProvider, UnauthorizedHandler, WSConnectConfig, WebDriverConfig,
+        },
         models::ws::portfolio_feed_response::PortfolioFeedResponse,
         protos::market_data_feed_v3::FeedResponse as MarketDataFeedV3Response,
     },
@@ -16,29 +18,47 @@ async fn main() {
     tracing_subscriber::fmt::init();
     let _ = dotenv();
 
-    let api_key: String = env::var(UPLINK_API_KEY_ENV).unwrap();
-    let (fetch_instruments, schedule_refresh_instruments) = (false, false);
+    let credentials: Credentials = Credentials::from_env().unwrap();
 
     // ApiClient which logs in automatically and schedules relogin daily when token expires
     let (_api_client, tasks_vec) = ApiClient::new(
-        &api_key,
+        credentials,
         LoginConfig {
             authorize: true,
+            // LoginStrategy::Notifier requests a token to be approved in the Upstox app instead, with no browser involved.
+            // The notifier webhook URL set for the app must forward to REDIRECT_PORT on this machine.
+            login_strategy: LoginStrategy::Browser,
             automate_login_config: Some(AutomateLoginConfig {
                 // geckodriver or chromedriver binary must be running locally with port specified in env to use automatic login or schedule login.
                 // ./geckodriver --binary "~/.local/share/flatpak/exports/bin/org.mozilla.firefox" --profile-root "~/.var/app/org.mozilla.firefox/cache/mozilla/firefox/cv70hco5.default-release"
 
                 // Either GOOGLE_AUTHORIZATION_CODE environment must not be set or must be recent if using for the first time or refresh_token.txt has been deleted
                 automate_login: true,
-                // Relogin is scheduled at 3:30 AM IST daily.
+                // Relogin is scheduled for when the access token expires at 3:30 AM IST.
                 schedule_login: true,
                 // Fetch OTP automatically from inbox by providing email IMAP access using env variables. For GMail, login page will be opened in your default web browsers and permission must be granted.
                 automate_fetching_otp: true,
+                // MailProvider::Outlook uses Microsoft OAuth2, while MailProvider::Imap(ImapConfig { .. }) logs in to any IMAP server with a password or app password.
                 mail_provider: Some(MailProvider::Google),
+                // Set to true and provide TOTP_SECRET env variable to enter TOTP from an authenticator app instead, which needs no mail access.
+                use_totp: false,
+                // Run the browser headless, bound each page step, retry the whole flow and save a screenshot and page source of failed attempts.
+                // Set driver_binary to have the SDK start geckodriver or chromedriver itself instead.
+                webdriver_config: Some(WebDriverConfig {
+                    browser: Browser::Firefox,
+                    headless: true,
+                    attempts: 3,
+                    diagnostics_dir: Some(PathBuf::from("login_diagnostics")),
+                    ..Default::default()
+                }),
             }),
+            // Tokens are saved in access_token.txt and refresh_token.txt in the working directory when no token store is given.
+            // Use EncryptedFileTokenStore, InMemoryTokenStore, EnvTokenStore or a custom TokenStore implementation to keep them elsewhere.
+            token_store: None,
+            // Log in again if Upstox rejects the access token before it is due to expire.
+            on_unauthorized: Some(UnauthorizedHandler::Relogin),
         },
-        fetch_instruments,
-        schedule_refresh_instruments,
+        InstrumentsConfig::default(),
         WSConnectConfig {
             connect_portfolio_stream: false,
             connect_market_data_stream_v3: false,
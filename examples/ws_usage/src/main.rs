@@ -1,11 +1,13 @@
 use {
     dotenvy::dotenv,
     futures::future::join_all,
-    std::{collections::HashSet, env},
+    std::collections::HashSet,
     tokio::signal,
     upstox_rust_sdk::{
-        client::{ApiClient, AutomateLoginConfig, LoginConfig, MailProvider, WSConnectConfig},
-        constants::UPLINK_API_KEY_ENV,
+        client::{
+            ApiClient, AutomateLoginConfig, Credentials, InstrumentsConfig, LoginConfig,
+            LoginStrategy, MailProvider, WSConnectConfig,
+        },
         models::ws::{
             market_data_feed_v3_message::{MessageDataV3, ModeTypeV3},
             portfolio_feed_request::PortfolioUpdateType,
@@ -28,23 +30,26 @@ async fn main() {
         println!("{:?}", data);
     };
 
-    let api_key: String = env::var(UPLINK_API_KEY_ENV).unwrap();
-    let (fetch_instruments, schedule_refresh_instruments) = (false, false);
+    let credentials: Credentials = Credentials::from_env().unwrap();
 
     // ApiClient with websockets connected and handler specified
     let (api_client, tasks_vec) = ApiClient::new(
-        &api_key,
+        credentials,
         LoginConfig {
             authorize: true,
+            login_strategy: LoginStrategy::Browser,
             automate_login_config: Some(AutomateLoginConfig {
                 automate_login: true,
                 schedule_login: false,
                 automate_fetching_otp: true,
                 mail_provider: Some(MailProvider::Google),
+                use_totp: false,
+                webdriver_config: None,
             }),
+            token_store: None,
+            on_unauthorized: None,
         },
-        fetch_instruments,
-        schedule_refresh_instruments,
+        InstrumentsConfig::default(),
         // Configuration to connect and handle websocket data.
         WSConnectConfig {
             connect_portfolio_stream: true,
@@ -69,9 +74,9 @@ async fn main() {
         .send_market_data_feed_v3_message(MarketDataV3Call::SubscribeInstrument(MessageDataV3 {
             mode: ModeTypeV3::Full,
             instrument_keys: vec![
-                "NSE_INDEX|NIFTY LARGEMID250".to_string(),
-                "NSE_INDEX|Nifty Auto".to_string(),
-                "NSE_INDEX|Nifty Midcap 50".to_string(),
+                "NSE_INDEX|NIFTY LARGEMID250".parse().unwrap(),
+                "NSE_INDEX|Nifty Auto".parse().unwrap(),
+                "NSE_INDEX|Nifty Midcap 50".parse().unwrap(),
             ],
         }))
         .await
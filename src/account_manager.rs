@@ -0,0 +1,81 @@
+use {
+    crate::{
+        client::{ApiClient, Credentials, InstrumentsConfig, LoginConfig, WSConnectConfig},
+        token_store::{FileTokenStore, ScopedTokenStore, TokenStore},
+    },
+    std::{collections::HashMap, sync::Arc},
+    tokio::{sync::Mutex, task::JoinHandle},
+};
+
+// Holds an ApiClient for each of several Upstox accounts. Every client logs in, schedules relogin and rate limits on its own,
+// while logins across clients run one at a time as they share the redirect port and WebDriver.
+#[derive(Default)]
+pub struct AccountManager {
+    accounts: HashMap<String, Arc<Mutex<ApiClient>>>,
+    tasks: HashMap<String, Vec<JoinHandle<()>>>,
+}
+
+impl AccountManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Accounts are identified by Credentials.user_id, or by the API key when it is not provided.
+    pub async fn add_account(
+        &mut self,
+        credentials: Credentials,
+        mut login_config: LoginConfig,
+        ws_connect_config: WSConnectConfig,
+    ) -> Result<Arc<Mutex<ApiClient>>, String> {
+        let account_id: String = credentials.account_id();
+        if self.accounts.contains_key(&account_id) {
+            return Err(format!("Account {} has already been added", account_id));
+        }
+        // ApiClient only scopes tokens by user id, accounts without one are kept apart by their API key
+        if credentials.user_id.is_none() {
+            let token_store: Arc<dyn TokenStore> = login_config
+                .token_store
+                .take()
+                .unwrap_or_else(|| Arc::new(FileTokenStore::default()));
+            login_config.token_store =
+                Some(Arc::new(ScopedTokenStore::new(token_store, &account_id)));
+        }
+
+        let (api_client, tasks_vec) = ApiClient::new(
+            credentials,
+            login_config,
+            InstrumentsConfig::default(),
+            ws_connect_config,
+        )
+        .await?;
+        self.accounts
+            .insert(account_id.clone(), Arc::clone(&api_client));
+        self.tasks.insert(account_id, tasks_vec);
+        Ok(api_client)
+    }
+
+    pub fn account(&self, account_id: &str) -> Option<Arc<Mutex<ApiClient>>> {
+        self.accounts.get(account_id).cloned()
+    }
+
+    pub fn account_ids(&self) -> Vec<String> {
+        self.accounts.keys().cloned().collect()
+    }
+
+    pub fn accounts(&self) -> impl Iterator<Item = (&String, &Arc<Mutex<ApiClient>>)> {
+        self.accounts.iter()
+    }
+
+    // Stops the websocket tasks of the account and returns its client.
+    pub fn remove_account(&mut self, account_id: &str) -> Option<Arc<Mutex<ApiClient>>> {
+        if let Some(tasks_vec) = self.tasks.remove(account_id) {
+            tasks_vec.iter().for_each(JoinHandle::abort);
+        }
+        self.accounts.remove(account_id)
+    }
+
+    // Websocket tasks of all accounts, to be awaited like the ones returned by ApiClient::new.
+    pub fn take_tasks(&mut self) -> Vec<JoinHandle<()>> {
+        self.tasks.values_mut().flat_map(std::mem::take).collect()
+    }
+}
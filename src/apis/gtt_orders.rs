@@ -22,6 +22,7 @@ use {
         utils::ToKeyValueTuples,
     },
     serde_valid::Validate,
+    std::time::Instant,
 };
 
 impl ApiClient {
@@ -29,7 +30,20 @@ impl ApiClient {
         &self,
         place_gtt_order_body: PlaceGTTOrderRequest,
     ) -> Result<Result<SuccessResponse<GTTOrdersResponse>, ErrorResponse>, RateLimitExceeded> {
+        let started_at: Instant = Instant::now();
         place_gtt_order_body.validate().unwrap();
+        if let Err(risk_violation) = self.check_place_gtt_order_risk(&place_gtt_order_body).await {
+            let result: Result<SuccessResponse<GTTOrdersResponse>, ErrorResponse> =
+                Err(risk_violation.into());
+            self.audit_call(
+                "place_gtt_order",
+                &place_gtt_order_body,
+                None,
+                started_at,
+                &result,
+            );
+            return Ok(result);
+        }
         let res: reqwest::Response = self
             .post(
                 GTT_ORDERS_PLACE_GTT_ORDER_ENDPOINT,
@@ -39,21 +53,39 @@ impl ApiClient {
                 BaseUrlType::REGULAR,
                 APIVersion::V3,
             )
-            .await?;
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "place_gtt_order",
+                    &place_gtt_order_body,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
 
-        Ok(match res.status().as_u16() {
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<GTTOrdersResponse>, ErrorResponse> = match status {
             200 => Ok(res
                 .json::<SuccessResponse<GTTOrdersResponse>>()
                 .await
                 .unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "place_gtt_order",
+            &place_gtt_order_body,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     pub async fn modify_gtt_order(
         &self,
         modify_gtt_order_body: ModifyGTTOrderRequest,
     ) -> Result<Result<SuccessResponse<GTTOrdersResponse>, ErrorResponse>, RateLimitExceeded> {
+        let started_at: Instant = Instant::now();
         modify_gtt_order_body.validate().unwrap();
         let res: reqwest::Response = self
             .put(
@@ -64,21 +96,39 @@ impl ApiClient {
                 BaseUrlType::REGULAR,
                 APIVersion::V3,
             )
-            .await?;
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "modify_gtt_order",
+                    &modify_gtt_order_body,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
 
-        Ok(match res.status().as_u16() {
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<GTTOrdersResponse>, ErrorResponse> = match status {
             200 => Ok(res
                 .json::<SuccessResponse<GTTOrdersResponse>>()
                 .await
                 .unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "modify_gtt_order",
+            &modify_gtt_order_body,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     pub async fn cancel_gtt_order(
         &self,
         gtt_order_id: String,
     ) -> Result<Result<SuccessResponse<GTTOrdersResponse>, ErrorResponse>, RateLimitExceeded> {
+        let started_at: Instant = Instant::now();
         let cancel_order_params: CancelGTTOrderRequest = CancelGTTOrderRequest { gtt_order_id };
         cancel_order_params.validate().unwrap();
 
@@ -91,15 +141,32 @@ impl ApiClient {
                 BaseUrlType::REGULAR,
                 APIVersion::V3,
             )
-            .await?;
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "cancel_gtt_order",
+                    &cancel_order_params,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
 
-        Ok(match res.status().as_u16() {
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<GTTOrdersResponse>, ErrorResponse> = match status {
             200 => Ok(res
                 .json::<SuccessResponse<GTTOrdersResponse>>()
                 .await
                 .unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "cancel_gtt_order",
+            &cancel_order_params,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     pub async fn get_gtt_order_details(
@@ -1,133 +1,447 @@
 use {
     crate::{
-        client::ApiClient,
+        client::{ApiClient, CacheRevalidation, InstrumentsConfig},
         constants::{
-            INSTRUMENTS_ARCHIVE_FILENAME, INSTRUMENTS_COMPLETE_URL, INSTRUMENTS_JSON_FILENAME,
+            INSTRUMENTS_BASE_URL, INSTRUMENTS_BSE_FILENAME, INSTRUMENTS_CACHE_METADATA_EXTENSION,
+            INSTRUMENTS_COMPLETE_FILENAME, INSTRUMENTS_MCX_FILENAME, INSTRUMENTS_NSE_FILENAME,
+            INSTRUMENTS_REFRESH_HOUR, INSTRUMENTS_REFRESH_MINUTE,
         },
-        models::{instruments::instruments_response::InstrumentsResponse, ExchangeSegment},
+        instruments::{diff::InstrumentsDiff, store::InstrumentStore},
+        models::{ExchangeSegment, instruments::instruments_response::InstrumentsResponse},
+        utils::latest_ist_time,
     },
+    chrono::{DateTime, Utc},
     flate2::read::GzDecoder,
-    reqwest::{Client, Response},
+    futures::future::try_join_all,
+    reqwest::{
+        RequestBuilder, Response, StatusCode,
+        header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    },
+    serde::{
+        Deserialize, Deserializer, Serialize,
+        de::{SeqAccess, Visitor},
+    },
     std::{
-        collections::HashMap,
+        collections::HashSet,
+        fmt,
         fs::File,
-        io::{copy, Read},
+        io::{self, BufReader, Cursor, Read},
+        marker::PhantomData,
+        path::{Path, PathBuf},
+        time::SystemTime,
     },
-    tokio::fs,
-    tracing::info,
+    tokio::{fs, io::AsyncWriteExt, sync::mpsc},
+    tracing::{info, warn},
 };
 
+// Chunks of a downloaded archive buffered between the download and its decompression
+const ARCHIVE_CHUNK_BUFFER: usize = 16;
+
+// Validators of the cached archive, sent back to revalidate it
+#[derive(Deserialize, Serialize, Default)]
+struct CacheMetadata {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+enum Archive {
+    Cached(PathBuf),
+    Downloaded(Response),
+}
+
+// Blocking reader over the chunks of a response body, which are sent by an async task as they are downloaded
+struct ChunkReader {
+    receiver: mpsc::Receiver<Result<Vec<u8>, String>>,
+    chunk: Cursor<Vec<u8>>,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let bytes_read: usize = self.chunk.read(buffer)?;
+            if bytes_read > 0 || buffer.is_empty() {
+                return Ok(bytes_read);
+            }
+            match self.receiver.blocking_recv() {
+                Some(Ok(chunk)) => self.chunk = Cursor::new(chunk),
+                Some(Err(err)) => return Err(io::Error::other(err)),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+// Collects the instruments of a JSON array as each one is parsed, leaving out those not in segments
+struct InstrumentsVisitor<T> {
+    segments: Option<HashSet<ExchangeSegment>>,
+    collection: PhantomData<T>,
+}
+
+impl<'de, T> Visitor<'de> for InstrumentsVisitor<T>
+where
+    T: Default + Extend<InstrumentsResponse>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of instruments")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut collection: T = T::default();
+        let mut error: Option<A::Error> = None;
+        collection.extend(
+            std::iter::from_fn(|| match seq.next_element::<InstrumentsResponse>() {
+                Ok(instrument) => instrument,
+                Err(err) => {
+                    error = Some(err);
+                    None
+                }
+            })
+            .filter(|instrument| {
+                self.segments
+                    .as_ref()
+                    .is_none_or(|segments| segments.contains(&instrument.segment()))
+            }),
+        );
+        match error {
+            Some(err) => Err(err),
+            None => Ok(collection),
+        }
+    }
+}
+
+// Instrument archives published by Upstox, one for each exchange along with one having all of them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum InstrumentsFile {
+    Complete,
+    Nse,
+    Bse,
+    Mcx,
+}
+
+impl InstrumentsFile {
+    fn for_segment(segment: ExchangeSegment) -> Self {
+        match segment {
+            ExchangeSegment::NseEq
+            | ExchangeSegment::NseIndex
+            | ExchangeSegment::NseFo
+            | ExchangeSegment::NseCom
+            | ExchangeSegment::NcdFo => InstrumentsFile::Nse,
+            ExchangeSegment::BseEq
+            | ExchangeSegment::BseIndex
+            | ExchangeSegment::BseFo
+            | ExchangeSegment::BcdFo => InstrumentsFile::Bse,
+            ExchangeSegment::McxFo | ExchangeSegment::McxIndex => InstrumentsFile::Mcx,
+        }
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            InstrumentsFile::Complete => INSTRUMENTS_COMPLETE_FILENAME,
+            InstrumentsFile::Nse => INSTRUMENTS_NSE_FILENAME,
+            InstrumentsFile::Bse => INSTRUMENTS_BSE_FILENAME,
+            InstrumentsFile::Mcx => INSTRUMENTS_MCX_FILENAME,
+        }
+    }
+}
+
 impl ApiClient {
-    pub async fn get_instruments(&self) -> Result<Vec<InstrumentsResponse>, String> {
-        let client: &Client = &self.client;
-        let archive_path: &str = INSTRUMENTS_ARCHIVE_FILENAME;
-        let json_path: &str = INSTRUMENTS_JSON_FILENAME;
-        let url: &str = INSTRUMENTS_COMPLETE_URL;
-
-        if File::open(json_path).is_ok() {
-            let mut json_file: File =
-                File::open(json_path).map_err(|_| "Failed to open JSON file".to_string())?;
-            let mut json_content: String = String::new();
-            json_file
-                .read_to_string(&mut json_content)
-                .map_err(|_| "Failed to read JSON file")?;
-            let instruments_data: Vec<InstrumentsResponse> = serde_json::from_str(&json_content)
-                .map_err(|_| "Failed to parse Instruments JSON".to_string())?;
-            return Ok(instruments_data);
+    // Fetches instruments of the given segments, or of all segments when not provided. Only the archives of the exchanges
+    // having these segments are downloaded.
+    pub async fn get_instruments(
+        &self,
+        segments: Option<&HashSet<ExchangeSegment>>,
+    ) -> Result<Vec<InstrumentsResponse>, String> {
+        let instruments_vecs: Vec<Vec<InstrumentsResponse>> =
+            self.get_instruments_files(segments).await?;
+        Ok(instruments_vecs.into_iter().flatten().collect())
+    }
+
+    // Like get_instruments, but indexes the instruments into the store as they are parsed
+    pub async fn get_instrument_store(
+        &self,
+        segments: Option<&HashSet<ExchangeSegment>>,
+    ) -> Result<InstrumentStore, String> {
+        let mut stores: std::vec::IntoIter<InstrumentStore> =
+            self.get_instruments_files(segments).await?.into_iter();
+        let mut store: InstrumentStore = stores.next().unwrap_or_default();
+        for other_store in stores {
+            store.extend(other_store);
         }
+        Ok(store)
+    }
 
-        let archive_file: File = match File::open(archive_path) {
-            Ok(file) => Ok(file),
-            Err(_) => {
-                let user_agent: &str =
-                    "Mozilla/5.0 (X11; Linux x86_64; rv:136.0) Gecko/20100101 Firefox/136.0";
-                let accept_header: &str =
-                    "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
-                let accept_encoding_header: &str = "gzip, deflate, br, zstd";
-
-                let response: Response = client
-                    .get(url)
-                    .header("User-Agent", user_agent)
-                    .header("Accept", accept_header)
-                    .header("Accept-Encoding", accept_encoding_header)
-                    .send()
-                    .await
-                    .map_err(|_| "Failed to fetch instruments".to_string())?;
-                let bytes = response
-                    .bytes()
+    // Fetches instruments again and replaces the loaded ones, returning what changed
+    pub async fn refresh_instruments(&mut self) -> Result<InstrumentsDiff, String> {
+        let new_store: InstrumentStore = self
+            .get_instrument_store(self.instruments_config.segments.as_ref())
+            .await?;
+        let diff: InstrumentsDiff = match &self.instruments {
+            Some(old_store) => InstrumentsDiff::new(old_store, &new_store),
+            None => InstrumentsDiff::new(&InstrumentStore::default(), &new_store),
+        };
+        info!(
+            "Instruments refreshed: {} listed, {} delisted, {} expired, {} contract changes, {} renames",
+            diff.listed.len(),
+            diff.delisted.len(),
+            diff.expired.len(),
+            diff.contract_changes.len(),
+            diff.renames.len()
+        );
+        self.instruments = Some(new_store);
+        Ok(diff)
+    }
+
+    async fn get_instruments_files<T>(
+        &self,
+        segments: Option<&HashSet<ExchangeSegment>>,
+    ) -> Result<Vec<T>, String>
+    where
+        T: Default + Extend<InstrumentsResponse> + Send + 'static,
+    {
+        let files: HashSet<InstrumentsFile> = match segments {
+            Some(segments) => segments
+                .iter()
+                .map(|&segment| InstrumentsFile::for_segment(segment))
+                .collect(),
+            None => HashSet::from([InstrumentsFile::Complete]),
+        };
+        try_join_all(
+            files
+                .into_iter()
+                .map(|file| self.get_instruments_file(file, segments.cloned())),
+        )
+        .await
+    }
+
+    async fn get_instruments_file<T>(
+        &self,
+        file: InstrumentsFile,
+        segments: Option<HashSet<ExchangeSegment>>,
+    ) -> Result<T, String>
+    where
+        T: Default + Extend<InstrumentsResponse> + Send + 'static,
+    {
+        let config: &InstrumentsConfig = &self.instruments_config;
+        let archive: Archive = match &config.cache_dir {
+            Some(cache_dir) => {
+                match self
+                    .fetch_cached_archive(cache_dir, file, config.cache_revalidation)
                     .await
-                    .map_err(|_| "Failed to read response bytes".to_string())?;
-                fs::write(archive_path, &bytes)
+                {
+                    Ok(archive_path) => Archive::Cached(archive_path),
+                    Err(err) => {
+                        let archive_path: PathBuf = cache_dir.join(file.file_name());
+                        if !archive_path.exists() {
+                            return Err(err);
+                        }
+                        warn!("{}, using cached instruments archive", err);
+                        Archive::Cached(archive_path)
+                    }
+                }
+            }
+            None => Archive::Downloaded(self.request_archive(file, None).await?),
+        };
+
+        // Decompression and parsing are CPU bound and run on a blocking thread. The archive is decompressed and parsed as
+        // it is read, or downloaded, without buffering it or the JSON whole.
+        match archive {
+            Archive::Cached(archive_path) => tokio::task::spawn_blocking(move || {
+                let archive_file: File = File::open(&archive_path)
+                    .map_err(|_| "Failed to open instruments archive".to_string())?;
+                Self::parse_archive(BufReader::new(archive_file), segments)
+            })
+            .await
+            .map_err(|_| "Failed to parse instruments archive".to_string())?,
+            Archive::Downloaded(mut response) => {
+                let (sender, receiver) =
+                    mpsc::channel::<Result<Vec<u8>, String>>(ARCHIVE_CHUNK_BUFFER);
+                let parse = tokio::task::spawn_blocking(move || {
+                    Self::parse_archive(
+                        ChunkReader {
+                            receiver,
+                            chunk: Cursor::new(Vec::new()),
+                        },
+                        segments,
+                    )
+                });
+                // Stops early when parsing failed and the receiver was dropped
+                loop {
+                    let chunk: Result<Vec<u8>, String> = match response.chunk().await {
+                        Ok(Some(chunk)) => Ok(chunk.to_vec()),
+                        Ok(None) => break,
+                        Err(_) => Err("Failed to read instruments archive".to_string()),
+                    };
+                    let failed: bool = chunk.is_err();
+                    if sender.send(chunk).await.is_err() || failed {
+                        break;
+                    }
+                }
+                drop(sender);
+                parse
                     .await
-                    .map_err(|_| "Failed to write archive".to_string())?;
-                File::open(archive_path).map_err(|_| "Failed to open archive".to_string())
+                    .map_err(|_| "Failed to parse instruments archive".to_string())?
+            }
+        }
+    }
+
+    fn parse_archive<T>(
+        reader: impl Read,
+        segments: Option<HashSet<ExchangeSegment>>,
+    ) -> Result<T, String>
+    where
+        T: Default + Extend<InstrumentsResponse>,
+    {
+        let mut deserializer =
+            serde_json::Deserializer::from_reader(BufReader::new(GzDecoder::new(reader)));
+        let instruments: T = deserializer
+            .deserialize_seq(InstrumentsVisitor {
+                segments,
+                collection: PhantomData,
+            })
+            .and_then(|instruments| deserializer.end().map(|_| instruments))
+            .map_err(|_| "Failed to parse Instruments JSON".to_string())?;
+        Ok(instruments)
+    }
+
+    // Returns the path of an archive in the cache directory which is up to date, downloading it when needed
+    async fn fetch_cached_archive(
+        &self,
+        cache_dir: &Path,
+        file: InstrumentsFile,
+        cache_revalidation: CacheRevalidation,
+    ) -> Result<PathBuf, String> {
+        let archive_path: PathBuf = cache_dir.join(file.file_name());
+        let metadata_path: PathBuf = cache_dir.join(format!(
+            "{}.{}",
+            file.file_name(),
+            INSTRUMENTS_CACHE_METADATA_EXTENSION
+        ));
+
+        let cached_at: Option<DateTime<Utc>> = fs::metadata(&archive_path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(DateTime::<Utc>::from);
+        let metadata: Option<CacheMetadata> = match cached_at {
+            Some(_) => fs::read_to_string(&metadata_path)
+                .await
+                .ok()
+                .and_then(|metadata| serde_json::from_str(&metadata).ok()),
+            None => None,
+        };
+
+        if let Some(cached_at) = cached_at
+            && cache_revalidation == CacheRevalidation::Daily
+            && cached_at
+                >= latest_ist_time(
+                    Utc::now(),
+                    INSTRUMENTS_REFRESH_HOUR,
+                    INSTRUMENTS_REFRESH_MINUTE,
+                )
+        {
+            info!("Using {} cached at {}", file.file_name(), cached_at);
+            return Ok(archive_path);
+        }
+
+        let mut response: Response = self.request_archive(file, metadata.as_ref()).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            info!("Cached {} is up to date", file.file_name());
+            // Touch the archive so that daily revalidation counts from now
+            if let Ok(archive_file) = File::options().append(true).open(&archive_path) {
+                let _ = archive_file.set_modified(SystemTime::now());
             }
-        }?;
-        info!("Instruments archive downloaded");
+            return Ok(archive_path);
+        }
 
-        let mut archive: GzDecoder<File> = GzDecoder::new(archive_file);
-        let mut output_file: File =
-            File::create(json_path).map_err(|_| "Failed to create JSON file".to_string())?;
-        copy(&mut archive, &mut output_file)
-            .map_err(|_| "Failed to extract archive".to_string())?;
+        fs::create_dir_all(cache_dir)
+            .await
+            .map_err(|_| "Failed to create instruments cache directory".to_string())?;
+        let new_metadata: CacheMetadata = CacheMetadata {
+            etag: Self::header_value(&response, ETAG.as_str()),
+            last_modified: Self::header_value(&response, LAST_MODIFIED.as_str()),
+        };
 
-        fs::remove_file(archive_path)
+        // Written next to the archive and renamed over it so that an interrupted download leaves the cache intact
+        let partial_path: PathBuf = archive_path.with_extension("gz.partial");
+        let mut partial_file: fs::File = fs::File::create(&partial_path)
+            .await
+            .map_err(|_| "Failed to create instruments archive".to_string())?;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|_| "Failed to read instruments archive".to_string())?
+        {
+            partial_file
+                .write_all(&chunk)
+                .await
+                .map_err(|_| "Failed to write instruments archive".to_string())?;
+        }
+        partial_file
+            .flush()
             .await
-            .expect("Failed to delete archive");
-
-        let mut json_file: File =
-            File::open(json_path).map_err(|_| "Failed to open JSON file".to_string())?;
-        let mut json_content: String = String::new();
-        json_file
-            .read_to_string(&mut json_content)
-            .map_err(|_| "Failed to read JSON file")?;
-        fs::remove_file(json_path)
+            .map_err(|_| "Failed to write instruments archive".to_string())?;
+        fs::rename(&partial_path, &archive_path)
             .await
-            .map_err(|_| "Failed to delete JSON file")?;
+            .map_err(|_| "Failed to write instruments archive".to_string())?;
+        fs::write(
+            &metadata_path,
+            serde_json::to_string(&new_metadata).unwrap_or_default(),
+        )
+        .await
+        .map_err(|_| "Failed to write instruments cache metadata".to_string())?;
+        info!("{} downloaded", file.file_name());
 
-        let instruments_data: Vec<InstrumentsResponse> = serde_json::from_str(&json_content)
-            .map_err(|_| "Failed to parse Instruments JSON".to_string())?;
-        Ok(instruments_data)
+        Ok(archive_path)
     }
 
-    pub fn parse_instruments(
-        instruments: Vec<InstrumentsResponse>,
-    ) -> HashMap<ExchangeSegment, HashMap<String, Vec<InstrumentsResponse>>> {
-        let mut map: HashMap<ExchangeSegment, HashMap<String, Vec<InstrumentsResponse>>> =
-            HashMap::new();
-
-        for instrument in instruments {
-            let (segment, instrument_type) = match &instrument {
-                InstrumentsResponse::EquityResponse {
-                    segment,
-                    instrument_type,
-                    ..
-                } => (segment.clone(), instrument_type.clone()),
-                InstrumentsResponse::DerivativeResponse {
-                    segment,
-                    instrument_type,
-                    ..
-                } => (segment.clone(), instrument_type.clone()),
-                InstrumentsResponse::IndexResponse {
-                    segment,
-                    instrument_type,
-                    ..
-                } => (segment.clone(), instrument_type.clone()),
-                InstrumentsResponse::CommodityResponse {
-                    segment,
-                    instrument_type,
-                    ..
-                } => (segment.clone(), instrument_type.clone()),
-            };
-
-            let segment_map: &mut HashMap<String, Vec<InstrumentsResponse>> =
-                map.entry(segment).or_insert_with(HashMap::new);
-            segment_map
-                .entry(instrument_type)
-                .or_insert_with(Vec::new)
-                .push(instrument);
+    async fn request_archive(
+        &self,
+        file: InstrumentsFile,
+        metadata: Option<&CacheMetadata>,
+    ) -> Result<Response, String> {
+        let user_agent: &str =
+            "Mozilla/5.0 (X11; Linux x86_64; rv:136.0) Gecko/20100101 Firefox/136.0";
+        let accept_header: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+        let accept_encoding_header: &str = "gzip, deflate, br, zstd";
+
+        let mut request: RequestBuilder = self
+            .client
+            .get(format!("{}{}", INSTRUMENTS_BASE_URL, file.file_name()))
+            .header("User-Agent", user_agent)
+            .header("Accept", accept_header)
+            .header("Accept-Encoding", accept_encoding_header);
+        if let Some(metadata) = metadata {
+            if let Some(etag) = &metadata.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &metadata.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response: Response = request
+            .send()
+            .await
+            .map_err(|_| "Failed to fetch instruments".to_string())?;
+        match response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
+            true => Ok(response),
+            false => Err(format!(
+                "Failed to fetch instruments: {}",
+                response.status()
+            )),
         }
+    }
 
-        map
+    fn header_value(response: &Response, name: &str) -> Option<String> {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
     }
 }
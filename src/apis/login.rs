@@ -1,17 +1,28 @@
 use {
     crate::{
-        client::{ApiClient, AutomateLoginConfig, LoginConfig, MailProvider},
+        client::{
+            ApiClient, AutomateLoginConfig, Credentials, ImapConfig, ImapTlsMode, LoginConfig,
+            LoginStrategy, MailProvider, WebDriverConfig,
+        },
         constants::{
-            APIVersion, BaseUrlType, EMAIL_ID_ENV, GOOGLE_AUTHORIZATION_CODE_ENV,
-            GOOGLE_CLIENT_ID_ENV, GOOGLE_CLIENT_SECRET_ENV, GOOGLE_IMAP_URL,
-            GOOGLE_OAUTH2_ACCESS_TOKEN_URL, GOOGLE_OAUTH2_AUTH_URL, GOOGLE_REFRESH_TOKEN_FILENAME,
-            LOGIN_AUTHORIZE_ENDPOINT, LOGIN_GET_TOKEN_ENDPOINT, LOGIN_PIN_ENV, LOGOUT_ENDPOINT,
-            MOBILE_NUMBER_ENV, REDIRECT_PORT_ENV, UPLINK_API_KEY_ENV, UPLINK_API_SECRET_ENV,
-            UPSTOX_ACCESS_TOKEN_FILENAME, WEBDRIVER_SOCKET_ENV,
+            APIVersion, BaseUrlType, GOOGLE_AUTHORIZATION_CODE_ENV, GOOGLE_CLIENT_ID_ENV,
+            GOOGLE_CLIENT_SECRET_ENV, GOOGLE_IMAP_URL, GOOGLE_OAUTH2_ACCESS_TOKEN_URL,
+            GOOGLE_OAUTH2_AUTH_URL, GOOGLE_REFRESH_TOKEN_KEY, IMAP_TLS_PORT,
+            LOGIN_ACCESS_TOKEN_REQUEST_ENDPOINT, LOGIN_AUTHORIZE_ENDPOINT,
+            LOGIN_GET_TOKEN_ENDPOINT, LOGOUT_ENDPOINT, MICROSOFT_AUTHORIZATION_CODE_ENV,
+            MICROSOFT_CLIENT_ID_ENV, MICROSOFT_CLIENT_SECRET_ENV,
+            MICROSOFT_OAUTH2_ACCESS_TOKEN_URL, MICROSOFT_OAUTH2_AUTH_URL,
+            MICROSOFT_OAUTH2_IMAP_SCOPE, MICROSOFT_REFRESH_TOKEN_KEY, OUTLOOK_IMAP_URL,
+            REDIRECT_PORT_ENV, REDIRECT_TIMEOUT_SECONDS, UPSTOX_ACCESS_TOKEN_ISSUED_AT_KEY,
+            UPSTOX_ACCESS_TOKEN_KEY, WEBDRIVER_SOCKET_ENV,
         },
         models::{
             error_response::ErrorResponse,
             login::{
+                access_token_request::AccessTokenRequest,
+                access_token_request_response::{
+                    AccessTokenNotification, AccessTokenRequestResponse, MessageType,
+                },
                 dialog_request::{DialogRequest, ResponseType},
                 google_oauth2_request::{
                     self, AccessType, GoogleOAuth2AuthRequest, GoogleOAuth2CodeTokenRequest,
@@ -20,39 +31,50 @@ use {
                 google_oauth2_response::{
                     GoogleOAuth2TokenErrorResponse, GoogleOAuth2TokenResponse,
                 },
+                microsoft_oauth2_request::{
+                    self, MicrosoftOAuth2AuthRequest, MicrosoftOAuth2CodeTokenRequest,
+                    MicrosoftOAuth2RefreshTokenRequest, ResponseMode,
+                },
+                microsoft_oauth2_response::{
+                    MicrosoftOAuth2TokenErrorResponse, MicrosoftOAuth2TokenResponse,
+                },
                 token_request::{self, TokenRequest},
                 token_response::TokenResponse,
             },
             success_response::SuccessResponse,
         },
-        utils::{create_url, read_value_from_file, write_value_to_file, ToKeyValueTuples},
+        redirect_listener::{RedirectError, RedirectListener, generate_state},
+        utils::{ToKeyValueTuples, create_url, ist_offset, write_value_to_file},
     },
     async_imap::{
-        self,
+        self, Authenticator, Client as ImapClient, Session,
         types::{Fetch, Mailbox},
-        Authenticator, Client as ImapClient, Session,
     },
     async_native_tls::{TlsConnector, TlsStream},
     chrono::{DateTime, Utc},
-    fantoccini::{elements::Element, Client as FantocciniClient, ClientBuilder, Locator},
+    fantoccini::{Client as FantocciniClient, ClientBuilder, Locator, elements::Element},
     futures::TryStreamExt,
-    mailparse::{parse_mail, ParsedMail},
+    mailparse::{ParsedMail, parse_mail},
     regex::Regex,
     reqwest::Url,
     scraper::{ElementRef, Html, Selector},
-    std::{self, borrow::Cow, env, fs::remove_file, net::SocketAddr, sync::Arc},
+    std::{self, env, fmt::Debug, fs, path::PathBuf, process::Stdio, sync::Arc},
     tokio::{
         self,
-        io::{AsyncReadExt, AsyncWriteExt},
-        net::{TcpListener, TcpStream},
+        io::{AsyncRead, AsyncWrite},
+        net::TcpStream,
+        process::{Child, Command},
         sync::{Mutex, MutexGuard},
-        time::{sleep, Duration},
+        time::{Duration, Instant, sleep, timeout},
     },
+    totp_rs::{Algorithm, Secret, TOTP},
     tracing::{debug, info, warn},
     url_open::UrlOpen,
-    urlencoding::decode,
 };
 
+// Logins of all clients in the process share the redirect port and WebDriver, so only one runs at a time
+static LOGIN_LOCK: Mutex<()> = Mutex::const_new(());
+
 #[derive(Debug)]
 struct OAuth2 {
     user: String,
@@ -71,13 +93,28 @@ impl Authenticator for &OAuth2 {
 
 impl ApiClient {
     pub(crate) async fn login(&mut self, login_config: &LoginConfig) -> Result<(), String> {
-        if let Ok(access_token) = read_value_from_file(UPSTOX_ACCESS_TOKEN_FILENAME) {
+        if let Some(access_token) = self.token_store.load(UPSTOX_ACCESS_TOKEN_KEY).await? {
             self.token = Some(access_token);
+            self.token_issued_at = self
+                .token_store
+                .load(UPSTOX_ACCESS_TOKEN_ISSUED_AT_KEY)
+                .await?
+                .and_then(|issued_at| DateTime::parse_from_rfc3339(&issued_at).ok())
+                .map(|issued_at| issued_at.with_timezone(&Utc));
             if self.verify_authorization().await {
                 return Ok(());
             }
+            self.token_store.invalidate(UPSTOX_ACCESS_TOKEN_KEY).await?;
+            self.token_store
+                .invalidate(UPSTOX_ACCESS_TOKEN_ISSUED_AT_KEY)
+                .await?;
         };
 
+        let _login_guard: MutexGuard<()> = LOGIN_LOCK.lock().await;
+        if let LoginStrategy::Notifier = login_config.login_strategy {
+            return self.login_with_notifier().await;
+        }
+
         if login_config.automate_login_config.is_none() {
             return Err("Must provide automate_login_config for authorization.".to_string());
         }
@@ -89,25 +126,142 @@ impl ApiClient {
 
         match self.get_token(auth_code.to_string()).await {
             Ok(token_response) => {
-                self.token = Some(token_response.access_token);
-                write_value_to_file(UPSTOX_ACCESS_TOKEN_FILENAME, &self.token.as_ref().unwrap())
-                    .unwrap();
-                Ok(())
+                self.store_access_token(token_response.access_token, Utc::now())
+                    .await
             }
             Err(error_response) => Err(error_response.errors[0].message.clone()),
         }
     }
 
+    async fn store_access_token(
+        &mut self,
+        access_token: String,
+        issued_at: DateTime<Utc>,
+    ) -> Result<(), String> {
+        self.token_store
+            .save(UPSTOX_ACCESS_TOKEN_KEY, &access_token)
+            .await?;
+        self.token_store
+            .save(UPSTOX_ACCESS_TOKEN_ISSUED_AT_KEY, &issued_at.to_rfc3339())
+            .await?;
+        self.token = Some(access_token);
+        self.token_issued_at = Some(issued_at);
+        info!(
+            "Logged in, access token expires at {}",
+            self.token_expires_at().unwrap()
+        );
+        Ok(())
+    }
+
+    // Requests an access token which the user approves in the Upstox app. Upstox then posts the token to the notifier webhook
+    // registered for the app, which must forward to REDIRECT_PORT on this machine.
+    async fn login_with_notifier(&mut self) -> Result<(), String> {
+        let client_secret: String =
+            Credentials::require(&self.credentials.api_secret, "api_secret")?;
+        // Bind before requesting so that the notification can not arrive before the listener is up
+        let listener: RedirectListener = self.bind_redirect_listener().await?;
+
+        let res: reqwest::Response = self
+            .post(
+                format!(
+                    "{}/{}",
+                    LOGIN_ACCESS_TOKEN_REQUEST_ENDPOINT, self.credentials.api_key
+                )
+                .as_str(),
+                false,
+                Some(&AccessTokenRequest { client_secret }),
+                None,
+                BaseUrlType::REGULAR,
+                APIVersion::V3,
+            )
+            .await
+            .map_err(|err| {
+                format!(
+                    "Rate limit exceeded while requesting access token: {:?}",
+                    err
+                )
+            })?;
+
+        let request_response: AccessTokenRequestResponse = match res.status().as_u16() {
+            200 => {
+                res.json::<SuccessResponse<AccessTokenRequestResponse>>()
+                    .await
+                    .unwrap()
+                    .data
+            }
+            _ => {
+                let error_response: ErrorResponse = res.json::<ErrorResponse>().await.unwrap();
+                return Err(error_response.errors[0].message.clone());
+            }
+        };
+
+        let authorization_expiry: DateTime<Utc> = request_response
+            .authorization_expiry
+            .parse::<i64>()
+            .ok()
+            .and_then(DateTime::from_timestamp_millis)
+            .ok_or("Invalid authorization expiry in access token request response".to_string())?;
+        info!(
+            "Access token requested, approve it in the Upstox app before {}",
+            authorization_expiry.with_timezone(&ist_offset())
+        );
+
+        let wait_duration: Duration = (authorization_expiry - Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO);
+        let notification: AccessTokenNotification = timeout(wait_duration, async {
+            loop {
+                let (mut socket, request) = match listener.next_request().await {
+                    Ok(accepted) => accepted,
+                    Err(RedirectError::MalformedRequest(reason)) => {
+                        debug!(
+                            "Ignoring malformed request on notifier listener: {}",
+                            reason
+                        );
+                        continue;
+                    }
+                    Err(err) => return Err(err.to_string()),
+                };
+                if request.method != "POST" {
+                    RedirectListener::respond(&mut socket, "404 Not Found", "").await;
+                    continue;
+                }
+                RedirectListener::respond(&mut socket, "200 OK", "").await;
+                match serde_json::from_slice::<AccessTokenNotification>(&request.body) {
+                    Ok(notification)
+                        if notification.client_id == self.credentials.api_key
+                            && notification.message_type == MessageType::AccessToken =>
+                    {
+                        return Ok(notification);
+                    }
+                    _ => debug!("Ignoring request received on notifier listener"),
+                }
+            }
+        })
+        .await
+        .map_err(|_| "Access token request was not approved in time".to_string())??;
+
+        let issued_at: DateTime<Utc> = notification
+            .issued_at
+            .parse::<i64>()
+            .ok()
+            .and_then(DateTime::from_timestamp_millis)
+            .unwrap_or_else(Utc::now);
+        self.store_access_token(notification.access_token, issued_at)
+            .await
+    }
+
     pub async fn get_authorization_code(
         &self,
         automate_login_config: &AutomateLoginConfig,
     ) -> Result<String, String> {
-        let redirect_port: String = env::var(REDIRECT_PORT_ENV).unwrap();
+        let redirect_port: u16 = self.redirect_port()?;
 
+        let state: String = generate_state();
         let dialog_request_params: DialogRequest = DialogRequest {
-            client_id: self.api_key.clone(),
-            redirect_uri: format!("{}{}", "http://127.0.0.1:", &redirect_port),
-            state: None,
+            client_id: self.credentials.api_key.clone(),
+            redirect_uri: format!("{}{}", "http://127.0.0.1:", redirect_port),
+            state: Some(state.clone()),
             response_type: ResponseType::Code,
         };
         let full_url: Url = Url::parse_with_params(
@@ -122,7 +276,8 @@ impl ApiClient {
         .unwrap();
 
         if automate_login_config.automate_login {
-            if automate_login_config.automate_fetching_otp
+            if !automate_login_config.use_totp
+                && automate_login_config.automate_fetching_otp
                 && automate_login_config.mail_provider.is_none()
             {
                 return Err(
@@ -130,127 +285,304 @@ impl ApiClient {
                 );
             }
 
-            let login_pin: String = env::var(LOGIN_PIN_ENV)
-                .expect("Env variable LOGIN_PIN must be set to automate login");
-            let webdriver_socket: String = env::var(WEBDRIVER_SOCKET_ENV)
-                .expect("Env variable WEBDRIVER_SOCKET must be set to automate login");
+            let webdriver_config: WebDriverConfig = automate_login_config
+                .webdriver_config
+                .clone()
+                .unwrap_or_default();
 
-            let fantoccini_client: FantocciniClient = ClientBuilder::native()
-                .connect(&webdriver_socket)
-                .await
-                .map_err(|_| "Failed to connect to WebDriver".to_string())?;
-            let fantoccini_client: Arc<Mutex<Option<FantocciniClient>>> =
-                Arc::new(Mutex::new(Some(fantoccini_client)));
-
-            {
-                let mut client: tokio::sync::MutexGuard<Option<FantocciniClient>> =
-                    fantoccini_client.lock().await;
-                let client: &mut FantocciniClient =
-                    client.as_mut().expect("Client is already closed");
-                client.goto(full_url.as_str()).await.unwrap();
-            }
-
-            sleep(Duration::from_millis(200)).await;
-            let otp_sent_timestamp: i64 = Utc::now().timestamp();
-            self.send_otp(fantoccini_client.clone()).await;
-
-            let automate_fetching_otp: bool = automate_login_config.automate_fetching_otp;
-
-            if automate_fetching_otp {
+            let attempts: u32 = webdriver_config.attempts.max(1);
+            let mut last_error: String = String::new();
+            for attempt in 1..=attempts {
                 match self
-                    .get_otp(
-                        otp_sent_timestamp,
-                        automate_login_config.mail_provider.clone().unwrap(),
+                    .automate_authorization(
+                        automate_login_config,
+                        &webdriver_config,
+                        &full_url,
+                        &state,
+                        attempt,
                     )
                     .await
                 {
-                    Ok(otp) => {
-                        let mut client: tokio::sync::MutexGuard<Option<FantocciniClient>> =
-                            fantoccini_client.lock().await;
-                        let client: &mut FantocciniClient =
-                            client.as_mut().expect("Client is already closed");
-                        let otp_field: Element = client
-                            .wait()
-                            .every(Duration::from_millis(100))
-                            .for_element(Locator::Id("otpNum"))
-                            .await
-                            .unwrap();
-                        otp_field.send_keys(&otp).await.unwrap();
-
-                        let continue_button: Element =
-                            client.find(Locator::Id("continueBtn")).await.unwrap();
-                        continue_button.click().await.unwrap();
-                    }
+                    Ok(auth_code) => return Ok(auth_code),
                     Err(err) => {
-                        warn!("Error while fetching OTP: {}", err);
+                        warn!(
+                            "Automated login attempt {} of {} failed: {}",
+                            attempt, attempts, err
+                        );
+                        last_error = err;
                     }
                 }
             }
+            Err(last_error)
+        } else {
+            let listener: RedirectListener = self.bind_redirect_listener().await?;
+            full_url.open();
+            listener
+                .await_code(&state, Duration::from_secs(REDIRECT_TIMEOUT_SECONDS))
+                .await
+                .map_err(|err| err.to_string())
+        }
+    }
 
-            let auth_code: String = {
-                let mut client: tokio::sync::MutexGuard<Option<FantocciniClient>> =
-                    fantoccini_client.lock().await;
-                let client: &mut FantocciniClient =
-                    client.as_mut().expect("Client is already closed");
+    async fn automate_authorization(
+        &self,
+        automate_login_config: &AutomateLoginConfig,
+        webdriver_config: &WebDriverConfig,
+        full_url: &Url,
+        state: &str,
+        attempt: u32,
+    ) -> Result<String, String> {
+        let webdriver_socket: String = env::var(WEBDRIVER_SOCKET_ENV).map_err(|_| {
+            "Env variable WEBDRIVER_SOCKET must be set to automate login".to_string()
+        })?;
+
+        // The spawned driver is killed once it is dropped at the end of the attempt
+        let _driver: Option<Child> = self
+            .spawn_webdriver(webdriver_config, &webdriver_socket)
+            .await?;
+        let fantoccini_client: FantocciniClient = self
+            .connect_webdriver(webdriver_config, &webdriver_socket)
+            .await?;
+        let fantoccini_client: Arc<Mutex<Option<FantocciniClient>>> =
+            Arc::new(Mutex::new(Some(fantoccini_client)));
+
+        let auth_code: Result<String, String> = self
+            .run_login_steps(
+                automate_login_config,
+                webdriver_config,
+                fantoccini_client.clone(),
+                full_url,
+                state,
+            )
+            .await;
+        if auth_code.is_err() {
+            self.dump_diagnostics(fantoccini_client.clone(), webdriver_config, attempt)
+                .await;
+        }
 
-                let pin_field: Element = client
-                    .wait()
-                    .every(Duration::from_millis(100))
-                    .for_element(Locator::Id("pinCode"))
-                    .await
-                    .unwrap();
-                pin_field.send_keys(&login_pin).await.unwrap();
+        self.close_fantoccini_client(fantoccini_client).await;
+        auth_code
+    }
 
-                let pin_continue_button: Element =
-                    client.find(Locator::Id("pinContinueBtn")).await.unwrap();
+    async fn run_login_steps(
+        &self,
+        automate_login_config: &AutomateLoginConfig,
+        webdriver_config: &WebDriverConfig,
+        fantoccini_client: Arc<Mutex<Option<FantocciniClient>>>,
+        full_url: &Url,
+        state: &str,
+    ) -> Result<String, String> {
+        let login_pin: String = Credentials::require(&self.credentials.login_pin, "login_pin")?;
 
-                let code_future = self.await_and_extract_code();
-                let click_result = pin_continue_button.click().await;
+        {
+            let mut client: MutexGuard<Option<FantocciniClient>> = fantoccini_client.lock().await;
+            let client: &mut FantocciniClient = client.as_mut().expect("Client is already closed");
+            timeout(
+                webdriver_config.step_timeout,
+                client.goto(full_url.as_str()),
+            )
+            .await
+            .map_err(|_| "Timed out loading login page".to_string())?
+            .map_err(|err| format!("Failed to load login page: {}", err))?;
+        }
 
-                match click_result {
-                    Ok(_) => {
-                        // Wait for the server to capture the code
-                        code_future.await
-                    }
-                    Err(err) => {
-                        // Handle the error and extract the code from the error message
-                        if let fantoccini::error::CmdError::Standard(webdriver_error) = err {
-                            let message = webdriver_error.message.into_owned();
-                            if message.contains("Reached error page") && message.contains("code%3D")
-                            {
-                                // Extract the code from the error message
-                                if let Some(code_start) = message.find("code%3D") {
-                                    let code_start = code_start + 7; // Skip "code="
-                                    if let Some(code_end) = message[code_start..].find('&') {
-                                        let code = &message[code_start..code_start + code_end];
-                                        return Ok(code.to_string());
-                                    }
-                                }
-                            }
-                        }
-                        panic!("Failed to click continue button and extract code")
-                    }
+        let otp_sent_timestamp: i64 = Utc::now().timestamp();
+        self.send_otp(fantoccini_client.clone(), webdriver_config)
+            .await?;
+
+        if automate_login_config.use_totp {
+            let totp: String = self.generate_totp().await?;
+            self.enter_otp(fantoccini_client.clone(), webdriver_config, &totp)
+                .await?;
+        } else if automate_login_config.automate_fetching_otp {
+            match self
+                .get_otp(
+                    otp_sent_timestamp,
+                    automate_login_config.mail_provider.as_ref().unwrap(),
+                )
+                .await
+            {
+                Ok(otp) => {
+                    self.enter_otp(fantoccini_client.clone(), webdriver_config, &otp)
+                        .await?
                 }
-            };
+                Err(err) => {
+                    warn!("Error while fetching OTP: {}", err);
+                }
+            }
+        }
 
-            self.close_fantoccini_client(fantoccini_client).await;
-            Ok(auth_code)
-        } else {
-            full_url.open();
-            Ok(self.await_and_extract_code().await)
+        // Bound only now as fetching the OTP from Gmail or Outlook may need the redirect port for their own OAuth flow
+        let listener: RedirectListener = self.bind_redirect_listener().await?;
+
+        let mut client: MutexGuard<Option<FantocciniClient>> = fantoccini_client.lock().await;
+        let client: &mut FantocciniClient = client.as_mut().expect("Client is already closed");
+
+        // Without automated OTP entry, this waits for the OTP to be entered manually
+        let pin_field: Element = self
+            .wait_for_element(client, webdriver_config, "pinCode")
+            .await?;
+        pin_field
+            .send_keys(&login_pin)
+            .await
+            .map_err(|err| format!("Failed to enter PIN: {}", err))?;
+
+        let pin_continue_button: Element = self
+            .wait_for_element(client, webdriver_config, "pinContinueBtn")
+            .await?;
+        // The click may only return once the redirect it causes is answered, so the code is awaited alongside it. Click
+        // errors are not fatal as long as the code is received.
+        let (click_result, auth_code) = tokio::join!(
+            pin_continue_button.click(),
+            listener.await_code(state, webdriver_config.step_timeout)
+        );
+        match (auth_code, click_result) {
+            (Ok(auth_code), _) => Ok(auth_code),
+            // The browser may fail to load the redirect, e.g. when it reached the listener too late, and then reports
+            // the redirect URI with the code in the click error
+            (Err(err), Err(click_err)) => {
+                warn!("Failed to click pinContinueBtn: {}", click_err);
+                Self::code_from_click_error(&click_err.to_string(), state)
+                    .ok_or_else(|| err.to_string())
+            }
+            (Err(err), Ok(_)) => Err(err.to_string()),
+        }
+    }
+
+    // Extracts the code of the redirect URI reported in a WebDriver error, which is URL encoded within the error page URL
+    fn code_from_click_error(message: &str, state: &str) -> Option<String> {
+        let param = |name: &str| -> Option<String> {
+            Regex::new(&format!(r"[?&]{}(?:=|%3D)([^&%\s]+)", name))
+                .unwrap()
+                .captures(&message.replace("%3F", "?").replace("%26", "&"))
+                .map(|captures| captures[1].to_string())
+        };
+        match param("state") {
+            Some(message_state) if message_state == state => param("code"),
+            _ => None,
+        }
+    }
+
+    async fn wait_for_element(
+        &self,
+        client: &FantocciniClient,
+        webdriver_config: &WebDriverConfig,
+        id: &str,
+    ) -> Result<Element, String> {
+        client
+            .wait()
+            .at_most(webdriver_config.step_timeout)
+            .every(Duration::from_millis(100))
+            .for_element(Locator::Id(id))
+            .await
+            .map_err(|err| format!("Failed to find #{}: {}", id, err))
+    }
+
+    // Starts the driver binary if one is configured, listening on the port of WEBDRIVER_SOCKET
+    async fn spawn_webdriver(
+        &self,
+        webdriver_config: &WebDriverConfig,
+        webdriver_socket: &str,
+    ) -> Result<Option<Child>, String> {
+        let Some(driver_binary) = &webdriver_config.driver_binary else {
+            return Ok(None);
+        };
+        let port: u16 = Url::parse(webdriver_socket)
+            .ok()
+            .and_then(|url| url.port())
+            .ok_or("WEBDRIVER_SOCKET must include a port to spawn the driver".to_string())?;
+
+        Command::new(driver_binary)
+            .arg(format!("--port={}", port))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map(Some)
+            .map_err(|err| format!("Failed to spawn {:?}: {}", driver_binary, err))
+    }
+
+    async fn connect_webdriver(
+        &self,
+        webdriver_config: &WebDriverConfig,
+        webdriver_socket: &str,
+    ) -> Result<FantocciniClient, String> {
+        let deadline: Instant = Instant::now() + webdriver_config.step_timeout;
+        loop {
+            match ClientBuilder::native()
+                .capabilities(webdriver_config.capabilities())
+                .connect(webdriver_socket)
+                .await
+            {
+                Ok(client) => return Ok(client),
+                // A freshly spawned driver takes a moment before accepting connections
+                Err(err) if Instant::now() >= deadline => {
+                    return Err(format!("Failed to connect to WebDriver: {}", err));
+                }
+                Err(_) => sleep(Duration::from_millis(250)).await,
+            }
+        }
+    }
+
+    async fn dump_diagnostics(
+        &self,
+        fantoccini_client: Arc<Mutex<Option<FantocciniClient>>>,
+        webdriver_config: &WebDriverConfig,
+        attempt: u32,
+    ) {
+        let Some(directory) = &webdriver_config.diagnostics_dir else {
+            return;
+        };
+        let mut client: MutexGuard<Option<FantocciniClient>> = fantoccini_client.lock().await;
+        let Some(client) = client.as_mut() else {
+            return;
+        };
+        if let Err(err) = fs::create_dir_all(directory) {
+            warn!(
+                "Failed to create diagnostics directory {:?}: {}",
+                directory, err
+            );
+            return;
+        }
+
+        let prefix: String = format!(
+            "login-failure-{}-attempt-{}",
+            Utc::now().format("%Y%m%dT%H%M%S"),
+            attempt
+        );
+        match client.screenshot().await {
+            Ok(screenshot) => {
+                let path: PathBuf = directory.join(format!("{}.png", prefix));
+                if let Err(err) = fs::write(&path, screenshot) {
+                    warn!("Failed to save screenshot to {:?}: {}", path, err);
+                }
+            }
+            Err(err) => warn!("Failed to take screenshot: {}", err),
+        }
+        match client.source().await {
+            Ok(source) => {
+                let path: PathBuf = directory.join(format!("{}.html", prefix));
+                if let Err(err) = write_value_to_file(&path, &source) {
+                    warn!("Failed to save page source to {:?}: {}", path, err);
+                }
+            }
+            Err(err) => warn!("Failed to get page source: {}", err),
         }
+        info!("Saved login failure diagnostics to {:?}", directory);
     }
 
     pub async fn get_token(&self, auth_code: String) -> Result<TokenResponse, ErrorResponse> {
-        let client_id: String = env::var(UPLINK_API_KEY_ENV).unwrap();
-        let client_secret: String = env::var(UPLINK_API_SECRET_ENV).unwrap();
-        let redirect_port: String = env::var(REDIRECT_PORT_ENV).unwrap();
+        let client_id: String = self.credentials.api_key.clone();
+        let client_secret: String =
+            Credentials::require(&self.credentials.api_secret, "api_secret")
+                .map_err(ErrorResponse::client_error)?;
+        let redirect_port: u16 = self.redirect_port().map_err(ErrorResponse::client_error)?;
 
         let token_request_form: TokenRequest = TokenRequest {
             code: auth_code,
             client_id,
             client_secret,
-            redirect_uri: format!("{}{}", "http://127.0.0.1:", &redirect_port),
+            redirect_uri: format!("{}{}", "http://127.0.0.1:", redirect_port),
             grant_type: token_request::GrantType::AuthorizationCode,
         };
 
@@ -285,61 +617,222 @@ impl ApiClient {
             .await
             .unwrap();
         match res.status().as_u16() {
-            200 => Ok(res.json::<SuccessResponse<bool>>().await.unwrap()),
+            200 => {
+                self.token_store.invalidate(UPSTOX_ACCESS_TOKEN_KEY).await?;
+                self.token_store
+                    .invalidate(UPSTOX_ACCESS_TOKEN_ISSUED_AT_KEY)
+                    .await?;
+                Ok(res.json::<SuccessResponse<bool>>().await.unwrap())
+            }
             _ => Err("Unexpected error while logging out".to_string()),
         }
     }
 
-    async fn send_otp(&self, fantoccini_client: Arc<Mutex<Option<FantocciniClient>>>) {
-        let mobile_number: String = env::var(MOBILE_NUMBER_ENV).unwrap();
-        {
-            let client: tokio::sync::MutexGuard<Option<FantocciniClient>> =
-                fantoccini_client.lock().await;
-            let client: &FantocciniClient = client.as_ref().expect("Client is already closed");
-            let mobile_number_field: Element = client.find(Locator::Id("mobileNum")).await.unwrap();
-            mobile_number_field.send_keys(&mobile_number).await.unwrap();
-
-            let get_otp_button: Element = client.find(Locator::Id("getOtp")).await.unwrap();
-            get_otp_button.click().await.unwrap();
+    async fn send_otp(
+        &self,
+        fantoccini_client: Arc<Mutex<Option<FantocciniClient>>>,
+        webdriver_config: &WebDriverConfig,
+    ) -> Result<(), String> {
+        let mobile_number: String =
+            Credentials::require(&self.credentials.mobile_number, "mobile_number")?;
+        let client: MutexGuard<Option<FantocciniClient>> = fantoccini_client.lock().await;
+        let client: &FantocciniClient = client.as_ref().expect("Client is already closed");
+
+        let mobile_number_field: Element = self
+            .wait_for_element(client, webdriver_config, "mobileNum")
+            .await?;
+        mobile_number_field
+            .send_keys(&mobile_number)
+            .await
+            .map_err(|err| format!("Failed to enter mobile number: {}", err))?;
+
+        let get_otp_button: Element = self
+            .wait_for_element(client, webdriver_config, "getOtp")
+            .await?;
+        get_otp_button
+            .click()
+            .await
+            .map_err(|err| format!("Failed to click getOtp: {}", err))
+    }
+
+    async fn enter_otp(
+        &self,
+        fantoccini_client: Arc<Mutex<Option<FantocciniClient>>>,
+        webdriver_config: &WebDriverConfig,
+        otp: &str,
+    ) -> Result<(), String> {
+        let client: MutexGuard<Option<FantocciniClient>> = fantoccini_client.lock().await;
+        let client: &FantocciniClient = client.as_ref().expect("Client is already closed");
+
+        let otp_field: Element = self
+            .wait_for_element(client, webdriver_config, "otpNum")
+            .await?;
+        otp_field
+            .send_keys(otp)
+            .await
+            .map_err(|err| format!("Failed to enter OTP: {}", err))?;
+
+        let continue_button: Element = self
+            .wait_for_element(client, webdriver_config, "continueBtn")
+            .await?;
+        continue_button
+            .click()
+            .await
+            .map_err(|err| format!("Failed to click continueBtn: {}", err))
+    }
+
+    async fn generate_totp(&self) -> Result<String, String> {
+        let totp_secret: String =
+            Credentials::require(&self.credentials.totp_secret, "totp_secret")?;
+        let secret: Vec<u8> = Secret::Encoded(totp_secret.replace(' ', "").to_uppercase())
+            .to_bytes()
+            .map_err(|_| "TOTP_SECRET is not a valid base32 secret".to_string())?;
+        let totp: TOTP = TOTP::new_unchecked(Algorithm::SHA1, 6, 1, 30, secret);
+
+        // Avoid submitting a code that expires before the login page verifies it
+        let ttl: u64 = totp.ttl().map_err(|err| err.to_string())?;
+        if ttl < 3 {
+            sleep(Duration::from_secs(ttl + 1)).await;
         }
+        totp.generate_current().map_err(|err| err.to_string())
     }
 
     async fn get_otp(
         &self,
         otp_sent_time: i64,
-        mail_provider: MailProvider,
+        mail_provider: &MailProvider,
     ) -> Result<String, String> {
-        let email: String = env::var(EMAIL_ID_ENV).unwrap();
-        let access_token: String = match mail_provider {
-            MailProvider::Google => match self.get_google_access_token().await {
-                Ok(token) => token,
-                Err(_) => self.get_google_access_token().await?,
-            },
-        };
+        info!("OTP Sent: {}", otp_sent_time);
 
-        let oauth2: OAuth2 = OAuth2 {
-            user: email,
-            access_token,
-        };
-        let domain: &str = match mail_provider {
-            MailProvider::Google => GOOGLE_IMAP_URL,
-        };
-        let tcp_stream: TcpStream = TcpStream::connect((domain, 993)).await.unwrap();
-        let tls_connector: TlsConnector = TlsConnector::new();
-        let tls_stream: TlsStream<TcpStream> =
-            tls_connector.connect(domain, tcp_stream).await.unwrap();
-
-        let mut client: ImapClient<TlsStream<TcpStream>> = ImapClient::new(tls_stream);
-        let _greeting = client
-            .read_response()
-            .await
-            .expect("Unexpected end of stream, expected greeting");
+        match mail_provider {
+            MailProvider::Google | MailProvider::Outlook => {
+                let email: String = Credentials::require(&self.credentials.email_id, "email_id")?;
+                let (access_token, domain): (String, &str) = match mail_provider {
+                    MailProvider::Google => (
+                        match self.get_google_access_token().await {
+                            Ok(token) => token,
+                            Err(_) => self.get_google_access_token().await?,
+                        },
+                        GOOGLE_IMAP_URL,
+                    ),
+                    _ => (
+                        match self.get_microsoft_access_token().await {
+                            Ok(token) => token,
+                            Err(_) => self.get_microsoft_access_token().await?,
+                        },
+                        OUTLOOK_IMAP_URL,
+                    ),
+                };
 
-        let mut imap_session: Session<TlsStream<TcpStream>> =
-            client.authenticate("XOAUTH2", &oauth2).await.unwrap();
+                let oauth2: OAuth2 = OAuth2 {
+                    user: email,
+                    access_token,
+                };
+                let mut client: ImapClient<TlsStream<TcpStream>> =
+                    ImapClient::new(self.connect_imap_tls(domain, IMAP_TLS_PORT).await?);
+                let _greeting = client
+                    .read_response()
+                    .await
+                    .expect("Unexpected end of stream, expected greeting");
 
-        info!("OTP Sent: {}", otp_sent_time);
+                let mut imap_session: Session<TlsStream<TcpStream>> = client
+                    .authenticate("XOAUTH2", &oauth2)
+                    .await
+                    .map_err(|(err, _)| format!("IMAP authentication failed: {}", err))?;
+                self.find_otp(&mut imap_session, otp_sent_time).await
+            }
+            MailProvider::Imap(imap_config) => {
+                let address: (&str, u16) = (imap_config.host.as_str(), imap_config.port);
+                match imap_config.tls_mode {
+                    ImapTlsMode::Tls => {
+                        let mut client: ImapClient<TlsStream<TcpStream>> =
+                            ImapClient::new(self.connect_imap_tls(address.0, address.1).await?);
+                        let _greeting = client
+                            .read_response()
+                            .await
+                            .expect("Unexpected end of stream, expected greeting");
+                        let mut imap_session: Session<TlsStream<TcpStream>> =
+                            self.login_imap(client, imap_config).await?;
+                        self.find_otp(&mut imap_session, otp_sent_time).await
+                    }
+                    ImapTlsMode::StartTls => {
+                        let tcp_stream: TcpStream = TcpStream::connect(address)
+                            .await
+                            .map_err(|err| format!("Failed to connect to IMAP server: {}", err))?;
+                        let mut client: ImapClient<TcpStream> = ImapClient::new(tcp_stream);
+                        let _greeting = client
+                            .read_response()
+                            .await
+                            .expect("Unexpected end of stream, expected greeting");
+                        client
+                            .run_command_and_check_ok("STARTTLS", None)
+                            .await
+                            .map_err(|err| format!("IMAP STARTTLS failed: {}", err))?;
 
+                        let tls_stream: TlsStream<TcpStream> = TlsConnector::new()
+                            .connect(address.0, client.into_inner())
+                            .await
+                            .map_err(|err| format!("IMAP TLS handshake failed: {}", err))?;
+                        let mut imap_session: Session<TlsStream<TcpStream>> = self
+                            .login_imap(ImapClient::new(tls_stream), imap_config)
+                            .await?;
+                        self.find_otp(&mut imap_session, otp_sent_time).await
+                    }
+                    ImapTlsMode::None => {
+                        let tcp_stream: TcpStream = TcpStream::connect(address)
+                            .await
+                            .map_err(|err| format!("Failed to connect to IMAP server: {}", err))?;
+                        let mut client: ImapClient<TcpStream> = ImapClient::new(tcp_stream);
+                        let _greeting = client
+                            .read_response()
+                            .await
+                            .expect("Unexpected end of stream, expected greeting");
+                        let mut imap_session: Session<TcpStream> =
+                            self.login_imap(client, imap_config).await?;
+                        self.find_otp(&mut imap_session, otp_sent_time).await
+                    }
+                }
+            }
+        }
+    }
+
+    async fn connect_imap_tls(
+        &self,
+        domain: &str,
+        port: u16,
+    ) -> Result<TlsStream<TcpStream>, String> {
+        let tcp_stream: TcpStream = TcpStream::connect((domain, port))
+            .await
+            .map_err(|err| format!("Failed to connect to IMAP server: {}", err))?;
+        TlsConnector::new()
+            .connect(domain, tcp_stream)
+            .await
+            .map_err(|err| format!("IMAP TLS handshake failed: {}", err))
+    }
+
+    async fn login_imap<T>(
+        &self,
+        client: ImapClient<T>,
+        imap_config: &ImapConfig,
+    ) -> Result<Session<T>, String>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Debug + Send,
+    {
+        client
+            .login(&imap_config.username, &imap_config.password)
+            .await
+            .map_err(|(err, _)| format!("IMAP login failed: {}", err))
+    }
+
+    async fn find_otp<T>(
+        &self,
+        imap_session: &mut Session<T>,
+        otp_sent_time: i64,
+    ) -> Result<String, String>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Debug + Send,
+    {
+        let re: Regex = Regex::new(r"[0-9]{6}").unwrap();
         let mut retries: u32 = 0;
         let max_retries: u32 = 5;
         loop {
@@ -355,7 +848,7 @@ impl ApiClient {
             for seq_no in (start_seq_no..=msg_count).rev() {
                 let msg_headers: Option<Fetch> = self
                     .get_message_data(
-                        &mut imap_session,
+                        imap_session,
                         seq_no.to_string(),
                         "BODY[HEADER.FIELDS (SUBJECT FROM DATE)]",
                     )
@@ -391,7 +884,7 @@ impl ApiClient {
                 if from_match && subject_match {
                     debug!("Found OTP email at time: {}", msg_timestamp);
                     let msg_text: Fetch = self
-                        .get_message_data(&mut imap_session, seq_no.to_string(), "BODY[TEXT]")
+                        .get_message_data(imap_session, seq_no.to_string(), "BODY[TEXT]")
                         .await
                         .unwrap();
                     imap_session.logout().await.unwrap();
@@ -403,7 +896,6 @@ impl ApiClient {
                     let document: Html = Html::parse_document(&html_content);
                     let span_selector: Selector = Selector::parse("span").unwrap();
 
-                    let re: Regex = Regex::new(r"[0-9]{6}").unwrap();
                     let otp_element: ElementRef = document
                         .select(&span_selector)
                         .into_iter()
@@ -425,12 +917,15 @@ impl ApiClient {
         }
     }
 
-    async fn get_message_data(
+    async fn get_message_data<T>(
         &self,
-        imap_session: &mut Session<TlsStream<TcpStream>>,
+        imap_session: &mut Session<T>,
         seq_set: String,
         query: &str,
-    ) -> Option<Fetch> {
+    ) -> Option<Fetch>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Debug + Send,
+    {
         let msgs_stream = imap_session.fetch(seq_set, query).await.unwrap();
         let msgs: Vec<Fetch> = msgs_stream.try_collect().await.unwrap();
         msgs.into_iter().next()
@@ -460,9 +955,9 @@ impl ApiClient {
 
         let google_oauth2_token_request_body: Box<dyn ToKeyValueTuples>;
         let refresh_token_found: bool;
-        match read_value_from_file(GOOGLE_REFRESH_TOKEN_FILENAME) {
+        match self.token_store.load(GOOGLE_REFRESH_TOKEN_KEY).await? {
             // Refresh Token already present, so use it to get new access token
-            Ok(refresh_token) => {
+            Some(refresh_token) => {
                 refresh_token_found = true;
                 google_oauth2_token_request_body = Box::new(GoogleOAuth2RefreshTokenRequest {
                     client_id,
@@ -472,14 +967,14 @@ impl ApiClient {
                 });
             }
             // No refresh token found, so use freshly generated authorization code from environment to generate access_token and refresh_token
-            Err(_) => {
+            None => {
                 refresh_token_found = false;
                 let code: String = match env::var(GOOGLE_AUTHORIZATION_CODE_ENV) {
                     Ok(code) => code,
-                    Err(_) => self.get_google_auth_code().await,
+                    Err(_) => self.get_google_auth_code().await?,
                 };
 
-                let redirect_port: String = env::var(REDIRECT_PORT_ENV).unwrap();
+                let redirect_port: u16 = self.redirect_port()?;
 
                 google_oauth2_token_request_body = Box::new(GoogleOAuth2CodeTokenRequest {
                     client_id,
@@ -487,7 +982,7 @@ impl ApiClient {
                     code,
                     code_verifier: None,
                     grant_type: google_oauth2_request::GrantType::AuthorizationCode,
-                    redirect_uri: format!("{}{}", "http://127.0.0.1:", &redirect_port),
+                    redirect_uri: format!("{}{}", "http://127.0.0.1:", redirect_port),
                 });
             }
         }
@@ -503,37 +998,42 @@ impl ApiClient {
             200 => {
                 let response_data = res.json::<GoogleOAuth2TokenResponse>().await.unwrap();
                 if !refresh_token_found {
-                    let _ = write_value_to_file(
-                        GOOGLE_REFRESH_TOKEN_FILENAME,
-                        response_data.refresh_token.unwrap().as_str(),
-                    );
+                    self.token_store
+                        .save(
+                            GOOGLE_REFRESH_TOKEN_KEY,
+                            response_data.refresh_token.unwrap().as_str(),
+                        )
+                        .await?;
                 }
-                return Ok(response_data.access_token);
+                Ok(response_data.access_token)
             }
             400 => {
                 let error_data: GoogleOAuth2TokenErrorResponse =
                     res.json::<GoogleOAuth2TokenErrorResponse>().await.unwrap();
                 if refresh_token_found {
-                    let _ = remove_file(GOOGLE_REFRESH_TOKEN_FILENAME);
+                    self.token_store
+                        .invalidate(GOOGLE_REFRESH_TOKEN_KEY)
+                        .await?;
                 }
-                return Err(error_data.error);
+                Err(error_data.error)
             }
             _ => panic!(),
-        };
+        }
     }
 
-    async fn get_google_auth_code(&self) -> String {
+    async fn get_google_auth_code(&self) -> Result<String, String> {
         let client_id: String = env::var(GOOGLE_CLIENT_ID_ENV).unwrap();
-        let redirect_port: String = env::var(REDIRECT_PORT_ENV).unwrap();
+        let redirect_port: u16 = self.redirect_port()?;
 
+        let state: String = generate_state();
         let google_oauth2_auth_request: GoogleOAuth2AuthRequest = GoogleOAuth2AuthRequest {
             client_id,
-            redirect_uri: format!("{}{}", "http://127.0.0.1:", &redirect_port),
+            redirect_uri: format!("{}{}", "http://127.0.0.1:", redirect_port),
             response_type: google_oauth2_request::ResponseType::Code,
             scope: "https://mail.google.com/".to_string(),
             code_challenge: None,
             code_challenge_method: None,
-            state: None,
+            state: Some(state.clone()),
             login_hint: None,
             access_type: Some(AccessType::Offline),
             prompt: Some(Prompt::SelectAccount),
@@ -544,40 +1044,133 @@ impl ApiClient {
             google_oauth2_auth_request.to_key_value_tuples_vec(),
         )
         .unwrap();
+        let listener: RedirectListener = self.bind_redirect_listener().await?;
         oauth_url.open();
 
-        self.await_and_extract_code().await
+        listener
+            .await_code(&state, Duration::from_secs(REDIRECT_TIMEOUT_SECONDS))
+            .await
+            .map_err(|err| err.to_string())
     }
 
-    async fn await_and_extract_code(&self) -> String {
-        let redirect_port: String = env::var(REDIRECT_PORT_ENV).unwrap();
+    async fn get_microsoft_access_token(&self) -> Result<String, String> {
+        let client: &reqwest::Client = &self.client;
 
-        let addr: SocketAddr =
-            SocketAddr::from(([127, 0, 0, 1], str::parse::<u16>(&redirect_port).unwrap()));
-        let listener: TcpListener = TcpListener::bind(addr).await.unwrap();
-        let (mut socket, _) = listener.accept().await.unwrap();
-        let mut buffer: [u8; 1024] = [0; 1024];
-        socket.read(&mut buffer).await.unwrap();
-        let request: Cow<str> = String::from_utf8_lossy(&buffer[..]);
+        let client_id: String = env::var(MICROSOFT_CLIENT_ID_ENV).unwrap();
+        let client_secret: String = env::var(MICROSOFT_CLIENT_SECRET_ENV).unwrap();
 
-        let response = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n<!DOCTYPE html><html><body>You can now close this tab!</body></html>";
-        socket.write_all(response.as_bytes()).await.unwrap();
-        socket.flush().await.unwrap();
-        socket.shutdown().await.unwrap();
-        self.parse_code(request.to_string()).unwrap()
-    }
+        let microsoft_oauth2_token_request_body: Box<dyn ToKeyValueTuples>;
+        let refresh_token_found: bool;
+        match self.token_store.load(MICROSOFT_REFRESH_TOKEN_KEY).await? {
+            // Refresh Token already present, so use it to get new access token
+            Some(refresh_token) => {
+                refresh_token_found = true;
+                microsoft_oauth2_token_request_body =
+                    Box::new(MicrosoftOAuth2RefreshTokenRequest {
+                        client_id,
+                        client_secret,
+                        grant_type: microsoft_oauth2_request::GrantType::RefreshToken,
+                        refresh_token,
+                        scope: MICROSOFT_OAUTH2_IMAP_SCOPE.to_string(),
+                    });
+            }
+            // No refresh token found, so use freshly generated authorization code from environment to generate access_token and refresh_token
+            None => {
+                refresh_token_found = false;
+                let code: String = match env::var(MICROSOFT_AUTHORIZATION_CODE_ENV) {
+                    Ok(code) => code,
+                    Err(_) => self.get_microsoft_auth_code().await?,
+                };
+
+                let redirect_port: u16 = self.redirect_port()?;
 
-    fn parse_code(&self, request: String) -> Option<String> {
-        if let Some(start_index) = request.find("code=") {
-            let start_index: usize = start_index + 5;
-            if let Some(end_index) = request[start_index..].find(|c| c == '&' || c == ' ') {
-                let end_index: usize = start_index + end_index;
-                let code: &str = &request[start_index..end_index];
-                return decode(code).ok().map(|decoded| decoded.into_owned());
+                microsoft_oauth2_token_request_body = Box::new(MicrosoftOAuth2CodeTokenRequest {
+                    client_id,
+                    client_secret,
+                    code,
+                    grant_type: microsoft_oauth2_request::GrantType::AuthorizationCode,
+                    redirect_uri: format!("{}{}", "http://127.0.0.1:", redirect_port),
+                    scope: MICROSOFT_OAUTH2_IMAP_SCOPE.to_string(),
+                });
             }
         }
 
-        None
+        let res: reqwest::Response = client
+            .post(MICROSOFT_OAUTH2_ACCESS_TOKEN_URL)
+            .form(&microsoft_oauth2_token_request_body.to_key_value_tuples_vec())
+            .send()
+            .await
+            .unwrap();
+
+        match res.status().as_u16() {
+            200 => {
+                let response_data = res.json::<MicrosoftOAuth2TokenResponse>().await.unwrap();
+                // Microsoft rotates refresh tokens, so always store the latest one
+                if let Some(refresh_token) = response_data.refresh_token {
+                    self.token_store
+                        .save(MICROSOFT_REFRESH_TOKEN_KEY, refresh_token.as_str())
+                        .await?;
+                }
+                Ok(response_data.access_token)
+            }
+            400 | 401 => {
+                let error_data: MicrosoftOAuth2TokenErrorResponse = res
+                    .json::<MicrosoftOAuth2TokenErrorResponse>()
+                    .await
+                    .unwrap();
+                if refresh_token_found {
+                    self.token_store
+                        .invalidate(MICROSOFT_REFRESH_TOKEN_KEY)
+                        .await?;
+                }
+                Err(error_data.error)
+            }
+            _ => panic!(),
+        }
+    }
+
+    async fn get_microsoft_auth_code(&self) -> Result<String, String> {
+        let client_id: String = env::var(MICROSOFT_CLIENT_ID_ENV).unwrap();
+        let redirect_port: u16 = self.redirect_port()?;
+
+        let state: String = generate_state();
+        let microsoft_oauth2_auth_request: MicrosoftOAuth2AuthRequest =
+            MicrosoftOAuth2AuthRequest {
+                client_id,
+                redirect_uri: format!("{}{}", "http://127.0.0.1:", redirect_port),
+                response_type: microsoft_oauth2_request::ResponseType::Code,
+                scope: MICROSOFT_OAUTH2_IMAP_SCOPE.to_string(),
+                response_mode: Some(ResponseMode::Query),
+                state: Some(state.clone()),
+                login_hint: self.credentials.email_id.clone(),
+                prompt: Some(microsoft_oauth2_request::Prompt::SelectAccount),
+            };
+
+        let oauth_url: Url = Url::parse_with_params(
+            MICROSOFT_OAUTH2_AUTH_URL,
+            microsoft_oauth2_auth_request.to_key_value_tuples_vec(),
+        )
+        .unwrap();
+        let listener: RedirectListener = self.bind_redirect_listener().await?;
+        oauth_url.open();
+
+        listener
+            .await_code(&state, Duration::from_secs(REDIRECT_TIMEOUT_SECONDS))
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    fn redirect_port(&self) -> Result<u16, String> {
+        env::var(REDIRECT_PORT_ENV)
+            .map_err(|_| "Env variable REDIRECT_PORT must be set".to_string())?
+            .parse::<u16>()
+            .map_err(|_| "Env variable REDIRECT_PORT must be a valid port".to_string())
+    }
+
+    async fn bind_redirect_listener(&self) -> Result<RedirectListener, String> {
+        RedirectListener::bind(self.redirect_port()?)
+            .await
+            .map_err(|err| err.to_string())
     }
 
     async fn close_fantoccini_client(
@@ -585,8 +1178,34 @@ impl ApiClient {
         fantoccini_client: Arc<Mutex<Option<FantocciniClient>>>,
     ) {
         let mut client: MutexGuard<Option<FantocciniClient>> = fantoccini_client.lock().await;
-        if let Some(client) = client.take() {
-            client.close().await.unwrap();
+        if let Some(client) = client.take()
+            && let Err(err) = client.close().await
+        {
+            warn!("Failed to close WebDriver session: {}", err);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ERROR_PAGE: &str = "Reached error page: about:neterror?e=connectionFailure&u=http%3A//127.0.0.1%3A5055/%3Fcode%3DaBc-12_3%26state%3Dxyz&c=UTF-8";
+
+    #[test]
+    fn extracts_code_from_error_page_url() {
+        assert_eq!(
+            ApiClient::code_from_click_error(ERROR_PAGE, "xyz"),
+            Some("aBc-12_3".to_string())
+        );
+    }
+
+    #[test]
+    fn ignores_code_of_another_state() {
+        assert_eq!(ApiClient::code_from_click_error(ERROR_PAGE, "other"), None);
+        assert_eq!(
+            ApiClient::code_from_click_error("no such element", "xyz"),
+            None
+        );
+    }
+}
@@ -34,6 +34,7 @@ use {
         utils::ToKeyValueTuples,
     },
     serde_valid::Validate,
+    std::time::{Duration, Instant},
 };
 
 impl ApiClient {
@@ -43,6 +44,7 @@ impl ApiClient {
         place_order_body: PlaceOrderRequest,
         sandbox: bool,
     ) -> Result<Result<SuccessResponse<OrderResponse>, ErrorResponse>, RateLimitExceeded> {
+        let started_at: Instant = Instant::now();
         place_order_body.validate().unwrap();
         let res: reqwest::Response = self
             .post(
@@ -57,12 +59,29 @@ impl ApiClient {
                 },
                 APIVersion::V2,
             )
-            .await?;
-
-        Ok(match res.status().as_u16() {
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "place_order",
+                    &place_order_body,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
+
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<OrderResponse>, ErrorResponse> = match status {
             200 => Ok(res.json::<SuccessResponse<OrderResponse>>().await.unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "place_order",
+            &place_order_body,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     pub async fn place_order_v3(
@@ -70,26 +89,74 @@ impl ApiClient {
         place_order_body: PlaceOrderV3Request,
         sandbox: bool,
     ) -> Result<Result<SuccessResponse<OrdersResponse>, ErrorResponse>, RateLimitExceeded> {
+        self.place_order_v3_with_timeout(place_order_body, sandbox, None)
+            .await
+    }
+
+    // Requests not answered within send_timeout fail with a transport error
+    pub(crate) async fn place_order_v3_with_timeout(
+        &self,
+        place_order_body: PlaceOrderV3Request,
+        sandbox: bool,
+        send_timeout: Option<Duration>,
+    ) -> Result<Result<SuccessResponse<OrdersResponse>, ErrorResponse>, RateLimitExceeded> {
+        let started_at: Instant = Instant::now();
         place_order_body.validate().unwrap();
+        if let Err(risk_violation) = self.check_place_order_risk(&place_order_body).await {
+            let result: Result<SuccessResponse<OrdersResponse>, ErrorResponse> =
+                Err(risk_violation.into());
+            self.audit_call(
+                "place_order_v3",
+                &place_order_body,
+                None,
+                started_at,
+                &result,
+            );
+            return Ok(result);
+        }
         let res: reqwest::Response = self
-            .post(
+            .post_with_timeout(
                 ORDERS_PLACE_ORDER_ENDPOINT,
                 true,
                 Some(&place_order_body.to_key_value_tuples_vec()),
-                None,
                 if sandbox {
                     BaseUrlType::SANDBOX
                 } else {
                     BaseUrlType::HFT
                 },
                 APIVersion::V3,
+                send_timeout,
             )
-            .await?;
-
-        Ok(match res.status().as_u16() {
-            200 => Ok(res.json::<SuccessResponse<OrdersResponse>>().await.unwrap()),
-            _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "place_order_v3",
+                    &place_order_body,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
+
+        let status: u16 = res.status().as_u16();
+        // The order may have been placed even when the response cannot be read
+        let result: Result<SuccessResponse<OrdersResponse>, ErrorResponse> = match status {
+            200 => res
+                .json::<SuccessResponse<OrdersResponse>>()
+                .await
+                .map_err(|err| ErrorResponse::transport_error(err.to_string())),
+            _ => Err(res
+                .json::<ErrorResponse>()
+                .await
+                .unwrap_or_else(|err| ErrorResponse::transport_error(err.to_string()))),
+        };
+        self.audit_call(
+            "place_order_v3",
+            &place_order_body,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     pub async fn place_multi_order(
@@ -98,7 +165,23 @@ impl ApiClient {
         sandbox: bool,
     ) -> Result<Result<SuccessResponse<PlaceMultiOrderResponse>, ErrorResponse>, RateLimitExceeded>
     {
+        let started_at: Instant = Instant::now();
         place_multi_order_body.validate().unwrap();
+        if let Err(risk_violation) = self
+            .check_place_multi_order_risk(&place_multi_order_body)
+            .await
+        {
+            let result: Result<SuccessResponse<PlaceMultiOrderResponse>, ErrorResponse> =
+                Err(risk_violation.into());
+            self.audit_call(
+                "place_multi_order",
+                &place_multi_order_body,
+                None,
+                started_at,
+                &result,
+            );
+            return Ok(result);
+        }
         let res: reqwest::Response = self
             .post(
                 ORDERS_PLACE_MULTI_ORDER_ENDPOINT,
@@ -112,15 +195,33 @@ impl ApiClient {
                 },
                 APIVersion::V2,
             )
-            .await?;
-
-        Ok(match res.status().as_u16() {
-            200 => Ok(res
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "place_multi_order",
+                    &place_multi_order_body,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
+
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<PlaceMultiOrderResponse>, ErrorResponse> = match status {
+            // 207 when only some of the orders were placed
+            200 | 207 => Ok(res
                 .json::<SuccessResponse<PlaceMultiOrderResponse>>()
                 .await
                 .unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "place_multi_order",
+            &place_multi_order_body,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     #[deprecated(note = "Use modify_order_v3 instead")]
@@ -129,6 +230,7 @@ impl ApiClient {
         modify_order_body: ModifyOrderRequest,
         sandbox: bool,
     ) -> Result<Result<SuccessResponse<OrderResponse>, ErrorResponse>, RateLimitExceeded> {
+        let started_at: Instant = Instant::now();
         modify_order_body.validate().unwrap();
         let res: reqwest::Response = self
             .put(
@@ -143,12 +245,29 @@ impl ApiClient {
                 },
                 APIVersion::V2,
             )
-            .await?;
-
-        Ok(match res.status().as_u16() {
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "modify_order",
+                    &modify_order_body,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
+
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<OrderResponse>, ErrorResponse> = match status {
             200 => Ok(res.json::<SuccessResponse<OrderResponse>>().await.unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "modify_order",
+            &modify_order_body,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     pub async fn modify_order_v3(
@@ -156,7 +275,20 @@ impl ApiClient {
         modify_order_body: ModifyOrderRequest,
         sandbox: bool,
     ) -> Result<Result<SuccessResponse<OrderResponse>, ErrorResponse>, RateLimitExceeded> {
+        let started_at: Instant = Instant::now();
         modify_order_body.validate().unwrap();
+        if let Err(risk_violation) = self.check_modify_order_risk(&modify_order_body).await {
+            let result: Result<SuccessResponse<OrderResponse>, ErrorResponse> =
+                Err(risk_violation.into());
+            self.audit_call(
+                "modify_order_v3",
+                &modify_order_body,
+                None,
+                started_at,
+                &result,
+            );
+            return Ok(result);
+        }
         let res: reqwest::Response = self
             .put(
                 ORDERS_MODIFY_ORDER_ENDPOINT,
@@ -170,12 +302,29 @@ impl ApiClient {
                 },
                 APIVersion::V3,
             )
-            .await?;
-
-        Ok(match res.status().as_u16() {
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "modify_order_v3",
+                    &modify_order_body,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
+
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<OrderResponse>, ErrorResponse> = match status {
             200 => Ok(res.json::<SuccessResponse<OrderResponse>>().await.unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "modify_order_v3",
+            &modify_order_body,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     #[deprecated(note = "Use cancel_order_v3 instead")]
@@ -184,6 +333,7 @@ impl ApiClient {
         order_id: String,
         sandbox: bool,
     ) -> Result<Result<SuccessResponse<OrderResponse>, ErrorResponse>, RateLimitExceeded> {
+        let started_at: Instant = Instant::now();
         let cancel_order_params: CancelOrderRequest = CancelOrderRequest { order_id };
         cancel_order_params.validate().unwrap();
 
@@ -200,12 +350,29 @@ impl ApiClient {
                 },
                 APIVersion::V2,
             )
-            .await?;
-
-        Ok(match res.status().as_u16() {
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "cancel_order",
+                    &cancel_order_params,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
+
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<OrderResponse>, ErrorResponse> = match status {
             200 => Ok(res.json::<SuccessResponse<OrderResponse>>().await.unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "cancel_order",
+            &cancel_order_params,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     pub async fn cancel_order_v3(
@@ -213,6 +380,7 @@ impl ApiClient {
         order_id: String,
         sandbox: bool,
     ) -> Result<Result<SuccessResponse<OrderResponse>, ErrorResponse>, RateLimitExceeded> {
+        let started_at: Instant = Instant::now();
         let cancel_order_params: CancelOrderRequest = CancelOrderRequest { order_id };
         cancel_order_params.validate().unwrap();
 
@@ -229,12 +397,29 @@ impl ApiClient {
                 },
                 APIVersion::V3,
             )
-            .await?;
-
-        Ok(match res.status().as_u16() {
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "cancel_order_v3",
+                    &cancel_order_params,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
+
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<OrderResponse>, ErrorResponse> = match status {
             200 => Ok(res.json::<SuccessResponse<OrderResponse>>().await.unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "cancel_order_v3",
+            &cancel_order_params,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     pub async fn cancel_multi_order(
@@ -242,6 +427,7 @@ impl ApiClient {
         cancel_multi_order_params: CancelMultiOrderRequest,
     ) -> Result<Result<SuccessResponse<CancelMultiOrderResponse>, ErrorResponse>, RateLimitExceeded>
     {
+        let started_at: Instant = Instant::now();
         cancel_multi_order_params.validate().unwrap();
 
         let res: reqwest::Response = self
@@ -253,21 +439,40 @@ impl ApiClient {
                 BaseUrlType::REGULAR,
                 APIVersion::V2,
             )
-            .await?;
-
-        Ok(match res.status().as_u16() {
-            200 => Ok(res
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "cancel_multi_order",
+                    &cancel_multi_order_params,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
+
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<CancelMultiOrderResponse>, ErrorResponse> = match status
+        {
+            200 | 207 => Ok(res
                 .json::<SuccessResponse<CancelMultiOrderResponse>>()
                 .await
                 .unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "cancel_multi_order",
+            &cancel_multi_order_params,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     pub async fn exit_all_positions(
         &self,
         exit_all_positions_params: ExitAllPositionsRequest,
     ) -> Result<Result<SuccessResponse<OrdersResponse>, ErrorResponse>, RateLimitExceeded> {
+        let started_at: Instant = Instant::now();
         exit_all_positions_params.validate().unwrap();
 
         let res: reqwest::Response = self
@@ -279,12 +484,29 @@ impl ApiClient {
                 BaseUrlType::REGULAR,
                 APIVersion::V2,
             )
-            .await?;
-
-        Ok(match res.status().as_u16() {
+            .await
+            .map_err(|rate_limit_exceeded| {
+                self.audit_rate_limited(
+                    "exit_all_positions",
+                    &exit_all_positions_params,
+                    started_at,
+                    rate_limit_exceeded,
+                )
+            })?;
+
+        let status: u16 = res.status().as_u16();
+        let result: Result<SuccessResponse<OrdersResponse>, ErrorResponse> = match status {
             200 => Ok(res.json::<SuccessResponse<OrdersResponse>>().await.unwrap()),
             _ => Err(res.json::<ErrorResponse>().await.unwrap()),
-        })
+        };
+        self.audit_call(
+            "exit_all_positions",
+            &exit_all_positions_params,
+            Some(status),
+            started_at,
+            &result,
+        );
+        Ok(result)
     }
 
     pub async fn get_order_details(
@@ -0,0 +1,274 @@
+use {
+    crate::{
+        client::ApiClient,
+        models::{
+            error_response::ErrorResponse, success_response::SuccessResponse,
+            ws::portfolio_feed_response::PortfolioFeedResponse,
+        },
+        rate_limiter::RateLimitExceeded,
+    },
+    chrono::Utc,
+    serde::{Deserialize, Serialize},
+    serde_json::{Map, Value},
+    std::{
+        fs::{File, OpenOptions},
+        io::{BufRead, BufReader, Write},
+        path::Path,
+        sync::mpsc::{self, Receiver, Sender},
+        thread,
+        time::Instant,
+    },
+    tokio::sync::oneshot,
+    tracing::warn,
+};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventKind {
+    ApiCall,
+    PortfolioFeed,
+}
+
+// One line of the audit log
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AuditRecord {
+    // RFC 3339 in UTC
+    pub timestamp: String,
+    pub kind: AuditEventKind,
+    // Name of the ApiClient method called
+    pub call: Option<String>,
+    // Tag of single orders and correlation ids of multi orders
+    pub correlation_ids: Vec<String>,
+    pub request: Option<Value>,
+    // HTTP status, None when the call was blocked before being sent
+    pub status: Option<u16>,
+    pub response: Value,
+    pub latency_ms: Option<u64>,
+}
+
+enum AuditMessage {
+    Record(String),
+    // Answered once the records sent before it are written and flushed
+    Flush(oneshot::Sender<()>),
+}
+
+// Append only log of order, GTT and exit calls and of portfolio feed order events, written as JSON lines. Records are
+// written by a background thread so that recording never blocks the caller.
+#[derive(Clone)]
+pub struct AuditLog {
+    sender: Sender<AuditMessage>,
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog").finish_non_exhaustive()
+    }
+}
+
+impl AuditLog {
+    // Appends to the file, creating it if needed
+    pub fn file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path: &Path = path.as_ref();
+        let file: File = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| format!("Failed to open audit log {:?}: {}", path, err))?;
+        Ok(Self::writer(file))
+    }
+
+    // The writer is moved to a background thread which stops once every clone of the log is dropped
+    pub fn writer(writer: impl Write + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel::<AuditMessage>();
+        thread::spawn(move || Self::write_records(writer, receiver));
+        AuditLog { sender }
+    }
+
+    fn write_records(mut writer: impl Write, receiver: Receiver<AuditMessage>) {
+        while let Ok(message) = receiver.recv() {
+            // Records waiting in the channel are written together and flushed once
+            let mut flushed: Vec<oneshot::Sender<()>> = Vec::new();
+            for message in std::iter::once(message).chain(receiver.try_iter()) {
+                match message {
+                    AuditMessage::Record(line) => {
+                        if let Err(err) = writeln!(writer, "{}", line) {
+                            warn!("Failed to write audit record: {}", err);
+                        }
+                    }
+                    AuditMessage::Flush(reply) => flushed.push(reply),
+                }
+            }
+            if let Err(err) = writer.flush() {
+                warn!("Failed to flush audit log: {}", err);
+            }
+            for reply in flushed {
+                let _ = reply.send(());
+            }
+        }
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Vec<AuditRecord>, String> {
+        let path: &Path = path.as_ref();
+        let file: File = File::open(path)
+            .map_err(|err| format!("Failed to open audit log {:?}: {}", path, err))?;
+        BufReader::new(file)
+            .lines()
+            .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+            .map(|line| {
+                let line: String =
+                    line.map_err(|err| format!("Failed to read audit log {:?}: {}", path, err))?;
+                serde_json::from_str::<AuditRecord>(&line)
+                    .map_err(|err| format!("Invalid audit record in {:?}: {}", path, err))
+            })
+            .collect()
+    }
+
+    // Failing to write is logged rather than failing the call being recorded
+    pub fn record(&self, record: &AuditRecord) {
+        let result: Result<(), String> = serde_json::to_string(record)
+            .map_err(|err| err.to_string())
+            .and_then(|line| {
+                self.sender
+                    .send(AuditMessage::Record(line))
+                    .map_err(|_| "Audit log writer stopped".to_string())
+            });
+        if let Err(err) = result {
+            warn!("Failed to write audit record: {}", err);
+        }
+    }
+
+    // Waits until the records recorded so far are written, e.g. before exiting
+    pub async fn flush(&self) {
+        let (reply, flushed) = oneshot::channel::<()>();
+        if self.sender.send(AuditMessage::Flush(reply)).is_ok() {
+            let _ = flushed.await;
+        }
+    }
+
+    // Callback for the portfolio feed which records order and GTT order events and then passes the data on to callback
+    // if given
+    pub fn portfolio_feed_callback(
+        &self,
+        mut callback: Option<Box<dyn FnMut(PortfolioFeedResponse) + Send + Sync>>,
+    ) -> Box<dyn FnMut(PortfolioFeedResponse) + Send + Sync> {
+        let audit_log: AuditLog = self.clone();
+        Box::new(move |data: PortfolioFeedResponse| {
+            audit_log.record_portfolio_feed(&data);
+            if let Some(callback) = &mut callback {
+                callback(data);
+            }
+        })
+    }
+
+    pub fn record_portfolio_feed(&self, data: &PortfolioFeedResponse) {
+        let correlation_ids: Vec<String> = match data {
+            PortfolioFeedResponse::OrderData { tag, .. } => tag.iter().cloned().collect(),
+            PortfolioFeedResponse::GTTOrderData { .. } => Vec::new(),
+            _ => return,
+        };
+        self.record(&AuditRecord {
+            timestamp: Utc::now().to_rfc3339(),
+            kind: AuditEventKind::PortfolioFeed,
+            call: None,
+            correlation_ids,
+            request: None,
+            status: None,
+            response: serde_json::to_value(data).unwrap_or(Value::Null),
+            latency_ms: None,
+        });
+    }
+}
+
+impl ApiClient {
+    pub fn set_audit_log(&mut self, audit_log: Option<AuditLog>) {
+        self.audit_log = audit_log;
+    }
+
+    pub(crate) fn audit_call<B, T>(
+        &self,
+        call: &str,
+        request: &B,
+        status: Option<u16>,
+        started_at: Instant,
+        result: &Result<SuccessResponse<T>, ErrorResponse>,
+    ) where
+        B: Serialize + ?Sized,
+        T: Serialize,
+    {
+        if self.audit_log.is_none() {
+            return;
+        }
+        let response: Value = match result {
+            Ok(success_response) => serde_json::to_value(success_response),
+            Err(error_response) => serde_json::to_value(error_response),
+        }
+        .unwrap_or(Value::Null);
+        self.audit_api_call(call, request, status, started_at, response);
+    }
+
+    // Records a call which was not sent because the rate limit was exceeded and passes the error on
+    pub(crate) fn audit_rate_limited<B>(
+        &self,
+        call: &str,
+        request: &B,
+        started_at: Instant,
+        rate_limit_exceeded: RateLimitExceeded,
+    ) -> RateLimitExceeded
+    where
+        B: Serialize + ?Sized,
+    {
+        if self.audit_log.is_some() {
+            let limit: &str = match rate_limit_exceeded {
+                RateLimitExceeded::PerSecond { .. } => "per_second",
+                RateLimitExceeded::PerMinute { .. } => "per_minute",
+                RateLimitExceeded::PerThirtyMinutes { .. } => "per_thirty_minutes",
+            };
+            let response: Value = Value::Object(Map::from_iter([(
+                "rate_limit_exceeded".to_string(),
+                Value::String(limit.to_string()),
+            )]));
+            self.audit_api_call(call, request, None, started_at, response);
+        }
+        rate_limit_exceeded
+    }
+
+    fn audit_api_call<B>(
+        &self,
+        call: &str,
+        request: &B,
+        status: Option<u16>,
+        started_at: Instant,
+        response: Value,
+    ) where
+        B: Serialize + ?Sized,
+    {
+        let Some(audit_log) = &self.audit_log else {
+            return;
+        };
+        let request: Value = serde_json::to_value(request).unwrap_or(Value::Null);
+        let correlation_ids: Vec<String> = match &request {
+            Value::Array(orders) => orders
+                .iter()
+                .filter_map(|order| order.get("correlation_id")?.as_str())
+                .map(str::to_string)
+                .collect(),
+            Value::Object(order) => order
+                .get("tag")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        };
+        audit_log.record(&AuditRecord {
+            timestamp: Utc::now().to_rfc3339(),
+            kind: AuditEventKind::ApiCall,
+            call: Some(call.to_string()),
+            correlation_ids,
+            request: Some(request),
+            status,
+            response,
+            latency_ms: Some(started_at.elapsed().as_millis() as u64),
+        });
+    }
+}
@@ -7,11 +7,13 @@
 //! use {
 //!     dotenvy::dotenv,
 //!     futures::future::join_all,
-//!     std::{collections::HashSet, env},
+//!     std::collections::HashSet,
 //!     tokio::signal,
 //!     upstox_rust_sdk::{
-//!         client::{ApiClient, AutomateLoginConfig, LoginConfig, MailProvider, WSConnectConfig},
-//!         constants::UPLINK_API_KEY_ENV,
+//!         client::{
+//!             ApiClient, AutomateLoginConfig, Credentials, InstrumentsConfig, LoginConfig,
+//!             LoginStrategy, MailProvider, WSConnectConfig,
+//!         },
 //!         models::ws::{
 //!             market_data_feed_v3_message::{MessageDataV3, ModeTypeV3},
 //!             portfolio_feed_request::PortfolioUpdateType,
@@ -34,23 +36,26 @@
 //!         println!("{:?}", data);
 //!     };
 
-//!     let api_key: String = env::var(UPLINK_API_KEY_ENV).unwrap();
-//!     let (fetch_instruments, schedule_refresh_instruments) = (false, false);
+//!     let credentials: Credentials = Credentials::from_env().unwrap();
 
 //!     // ApiClient with websockets connected and handler specified
 //!     let (api_client, tasks_vec) = ApiClient::new(
-//!         &api_key,
+//!         credentials,
 //!         LoginConfig {
 //!             authorize: true,
+//!             login_strategy: LoginStrategy::Browser,
 //!             automate_login_config: Some(AutomateLoginConfig {
 //!                 automate_login: true,
 //!                 schedule_login: false,
 //!                 automate_fetching_otp: true,
 //!                 mail_provider: Some(MailProvider::Google),
+//!                 use_totp: false,
+//!                 webdriver_config: None,
 //!             }),
+//!             token_store: None,
+//!             on_unauthorized: None,
 //!         },
-//!         fetch_instruments,
-//!         schedule_refresh_instruments,
+//!         InstrumentsConfig::default(),
 //!         // Configuration to connect and handle websocket data.
 //!         WSConnectConfig {
 //!             connect_portfolio_stream: true,
@@ -75,9 +80,9 @@
 //!         .send_market_data_feed_v3_message(MarketDataV3Call::SubscribeInstrument(MessageDataV3 {
 //!             mode: ModeTypeV3::Full,
 //!             instrument_keys: vec![
-//!                 "NSE_INDEX|NIFTY LARGEMID250".to_string(),
-//!                 "NSE_INDEX|Nifty Auto".to_string(),
-//!                 "NSE_INDEX|Nifty Midcap 50".to_string(),
+//!                 "NSE_INDEX|NIFTY LARGEMID250".parse().unwrap(),
+//!                 "NSE_INDEX|Nifty Auto".parse().unwrap(),
+//!                 "NSE_INDEX|Nifty Midcap 50".parse().unwrap(),
 //!             ],
 //!         }))
 //!         .await
@@ -103,29 +108,36 @@
 //!     tracing_subscriber::fmt::init();
 //!     let _ = dotenv();
 
-//!     let api_key: String = env::var(UPLINK_API_KEY_ENV).unwrap();
-//!     let (fetch_instruments, schedule_refresh_instruments) = (false, false);
+//!     let credentials: Credentials = Credentials::from_env().unwrap();
 
 //!     // ApiClient which logs in automatically and schedules relogin daily when token expires
 //!     let (_api_client, tasks_vec) = ApiClient::new(
-//!         &api_key,
+//!         credentials,
 //!         LoginConfig {
 //!             authorize: true,
+//!             login_strategy: LoginStrategy::Browser,
 //!             automate_login_config: Some(AutomateLoginConfig {
 //!                 // geckodriver or chromedriver binary must be running locally with port specified in env to use automatic login or schedule login.
 //!                 // ./geckodriver --binary "~/.local/share/flatpak/exports/bin/org.mozilla.firefox" --profile-root "~/.var/app/org.mozilla.firefox/cache/mozilla/firefox/cv70hco5.default-release"
 
 //!                 // Either GOOGLE_AUTHORIZATION_CODE environment must not be set or must be recent if using for the first time or refresh_token.txt has been deleted
 //!                 automate_login: true,
-//!                 // Relogin is scheduled at 3:30 AM IST daily.
+//!                 // Relogin is scheduled for when the access token expires at 3:30 AM IST.
 //!                 schedule_login: true,
 //!                 // Fetch OTP automatically from inbox by providing email IMAP access using env variables. For GMail, login page will be opened in your default web browsers and permission must be granted.
 //!                 automate_fetching_otp: true,
 //!                 mail_provider: Some(MailProvider::Google),
+//!                 // Set to true and provide TOTP_SECRET env variable to enter TOTP from an authenticator app instead, which needs no mail access.
+//!                 use_totp: false,
+//!                 webdriver_config: None,
 //!             }),
+//!             // Tokens are saved in access_token.txt and refresh_token.txt in the working directory when no token store is given.
+//!             // Use EncryptedFileTokenStore, InMemoryTokenStore, EnvTokenStore or a custom TokenStore implementation to keep them elsewhere.
+//!             token_store: None,
+//!             // Log in again if Upstox rejects the access token before it is due to expire.
+//!             on_unauthorized: Some(UnauthorizedHandler::Relogin),
 //!         },
-//!         fetch_instruments,
-//!         schedule_refresh_instruments,
+//!         InstrumentsConfig::default(),
 //!         WSConnectConfig {
 //!             connect_portfolio_stream: false,
 //!             connect_market_data_stream_v3: false,
@@ -157,25 +169,46 @@
 //!     tracing_subscriber::fmt::init();
 //!     let _ = dotenv();
 
-//!     let api_key: String = env::var(UPLINK_API_KEY_ENV).unwrap();
-//!     let (fetch_instruments, schedule_refresh_instruments) = (true, true);
+//!     let credentials: Credentials = Credentials::from_env().unwrap();
 
 //!     // ApiClient which fetches instruments, schedules instruments refresh daily and stores it in ApiClient
 //!     let (api_client, tasks_vec) = ApiClient::new(
-//!         &api_key,
+//!         credentials,
 //!         LoginConfig {
 //!             authorize: false,
+//!             login_strategy: LoginStrategy::Browser,
 //!             automate_login_config: Some(AutomateLoginConfig {
 //!                 automate_login: false,
 //!                 schedule_login: false,
 //!                 automate_fetching_otp: false,
 //!                 mail_provider: Some(MailProvider::Google),
+//!                 use_totp: false,
+//!                 webdriver_config: None,
 //!             }),
+//!             token_store: None,
+//!             on_unauthorized: None,
 //!         },
 //!         // Fetch all instruments data from UPSTOX and store it in the ApiClient.
-//!         fetch_instruments,
-//!         // Refresh instruments data daily at 6:30 AM.
-//!         schedule_refresh_instruments,
+//!         InstrumentsConfig {
+//!             fetch_instruments: true,
+//!             // Refresh instruments data daily at 6:30 AM.
+//!             schedule_refresh: true,
+//!             // Only download the NSE archive and keep its cash and index instruments.
+//!             segments: Some(HashSet::from([
+//!                 ExchangeSegment::NseEq,
+//!                 ExchangeSegment::NseIndex,
+//!             ])),
+//!             // Keep the downloaded instruments across restarts.
+//!             cache_dir: Some(PathBuf::from("instruments_cache")),
+//!             cache_revalidation: CacheRevalidation::Daily,
+//!             // Log lot size changes found by the daily refresh.
+//!             on_refresh: Some(Arc::new(|diff: &InstrumentsDiff| {
+//!                 diff.contract_changes
+//!                     .iter()
+//!                     .filter(|change| change.field == ContractField::LotSize)
+//!                     .for_each(|change| println!("{:?}", change));
+//!             })),
+//!         },
 //!         WSConnectConfig {
 //!             connect_portfolio_stream: false,
 //!             connect_market_data_stream_v3: false,
@@ -198,10 +231,7 @@
 //!                 .instruments
 //!                 .as_ref()
 //!                 .unwrap()
-//!                 .get(&ExchangeSegment::NseIndex)
-//!                 .unwrap()
-//!                 .get("INDEX")
-//!                 .unwrap()
+//!                 .get_by_segment(ExchangeSegment::NseIndex, "INDEX")
 //!         );
 //!         std::io::stdout().flush().unwrap();
 //!     }
@@ -220,7 +250,6 @@
 //! use {
 //!     dotenvy::dotenv,
 //!     futures::future::join_all,
-//!     std::env,
 //!     tokio::{
 //!         signal,
 //!         sync::MutexGuard,
@@ -228,8 +257,10 @@
 //!     },
 //!     tracing::info,
 //!     upstox_rust_sdk::{
-//!         client::{ApiClient, AutomateLoginConfig, LoginConfig, MailProvider, WSConnectConfig},
-//!         constants::UPLINK_API_KEY_ENV,
+//!         client::{
+//!             ApiClient, AutomateLoginConfig, Credentials, InstrumentsConfig, LoginConfig,
+//!             LoginStrategy, MailProvider, WSConnectConfig,
+//!         },
 //!         models::{
 //!             ProductType, TransactionType,
 //!             charges::brokerage_details_request::BrokerageDetailsRequest,
@@ -250,22 +281,28 @@
 //!     tracing_subscriber::fmt::init();
 //!     let _ = dotenv();
 
-//!     let api_key: String = env::var(UPLINK_API_KEY_ENV).unwrap();
-//!     let (fetch_instruments, schedule_refresh_instruments) = (true, false);
+//!     let credentials: Credentials = Credentials::from_env().unwrap();
 
 //!     let (api_client, tasks_vec) = ApiClient::new(
-//!         &api_key,
+//!         credentials,
 //!         LoginConfig {
 //!             authorize: true,
+//!             login_strategy: LoginStrategy::Browser,
 //!             automate_login_config: Some(AutomateLoginConfig {
 //!                 automate_login: true,
 //!                 schedule_login: false,
 //!                 automate_fetching_otp: true,
 //!                 mail_provider: Some(MailProvider::Google),
+//!                 use_totp: false,
+//!                 webdriver_config: None,
 //!             }),
+//!             token_store: None,
+//!             on_unauthorized: None,
+//!         },
+//!         InstrumentsConfig {
+//!             fetch_instruments: true,
+//!             ..InstrumentsConfig::default()
 //!         },
-//!         fetch_instruments,
-//!         schedule_refresh_instruments,
 //!         WSConnectConfig {
 //!             connect_portfolio_stream: false,
 //!             connect_market_data_stream_v3: false,
@@ -290,7 +327,7 @@
 //!         // Charges Endpoints
 //!         let _charges_result = api_client
 //!             .get_brokerage_details(BrokerageDetailsRequest {
-//!                 instrument_token: "NSE_EQ|INE806T01012".to_string(),
+//!                 instrument_token: "NSE_EQ|INE806T01012".parse().unwrap(),
 //!                 quantity: 2,
 //!                 product: ProductType::I,
 //!                 transaction_type: TransactionType::Buy,
@@ -313,7 +350,7 @@
 
 //!         let charges = api_client
 //!             .get_brokerage_details(BrokerageDetailsRequest {
-//!                 instrument_token: "NSE_EQ|INE806T01012".to_string(),
+//!                 instrument_token: "NSE_EQ|INE806T01012".parse().unwrap(),
 //!                 quantity: 2,
 //!                 product: ProductType::I,
 //!                 transaction_type: TransactionType::Buy,
@@ -345,14 +382,17 @@
 
 use {
     crate::{
+        audit::AuditLog,
         constants::{
-            APIVersion, BaseUrlType, RATE_LIMIT_PER_MINUTE, RATE_LIMIT_PER_SECOND,
-            RATE_LIMIT_PER_THIRTY_MINUTES,
+            APIVersion, BaseUrlType, EMAIL_ID_ENV, INSTRUMENTS_REFRESH_HOUR,
+            INSTRUMENTS_REFRESH_MINUTE, LOGIN_PIN_ENV, MOBILE_NUMBER_ENV, RATE_LIMIT_PER_MINUTE,
+            RATE_LIMIT_PER_SECOND, RATE_LIMIT_PER_THIRTY_MINUTES, TOTP_SECRET_ENV,
+            UPLINK_API_KEY_ENV, UPLINK_API_SECRET_ENV, UPSTOX_USER_ID_ENV,
         },
+        instruments::{diff::InstrumentsDiff, store::InstrumentStore},
         models::{
             ExchangeSegment,
             error_response::ErrorResponse,
-            instruments::instruments_response::InstrumentsResponse,
             success_response::SuccessResponse,
             user::profile_response::ProfileResponse,
             ws::{
@@ -360,55 +400,75 @@ use {
                 portfolio_feed_response::PortfolioFeedResponse,
             },
         },
+        orders::risk::RiskManager,
         protos::market_data_feed_v3::FeedResponse as MarketDataFeedV3Response,
         rate_limiter::{ApiRateLimiter, RateLimitExceeded},
-        utils::create_url,
+        token_store::{FileTokenStore, ScopedTokenStore, TokenStore},
+        utils::{create_url, ist_offset, token_expiry_after, transport_error_response},
         ws_client::{MarketDataFeedV3Client, PortfolioFeedClient},
     },
-    chrono::FixedOffset,
+    chrono::{DateTime, FixedOffset, Utc},
     ezsockets::Client as EzClient,
-    reqwest::{Client as ReqwestClient, Method, RequestBuilder, Response},
+    reqwest::{Client as ReqwestClient, Method, RequestBuilder, Response, StatusCode},
     serde::Serialize,
-    std::{
-        collections::{HashMap, HashSet},
-        sync::Arc,
-    },
+    serde_json::{Map, Value, json},
+    std::{collections::HashSet, env, path::PathBuf, sync::Arc},
     tokio::{
-        sync::{Mutex, MutexGuard},
+        sync::{Mutex, MutexGuard, Notify},
         task::JoinHandle,
+        time::{Duration, sleep},
     },
     tokio_cron_scheduler::{Job, JobScheduler},
-    tracing::info,
+    tracing::{info, warn},
 };
 
 pub struct ApiClient {
     pub(crate) client: ReqwestClient,
-    pub(crate) api_key: String,
+    pub(crate) credentials: Credentials,
     pub(crate) token: Option<String>,
-    pub instruments: Option<HashMap<ExchangeSegment, HashMap<String, Vec<InstrumentsResponse>>>>,
+    pub(crate) token_store: Arc<dyn TokenStore>,
+    pub(crate) token_issued_at: Option<DateTime<Utc>>,
+    pub(crate) unauthorized_notify: Arc<Notify>,
+    pub instruments: Option<InstrumentStore>,
+    pub(crate) instruments_config: InstrumentsConfig,
     pub portfolio_feed_client:
         Option<EzClient<PortfolioFeedClient<Box<dyn FnMut(PortfolioFeedResponse) + Send + Sync>>>>,
     pub market_data_feed_v3_client: Option<
         EzClient<MarketDataFeedV3Client<Box<dyn FnMut(MarketDataFeedV3Response) + Send + Sync>>>,
     >,
     pub rate_limiter: ApiRateLimiter,
+    pub(crate) risk_manager: Option<RiskManager>,
+    pub(crate) audit_log: Option<AuditLog>,
 }
 
 impl ApiClient {
     pub async fn new(
-        api_key: &str,
+        credentials: Credentials,
         login_config: LoginConfig,
-        fetch_instruments: bool,
-        schedule_refresh_instruments: bool,
+        instruments_config: InstrumentsConfig,
         ws_connect_config: WSConnectConfig,
     ) -> Result<(Arc<Mutex<ApiClient>>, Vec<JoinHandle<()>>), String> {
+        let token_store: Arc<dyn TokenStore> = login_config
+            .token_store
+            .clone()
+            .unwrap_or_else(|| Arc::new(FileTokenStore::default()));
         let api_client = ApiClient {
             client: ReqwestClient::new(),
-            api_key: api_key.to_string(),
             token: None,
+            // Keep the tokens of different accounts apart when they share a store
+            token_store: match &credentials.user_id {
+                Some(user_id) => Arc::new(ScopedTokenStore::new(token_store, user_id)),
+                None => token_store,
+            },
+            credentials,
+            token_issued_at: None,
+            unauthorized_notify: Arc::new(Notify::new()),
             instruments: None,
+            instruments_config: instruments_config.clone(),
             portfolio_feed_client: None,
             market_data_feed_v3_client: None,
+            risk_manager: None,
+            audit_log: None,
             rate_limiter: ApiRateLimiter::new(
                 RATE_LIMIT_PER_SECOND,
                 RATE_LIMIT_PER_MINUTE,
@@ -423,11 +483,13 @@ impl ApiClient {
         scheduler.start().await.unwrap();
         scheduler.shutdown_on_ctrl_c();
 
-        if fetch_instruments {
+        if instruments_config.fetch_instruments {
             let mut api_client = shared_api_client.lock().await;
-            api_client.instruments =
-                Some(Self::parse_instruments(api_client.get_instruments().await?));
-            if schedule_refresh_instruments {
+            let instruments: InstrumentStore = api_client
+                .get_instrument_store(instruments_config.segments.as_ref())
+                .await?;
+            api_client.instruments = Some(instruments);
+            if instruments_config.schedule_refresh {
                 Self::schedule_refresh_instruments(&scheduler, &shared_api_client).await;
             }
         }
@@ -457,14 +519,26 @@ impl ApiClient {
             }
         }
 
-        if let Some(automate_login_config) = login_config.automate_login_config {
-            if automate_login_config.schedule_login {
-                Self::schedule_auto_login(&scheduler, &shared_api_client, login_config).await;
-            }
+        if let Some(on_unauthorized) = login_config.on_unauthorized.clone() {
+            Self::watch_unauthorized(&shared_api_client, on_unauthorized, login_config.clone())
+                .await;
+        }
+        if login_config
+            .automate_login_config
+            .as_ref()
+            .is_some_and(|automate_login_config| automate_login_config.schedule_login)
+        {
+            Self::schedule_auto_login(&shared_api_client, login_config);
         }
         Ok((shared_api_client, tasks_vec))
     }
 
+    // Expiry of the current access token, known only when its issue time was recorded at login.
+    pub fn token_expires_at(&self) -> Option<DateTime<FixedOffset>> {
+        self.token.as_ref()?;
+        self.token_issued_at.map(token_expiry_after)
+    }
+
     pub(crate) async fn get(
         &self,
         endpoint: &str,
@@ -482,6 +556,7 @@ impl ApiClient {
             None,
             base_url_type,
             api_version,
+            None,
         )
         .await
     }
@@ -507,6 +582,34 @@ impl ApiClient {
             form_body,
             base_url_type,
             api_version,
+            None,
+        )
+        .await
+    }
+
+    // The timeout covers connecting until the response body is read, not waiting on the rate limit
+    pub(crate) async fn post_with_timeout<T>(
+        &self,
+        endpoint: &str,
+        authorized: bool,
+        json_body: Option<&T>,
+        base_url_type: BaseUrlType,
+        api_version: APIVersion,
+        send_timeout: Option<Duration>,
+    ) -> Result<Response, RateLimitExceeded>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.request(
+            Method::POST,
+            endpoint,
+            authorized,
+            None,
+            json_body,
+            None,
+            base_url_type,
+            api_version,
+            send_timeout,
         )
         .await
     }
@@ -532,6 +635,7 @@ impl ApiClient {
             form_body,
             base_url_type,
             api_version,
+            None,
         )
         .await
     }
@@ -557,6 +661,7 @@ impl ApiClient {
             None,
             base_url_type,
             api_version,
+            None,
         )
         .await
     }
@@ -571,18 +676,17 @@ impl ApiClient {
         form_body: Option<&Vec<(String, String)>>,
         base_url_type: BaseUrlType,
         api_version: APIVersion,
+        send_timeout: Option<Duration>,
     ) -> Result<Response, RateLimitExceeded>
     where
         T: Serialize + ?Sized,
     {
-        let rate_limit_check_result: Option<RateLimitExceeded> =
-            self.rate_limiter.check_rate_limit(endpoint).await;
-        if rate_limit_check_result.is_some() {
-            return Err(rate_limit_check_result.unwrap());
+        if let Some(rate_limit_exceeded) = self.rate_limiter.check_rate_limit(endpoint).await {
+            return Err(rate_limit_exceeded);
         }
         let url: String = create_url(base_url_type, api_version, endpoint);
 
-        if authorized && !self.token.is_some() {
+        if authorized && self.token.is_none() {
             panic!(
                 "{}",
                 format!(
@@ -593,10 +697,10 @@ impl ApiClient {
         }
 
         let mut request: RequestBuilder = match method {
-            Method::GET => self.client.get(url),
-            Method::POST => self.client.post(url),
-            Method::PUT => self.client.put(url),
-            Method::DELETE => self.client.delete(url),
+            Method::GET => self.client.get(&url),
+            Method::POST => self.client.post(&url),
+            Method::PUT => self.client.put(&url),
+            Method::DELETE => self.client.delete(&url),
             _ => panic!("Unsupported HTTP Method"),
         };
 
@@ -613,13 +717,36 @@ impl ApiClient {
         }
 
         if authorized {
-            request = request.bearer_auth(&self.token.as_ref().unwrap());
+            request = request.bearer_auth(self.token.as_ref().unwrap());
+        }
+        if let Some(send_timeout) = send_timeout {
+            request = request.timeout(send_timeout);
         }
         request = request.header("Accept", "application/json");
-        Ok(request.send().await.unwrap())
+        let response: Response = match request.send().await {
+            Ok(response) => response,
+            Err(err) => {
+                warn!("Request to {} failed: {}", url, err);
+                transport_error_response(&err)
+            }
+        };
+
+        if authorized && response.status() == StatusCode::UNAUTHORIZED {
+            warn!("Access token rejected by {}", url);
+            self.unauthorized_notify.notify_one();
+        }
+        Ok(response)
     }
 
     pub(crate) async fn verify_authorization(&mut self) -> bool {
+        if self
+            .token_expires_at()
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+        {
+            info!("Upstox saved access token expired");
+            return false;
+        }
+
         let verify_response: Result<SuccessResponse<ProfileResponse>, ErrorResponse> =
             self.get_profile().await.unwrap();
         verify_response.map_or_else(
@@ -628,7 +755,7 @@ impl ApiClient {
                 false
             },
             |_| {
-                info!("Using valid access token from file");
+                info!("Using valid access token from token store");
                 true
             },
         )
@@ -638,66 +765,290 @@ impl ApiClient {
         scheduler: &JobScheduler,
         shared_api_client: &Arc<Mutex<ApiClient>>,
     ) {
-        let shared_api_client_clone: Arc<Mutex<ApiClient>> = Arc::clone(&shared_api_client);
-        let job: Job = Job::new_async_tz(
-            "0 30 06 * * *",
-            FixedOffset::east_opt(19800).unwrap(),
-            move |_, _| {
-                let api_client: Arc<Mutex<ApiClient>> = Arc::clone(&shared_api_client_clone);
-                Box::pin(async move {
+        let shared_api_client_clone: Arc<Mutex<ApiClient>> = Arc::clone(shared_api_client);
+        let schedule: String = format!(
+            "0 {} {} * * *",
+            INSTRUMENTS_REFRESH_MINUTE, INSTRUMENTS_REFRESH_HOUR
+        );
+        let job: Job = Job::new_async_tz(schedule.as_str(), ist_offset(), move |_, _| {
+            let api_client: Arc<Mutex<ApiClient>> = Arc::clone(&shared_api_client_clone);
+            Box::pin(async move {
+                let (diff, on_refresh) = {
                     let mut client: MutexGuard<ApiClient> = api_client.lock().await;
-                    if let Ok(instruments) = client.get_instruments().await {
-                        client.instruments = Some(Self::parse_instruments(instruments));
+                    let on_refresh: Option<InstrumentsRefreshCallback> =
+                        client.instruments_config.on_refresh.clone();
+                    match client.refresh_instruments().await {
+                        Ok(diff) => (diff, on_refresh),
+                        Err(err) => {
+                            warn!("Failed to refresh instruments: {}", err);
+                            return;
+                        }
                     }
-                })
-            },
-        )
+                };
+                // Called without holding the lock so that the callback can use the client
+                if let Some(on_refresh) = on_refresh {
+                    on_refresh(&diff);
+                }
+            })
+        })
         .unwrap();
 
         scheduler.add(job).await.unwrap();
     }
 
-    async fn schedule_auto_login(
-        scheduler: &JobScheduler,
+    // Logs in again once the current token expires, falling back to the next 3:30 AM IST when its issue time is unknown.
+    fn schedule_auto_login(shared_api_client: &Arc<Mutex<ApiClient>>, login_config: LoginConfig) {
+        let api_client: Arc<Mutex<ApiClient>> = Arc::clone(shared_api_client);
+        tokio::spawn(async move {
+            loop {
+                let expires_at: DateTime<FixedOffset> = api_client
+                    .lock()
+                    .await
+                    .token_expires_at()
+                    .unwrap_or_else(|| token_expiry_after(Utc::now()));
+                info!(
+                    "Relogin scheduled at {}",
+                    expires_at.with_timezone(&ist_offset())
+                );
+                sleep(
+                    (expires_at.with_timezone(&Utc) - Utc::now())
+                        .to_std()
+                        .unwrap_or_default(),
+                )
+                .await;
+
+                let mut client: MutexGuard<ApiClient> = api_client.lock().await;
+                if let Err(err) = client.login(&login_config).await {
+                    warn!("Scheduled relogin failed, retrying in a minute: {}", err);
+                    drop(client);
+                    sleep(Duration::from_secs(60)).await;
+                }
+            }
+        });
+    }
+
+    async fn watch_unauthorized(
         shared_api_client: &Arc<Mutex<ApiClient>>,
+        on_unauthorized: UnauthorizedHandler,
         login_config: LoginConfig,
     ) {
-        let shared_api_client_clone: Arc<Mutex<ApiClient>> = Arc::clone(&shared_api_client);
-        let job: Job = Job::new_async_tz(
-            "0 30 03 * * *",
-            FixedOffset::east_opt(19800).unwrap(),
-            move |_, _| {
-                let api_client: Arc<Mutex<ApiClient>> = Arc::clone(&shared_api_client_clone);
-                let login_config: LoginConfig = login_config.clone();
-                Box::pin(async move {
-                    let mut client: MutexGuard<ApiClient> = api_client.lock().await;
-                    client.login(&login_config).await.unwrap();
-                })
-            },
-        )
-        .unwrap();
+        let api_client: Arc<Mutex<ApiClient>> = Arc::clone(shared_api_client);
+        let unauthorized_notify: Arc<Notify> =
+            Arc::clone(&shared_api_client.lock().await.unauthorized_notify);
+        tokio::spawn(async move {
+            loop {
+                unauthorized_notify.notified().await;
+                match &on_unauthorized {
+                    UnauthorizedHandler::Relogin => {
+                        let mut client: MutexGuard<ApiClient> = api_client.lock().await;
+                        if let Err(err) = client.login(&login_config).await {
+                            warn!("Relogin after unauthorized response failed: {}", err);
+                        }
+                    }
+                    UnauthorizedHandler::Callback(callback) => callback(),
+                }
+            }
+        });
+    }
+}
 
-        scheduler.add(job).await.unwrap();
+// Per account details used for authorization and automated login.
+#[derive(Clone)]
+pub struct Credentials {
+    pub api_key: String,
+    pub api_secret: Option<String>,
+    // Upstox user id. When provided, tokens are stored under keys prefixed with it so several accounts can share a TokenStore.
+    pub user_id: Option<String>,
+    pub mobile_number: Option<String>,
+    pub login_pin: Option<String>,
+    pub email_id: Option<String>, // Inbox the OTP is fetched from
+    pub totp_secret: Option<String>,
+}
+
+impl Credentials {
+    pub fn new(api_key: &str, api_secret: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            api_secret: Some(api_secret.to_string()),
+            user_id: None,
+            mobile_number: None,
+            login_pin: None,
+            email_id: None,
+            totp_secret: None,
+        }
+    }
+
+    // Reads UPLINK_API_KEY, UPLINK_API_SECRET, UPSTOX_USER_ID, MOBILE_NUMBER, LOGIN_PIN, EMAIL_ID and TOTP_SECRET.
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Self {
+            api_key: env::var(UPLINK_API_KEY_ENV)
+                .map_err(|_| "Env variable UPLINK_API_KEY must be set".to_string())?,
+            api_secret: env::var(UPLINK_API_SECRET_ENV).ok(),
+            user_id: env::var(UPSTOX_USER_ID_ENV).ok(),
+            mobile_number: env::var(MOBILE_NUMBER_ENV).ok(),
+            login_pin: env::var(LOGIN_PIN_ENV).ok(),
+            email_id: env::var(EMAIL_ID_ENV).ok(),
+            totp_secret: env::var(TOTP_SECRET_ENV).ok(),
+        })
+    }
+
+    // Identifies the account in AccountManager and scopes its tokens
+    pub fn account_id(&self) -> String {
+        self.user_id.clone().unwrap_or_else(|| self.api_key.clone())
+    }
+
+    pub(crate) fn require(value: &Option<String>, name: &str) -> Result<String, String> {
+        value
+            .clone()
+            .ok_or_else(|| format!("{} must be set in Credentials", name))
     }
 }
 
 #[derive(Clone)]
 pub struct LoginConfig {
     pub authorize: bool,
+    pub login_strategy: LoginStrategy,
     pub automate_login_config: Option<AutomateLoginConfig>,
+    // Defaults to FileTokenStore in the current working directory.
+    pub token_store: Option<Arc<dyn TokenStore>>,
+    // Invoked whenever an authorized request is rejected with 401 Unauthorized.
+    pub on_unauthorized: Option<UnauthorizedHandler>,
 }
 
 #[derive(Clone, Copy)]
+pub enum LoginStrategy {
+    Browser,  // Authorization dialog, optionally automated using automate_login_config
+    Notifier, // Access token request approved in the Upstox app and delivered to the notifier webhook
+}
+
+#[derive(Clone)]
+pub enum UnauthorizedHandler {
+    Relogin,
+    Callback(Arc<dyn Fn() + Send + Sync>),
+}
+
+#[derive(Clone)]
 pub struct AutomateLoginConfig {
     pub automate_login: bool,
-    pub schedule_login: bool, // At 3:30 AM IST daily
+    pub schedule_login: bool, // When the token expires at 3:30 AM IST
     pub automate_fetching_otp: bool,
     pub mail_provider: Option<MailProvider>,
+    // Enter a TOTP generated from TOTP_SECRET instead of the OTP sent over SMS and email.
+    pub use_totp: bool,
+    // Defaults to WebDriverConfig::default() when not provided.
+    pub webdriver_config: Option<WebDriverConfig>,
+}
+
+#[derive(Clone)]
+pub struct WebDriverConfig {
+    pub browser: Browser,
+    pub headless: bool,
+    pub browser_binary: Option<PathBuf>,
+    // Spawned on the port in WEBDRIVER_SOCKET for every login attempt when provided, otherwise the driver must already be running.
+    pub driver_binary: Option<PathBuf>,
+    // Upper bound for each step of the login page, such as waiting for a field or the redirect.
+    pub step_timeout: Duration,
+    // Number of times the whole login flow is tried before giving up.
+    pub attempts: u32,
+    // Screenshot and page source are saved here when an attempt fails.
+    pub diagnostics_dir: Option<PathBuf>,
+}
+
+impl Default for WebDriverConfig {
+    fn default() -> Self {
+        Self {
+            browser: Browser::Firefox,
+            headless: false,
+            browser_binary: None,
+            driver_binary: None,
+            step_timeout: Duration::from_secs(60),
+            attempts: 1,
+            diagnostics_dir: None,
+        }
+    }
+}
+
+impl WebDriverConfig {
+    pub(crate) fn capabilities(&self) -> Map<String, Value> {
+        let mut args: Vec<&str> = Vec::new();
+        let mut options: Map<String, Value> = Map::new();
+        let (browser_name, options_key) = match self.browser {
+            Browser::Chrome => {
+                if self.headless {
+                    args.extend(["--headless=new", "--disable-gpu", "--window-size=1280,800"]);
+                }
+                ("chrome", "goog:chromeOptions")
+            }
+            Browser::Firefox => {
+                if self.headless {
+                    args.extend(["-headless", "-width=1280", "-height=800"]);
+                }
+                ("firefox", "moz:firefoxOptions")
+            }
+        };
+        options.insert("args".to_string(), json!(args));
+        if let Some(browser_binary) = &self.browser_binary {
+            options.insert("binary".to_string(), json!(browser_binary));
+        }
+
+        let mut capabilities: Map<String, Value> = Map::new();
+        capabilities.insert("browserName".to_string(), json!(browser_name));
+        capabilities.insert(options_key.to_string(), Value::Object(options));
+        capabilities
+    }
 }
 
 #[derive(Clone, Copy)]
+pub enum Browser {
+    Chrome,
+    Firefox,
+}
+
+#[derive(Clone)]
 pub enum MailProvider {
     Google,
+    Outlook, // Office365 and Outlook.com accounts using OAuth2
+    Imap(ImapConfig),
+}
+
+#[derive(Clone)]
+pub struct ImapConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls_mode: ImapTlsMode,
+    pub username: String,
+    pub password: String, // Account or app password
+}
+
+#[derive(Clone, Copy)]
+pub enum ImapTlsMode {
+    Tls,
+    StartTls,
+    None,
+}
+
+#[derive(Clone, Default)]
+pub struct InstrumentsConfig {
+    pub fetch_instruments: bool,
+    pub schedule_refresh: bool, // Daily at 6:30 AM IST
+    // Only the instruments of these segments are fetched, from the archives of their exchanges. All when not provided.
+    pub segments: Option<HashSet<ExchangeSegment>>,
+    // Directory in which the instruments archive is kept between runs. Nothing is written to disk when not provided.
+    pub cache_dir: Option<PathBuf>,
+    pub cache_revalidation: CacheRevalidation,
+    // Called with the changes after each scheduled refresh, e.g. to pick up lot size changes before trading starts
+    pub on_refresh: Option<InstrumentsRefreshCallback>,
+}
+
+pub type InstrumentsRefreshCallback = Arc<dyn Fn(&InstrumentsDiff) + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CacheRevalidation {
+    // Use the cached archive without a request when it was fetched after the latest refresh at 6:30 AM IST
+    #[default]
+    Daily,
+    // Always ask Upstox whether the archive changed, using the ETag and Last-Modified it was served with
+    Conditional,
 }
 
 pub struct WSConnectConfig {
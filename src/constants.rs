@@ -11,10 +11,11 @@ pub(super) enum APIVersion {
 
 pub(super) const LOGIN_AUTHORIZE_ENDPOINT: &str = "/login/authorization/dialog";
 pub(super) const LOGIN_GET_TOKEN_ENDPOINT: &str = "/login/authorization/token";
+pub(super) const LOGIN_ACCESS_TOKEN_REQUEST_ENDPOINT: &str = "/login/auth/token/request";
 pub(super) const LOGOUT_ENDPOINT: &str = "/logout";
 
-pub(super) const INSTRUMENTS_COMPLETE_URL: &str =
-    "https://assets.upstox.com/market-quote/instruments/exchange/complete.json.gz";
+pub(super) const INSTRUMENTS_BASE_URL: &str =
+    "https://assets.upstox.com/market-quote/instruments/exchange/";
 
 pub(super) const USER_GET_FUND_AND_MARGIN_ENDPOINT: &str = "/user/get-funds-and-margin";
 pub(super) const USER_GET_PROFILE_ENDPOINT: &str = "/user/profile";
@@ -79,23 +80,53 @@ pub(super) const WS_MARKET_DATA_FEED_AUTHORIZE_ENDPOINT: &str = "/feed/market-da
 pub(super) const GOOGLE_IMAP_URL: &str = "imap.gmail.com";
 pub(super) const GOOGLE_OAUTH2_AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
 pub(super) const GOOGLE_OAUTH2_ACCESS_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
-
-pub(super) const GOOGLE_REFRESH_TOKEN_FILENAME: &str = "refresh_token.txt";
-pub(super) const UPSTOX_ACCESS_TOKEN_FILENAME: &str = "access_token.txt";
-pub(super) const INSTRUMENTS_ARCHIVE_FILENAME: &str = "complete.json.gz";
-pub(super) const INSTRUMENTS_JSON_FILENAME: &str = "complete.json";
+pub(super) const OUTLOOK_IMAP_URL: &str = "outlook.office365.com";
+pub(super) const MICROSOFT_OAUTH2_AUTH_URL: &str =
+    "https://login.microsoftonline.com/common/oauth2/v2.0/authorize";
+pub(super) const MICROSOFT_OAUTH2_ACCESS_TOKEN_URL: &str =
+    "https://login.microsoftonline.com/common/oauth2/v2.0/token";
+pub(super) const MICROSOFT_OAUTH2_IMAP_SCOPE: &str =
+    "https://outlook.office.com/IMAP.AccessAsUser.All offline_access";
+pub(super) const IMAP_TLS_PORT: u16 = 993;
+pub(super) const REDIRECT_TIMEOUT_SECONDS: u64 = 300;
+
+// Keys under which tokens are kept in the TokenStore
+pub const GOOGLE_REFRESH_TOKEN_KEY: &str = "refresh_token";
+pub const MICROSOFT_REFRESH_TOKEN_KEY: &str = "microsoft_refresh_token";
+pub const UPSTOX_ACCESS_TOKEN_KEY: &str = "access_token";
+pub const UPSTOX_ACCESS_TOKEN_ISSUED_AT_KEY: &str = "access_token_issued_at";
+pub(super) const INSTRUMENTS_COMPLETE_FILENAME: &str = "complete.json.gz";
+pub(super) const INSTRUMENTS_NSE_FILENAME: &str = "NSE.json.gz";
+pub(super) const INSTRUMENTS_BSE_FILENAME: &str = "BSE.json.gz";
+pub(super) const INSTRUMENTS_MCX_FILENAME: &str = "MCX.json.gz";
+pub(super) const INSTRUMENTS_CACHE_METADATA_EXTENSION: &str = "meta";
+// Upstox publishes the instruments for the day before this time.
+pub(super) const INSTRUMENTS_REFRESH_HOUR: u32 = 6;
+pub(super) const INSTRUMENTS_REFRESH_MINUTE: u32 = 30;
+
+pub(super) const IST_UTC_OFFSET_SECONDS: i32 = 19800;
+// Upstox access tokens expire at 3:30 AM IST on the day after they are issued.
+pub(super) const UPSTOX_TOKEN_EXPIRY_HOUR: u32 = 3;
+pub(super) const UPSTOX_TOKEN_EXPIRY_MINUTE: u32 = 30;
 
 pub(super) const RATE_LIMIT_PER_SECOND: usize = 25;
 pub(super) const RATE_LIMIT_PER_MINUTE: usize = 250;
 pub(super) const RATE_LIMIT_PER_THIRTY_MINUTES: usize = 1000;
+pub(super) const MULTI_ORDER_MAX_ORDERS: usize = 25;
+pub(super) const MULTI_ORDER_MAX_CORRELATION_ID_LENGTH: usize = 20;
 
 pub(super) const EMAIL_ID_ENV: &str = "EMAIL_ID";
 pub(super) const GOOGLE_AUTHORIZATION_CODE_ENV: &str = "GOOGLE_AUTHORIZATION_CODE";
 pub(super) const GOOGLE_CLIENT_ID_ENV: &str = "GOOGLE_CLIENT_ID";
 pub(super) const GOOGLE_CLIENT_SECRET_ENV: &str = "GOOGLE_CLIENT_SECRET";
+pub(super) const MICROSOFT_AUTHORIZATION_CODE_ENV: &str = "MICROSOFT_AUTHORIZATION_CODE";
+pub(super) const MICROSOFT_CLIENT_ID_ENV: &str = "MICROSOFT_CLIENT_ID";
+pub(super) const MICROSOFT_CLIENT_SECRET_ENV: &str = "MICROSOFT_CLIENT_SECRET";
 pub(super) const MOBILE_NUMBER_ENV: &str = "MOBILE_NUMBER";
 pub(super) const LOGIN_PIN_ENV: &str = "LOGIN_PIN";
 pub(super) const REDIRECT_PORT_ENV: &str = "REDIRECT_PORT";
+pub(super) const TOTP_SECRET_ENV: &str = "TOTP_SECRET";
 pub const UPLINK_API_KEY_ENV: &str = "UPLINK_API_KEY";
 pub(super) const UPLINK_API_SECRET_ENV: &str = "UPLINK_API_SECRET";
+pub(super) const UPSTOX_USER_ID_ENV: &str = "UPSTOX_USER_ID";
 pub(super) const WEBDRIVER_SOCKET_ENV: &str = "WEBDRIVER_SOCKET";
@@ -0,0 +1,240 @@
+use {
+    crate::{
+        instruments::store::InstrumentStore,
+        models::{
+            instrument_key::InstrumentKey, instruments::instruments_response::InstrumentsResponse,
+            trading_symbol::TradingSymbol,
+        },
+    },
+    std::collections::HashSet,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContractField {
+    LotSize,
+    TickSize,
+    FreezeQuantity,
+}
+
+#[derive(Clone, Debug)]
+pub struct ContractChange {
+    pub instrument_key: InstrumentKey,
+    pub trading_symbol: TradingSymbol,
+    pub field: ContractField,
+    pub old_value: f64,
+    pub new_value: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct SymbolRename {
+    pub isin: String,
+    pub old_instrument_key: InstrumentKey,
+    pub new_instrument_key: InstrumentKey,
+    pub old_trading_symbol: TradingSymbol,
+    pub new_trading_symbol: TradingSymbol,
+}
+
+// Changes between two loads of the instrument master, such as the previous day's and today's
+#[derive(Clone, Debug, Default)]
+pub struct InstrumentsDiff {
+    pub listed: Vec<InstrumentsResponse>,
+    pub delisted: Vec<InstrumentsResponse>,
+    // Derivatives no longer present, usually because they expired
+    pub expired: Vec<InstrumentsResponse>,
+    pub contract_changes: Vec<ContractChange>,
+    // Instruments with the same ISIN in the same segment whose trading symbol changed
+    pub renames: Vec<SymbolRename>,
+}
+
+impl InstrumentsDiff {
+    pub fn new(old: &InstrumentStore, new: &InstrumentStore) -> Self {
+        let mut diff: InstrumentsDiff = InstrumentsDiff::default();
+        let mut renamed_keys: HashSet<&InstrumentKey> = HashSet::new();
+
+        for old_instrument in old.iter() {
+            let Some(isin) = old_instrument.isin() else {
+                continue;
+            };
+            let same_isin: Vec<&InstrumentsResponse> = new
+                .get_by_isin(isin)
+                .into_iter()
+                .filter(|new_instrument| new_instrument.segment() == old_instrument.segment())
+                .collect();
+            // Not a rename while the old trading symbol is still listed, e.g. alongside another series of the ISIN
+            if same_isin.iter().any(|new_instrument| {
+                new_instrument.trading_symbol() == old_instrument.trading_symbol()
+            }) {
+                continue;
+            }
+            if let Some(new_instrument) = same_isin.first() {
+                renamed_keys.insert(old_instrument.instrument_key());
+                renamed_keys.insert(new_instrument.instrument_key());
+                diff.renames.push(SymbolRename {
+                    isin: isin.to_string(),
+                    old_instrument_key: old_instrument.instrument_key().clone(),
+                    new_instrument_key: new_instrument.instrument_key().clone(),
+                    old_trading_symbol: old_instrument.trading_symbol().clone(),
+                    new_trading_symbol: new_instrument.trading_symbol().clone(),
+                });
+            }
+        }
+
+        for old_instrument in old.iter() {
+            match new.get(old_instrument.instrument_key()) {
+                Some(new_instrument) => {
+                    diff.contract_changes
+                        .extend(Self::contract_changes(old_instrument, new_instrument));
+                }
+                None if renamed_keys.contains(old_instrument.instrument_key()) => {}
+                None if old_instrument.expiry().is_some() => {
+                    diff.expired.push(old_instrument.clone())
+                }
+                None => diff.delisted.push(old_instrument.clone()),
+            }
+        }
+
+        diff.listed = new
+            .iter()
+            .filter(|new_instrument| {
+                old.get(new_instrument.instrument_key()).is_none()
+                    && !renamed_keys.contains(new_instrument.instrument_key())
+            })
+            .cloned()
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listed.is_empty()
+            && self.delisted.is_empty()
+            && self.expired.is_empty()
+            && self.contract_changes.is_empty()
+            && self.renames.is_empty()
+    }
+
+    fn contract_changes(
+        old_instrument: &InstrumentsResponse,
+        new_instrument: &InstrumentsResponse,
+    ) -> Vec<ContractChange> {
+        [
+            (
+                ContractField::LotSize,
+                old_instrument.lot_size().map(f64::from),
+                new_instrument.lot_size().map(f64::from),
+            ),
+            (
+                ContractField::TickSize,
+                old_instrument.tick_size(),
+                new_instrument.tick_size(),
+            ),
+            (
+                ContractField::FreezeQuantity,
+                old_instrument.freeze_quantity(),
+                new_instrument.freeze_quantity(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(
+            |(field, old_value, new_value)| match (old_value, new_value) {
+                (Some(old_value), Some(new_value)) if old_value != new_value => {
+                    Some(ContractChange {
+                        instrument_key: new_instrument.instrument_key().clone(),
+                        trading_symbol: new_instrument.trading_symbol().clone(),
+                        field,
+                        old_value,
+                        new_value,
+                    })
+                }
+                _ => None,
+            },
+        )
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::models::instruments::fixtures::{derivative, equity},
+        chrono::NaiveDate,
+    };
+
+    fn future(token: &str) -> InstrumentsResponse {
+        derivative(
+            token,
+            "FUT",
+            "NSE_INDEX|Nifty 50",
+            NaiveDate::from_ymd_opt(2025, 12, 30).unwrap(),
+            false,
+        )
+    }
+
+    fn keys(instruments: &[InstrumentsResponse]) -> Vec<String> {
+        instruments
+            .iter()
+            .map(|instrument| instrument.instrument_key().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn classifies_listed_delisted_and_expired() {
+        let old: InstrumentStore = InstrumentStore::new(vec![
+            equity("1", "INE000000001", "KEPT", 1),
+            equity("2", "INE000000002", "GONE", 1),
+            future("3"),
+        ]);
+        let new: InstrumentStore = InstrumentStore::new(vec![
+            equity("1", "INE000000001", "KEPT", 1),
+            equity("4", "INE000000004", "NEW", 1),
+        ]);
+        let diff: InstrumentsDiff = InstrumentsDiff::new(&old, &new);
+        assert_eq!(keys(&diff.listed), vec!["NSE_EQ|4"]);
+        assert_eq!(keys(&diff.delisted), vec!["NSE_EQ|2"]);
+        assert_eq!(keys(&diff.expired), vec!["NSE_FO|3"]);
+        assert!(diff.contract_changes.is_empty());
+        assert!(diff.renames.is_empty());
+    }
+
+    #[test]
+    fn reports_contract_changes() {
+        let old: InstrumentStore =
+            InstrumentStore::new(vec![equity("1", "INE000000001", "KEPT", 1)]);
+        let new: InstrumentStore =
+            InstrumentStore::new(vec![equity("1", "INE000000001", "KEPT", 5)]);
+        let diff: InstrumentsDiff = InstrumentsDiff::new(&old, &new);
+        assert_eq!(diff.contract_changes.len(), 1);
+        assert_eq!(diff.contract_changes[0].field, ContractField::LotSize);
+        assert_eq!(diff.contract_changes[0].old_value, 1.0);
+        assert_eq!(diff.contract_changes[0].new_value, 5.0);
+    }
+
+    #[test]
+    fn reports_renames_instead_of_listing_and_delisting() {
+        let old: InstrumentStore =
+            InstrumentStore::new(vec![equity("1", "INE000000001", "OLDNAME", 1)]);
+        let new: InstrumentStore =
+            InstrumentStore::new(vec![equity("2", "INE000000001", "NEWNAME", 1)]);
+        let diff: InstrumentsDiff = InstrumentsDiff::new(&old, &new);
+        assert_eq!(diff.renames.len(), 1);
+        assert_eq!(diff.renames[0].old_trading_symbol.as_str(), "OLDNAME");
+        assert_eq!(diff.renames[0].new_trading_symbol.as_str(), "NEWNAME");
+        assert!(diff.listed.is_empty());
+        assert!(diff.delisted.is_empty());
+    }
+
+    #[test]
+    fn does_not_rename_while_old_trading_symbol_is_listed() {
+        let old: InstrumentStore =
+            InstrumentStore::new(vec![equity("1", "INE000000001", "STOCK", 1)]);
+        let new: InstrumentStore = InstrumentStore::new(vec![
+            equity("2", "INE000000001", "STOCK-BE", 1),
+            equity("1", "INE000000001", "STOCK", 1),
+        ]);
+        let diff: InstrumentsDiff = InstrumentsDiff::new(&old, &new);
+        assert!(diff.renames.is_empty());
+        assert_eq!(keys(&diff.listed), vec!["NSE_EQ|2"]);
+        assert!(diff.delisted.is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+use {
+    crate::{instruments::store::InstrumentStore, models::instrument_key::InstrumentKey},
+    chrono::{Datelike, NaiveDate},
+    std::collections::{BTreeMap, HashMap},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExpiryKind {
+    Weekly,
+    Monthly,
+    Quarterly, // Monthly expiry in March, June, September or December
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RelativeExpiry {
+    CurrentWeek,
+    NextWeek,
+    CurrentMonth,
+    NextMonth,
+    FarMonth,
+    CurrentQuarter,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Expiry {
+    pub date: NaiveDate,
+    pub kind: ExpiryKind,
+}
+
+// Expiry dates of derivatives per underlying, e.g. NSE_INDEX|Nifty Bank
+#[derive(Clone, Debug, Default)]
+pub struct ExpiryCalendar {
+    // Whether contracts expiring on the date are flagged weekly, None when unknown as for expired ones
+    expiries: HashMap<InstrumentKey, BTreeMap<NaiveDate, Option<bool>>>,
+}
+
+impl ExpiryCalendar {
+    pub fn new(store: &InstrumentStore) -> Self {
+        let mut calendar: ExpiryCalendar = ExpiryCalendar::default();
+        for instrument in store.iter() {
+            let (Some(underlying_key), Some(expiry_date)) =
+                (instrument.underlying_key(), instrument.expiry_date())
+            else {
+                continue;
+            };
+            let weekly: &mut Option<bool> = calendar
+                .expiries
+                .entry(underlying_key)
+                .or_default()
+                .entry(expiry_date)
+                .or_default();
+            *weekly = match (*weekly, instrument.weekly()) {
+                (Some(current), Some(flag)) => Some(current || flag),
+                (current, flag) => current.or(flag),
+            };
+        }
+        calendar
+    }
+
+    // Adds expiries as returned by get_expiries in yyyy-mm-dd format, so that expired contracts can be looked up as well
+    pub fn add_expiries(
+        &mut self,
+        underlying_key: &InstrumentKey,
+        expiries: &[String],
+    ) -> Result<(), String> {
+        let dates: BTreeMap<NaiveDate, Option<bool>> = expiries
+            .iter()
+            .map(|expiry| {
+                NaiveDate::parse_from_str(expiry, "%Y-%m-%d")
+                    .map(|date| (date, None))
+                    .map_err(|_| format!("Invalid expiry date {}", expiry))
+            })
+            .collect::<Result<_, String>>()?;
+        let underlying_expiries: &mut BTreeMap<NaiveDate, Option<bool>> =
+            self.expiries.entry(underlying_key.clone()).or_default();
+        for (date, weekly) in dates {
+            underlying_expiries.entry(date).or_insert(weekly);
+        }
+        Ok(())
+    }
+
+    pub fn underlyings(&self) -> impl Iterator<Item = &InstrumentKey> {
+        self.expiries.keys()
+    }
+
+    // All expiries of the underlying in ascending order. An expiry is monthly when it is the last one of its month and
+    // its contracts are not flagged weekly.
+    pub fn expiries(&self, underlying_key: &InstrumentKey) -> Vec<Expiry> {
+        let Some(dates) = self.expiries.get(underlying_key) else {
+            return Vec::new();
+        };
+        let mut expiries: Vec<Expiry> = Vec::with_capacity(dates.len());
+        let mut dates_iter = dates.iter().peekable();
+        while let Some((&date, &weekly)) = dates_iter.next() {
+            let last_of_month: bool = match dates_iter.peek() {
+                Some((next_date, _)) => {
+                    (next_date.year(), next_date.month()) != (date.year(), date.month())
+                }
+                None => true,
+            };
+            let kind: ExpiryKind = match (weekly, last_of_month) {
+                (Some(true), _) | (_, false) => ExpiryKind::Weekly,
+                _ if date.month() % 3 == 0 => ExpiryKind::Quarterly,
+                _ => ExpiryKind::Monthly,
+            };
+            expiries.push(Expiry { date, kind });
+        }
+        expiries
+    }
+
+    pub fn expiries_of_kind(
+        &self,
+        underlying_key: &InstrumentKey,
+        kind: ExpiryKind,
+    ) -> Vec<Expiry> {
+        self.expiries(underlying_key)
+            .into_iter()
+            .filter(|expiry| match kind {
+                // Quarterly expiries are monthly ones as well
+                ExpiryKind::Monthly => expiry.kind != ExpiryKind::Weekly,
+                _ => expiry.kind == kind,
+            })
+            .collect()
+    }
+
+    // First expiry on or after the date, as contracts can be traded on their expiry date
+    pub fn next_expiry(&self, underlying_key: &InstrumentKey, date: NaiveDate) -> Option<Expiry> {
+        self.expiries(underlying_key)
+            .into_iter()
+            .find(|expiry| expiry.date >= date)
+    }
+
+    pub fn previous_expiry(
+        &self,
+        underlying_key: &InstrumentKey,
+        date: NaiveDate,
+    ) -> Option<Expiry> {
+        self.expiries(underlying_key)
+            .into_iter()
+            .rev()
+            .find(|expiry| expiry.date < date)
+    }
+
+    // Weekly relative expiries count every expiry, as the monthly expiry is also the expiry of its week
+    pub fn resolve(
+        &self,
+        underlying_key: &InstrumentKey,
+        relative_expiry: RelativeExpiry,
+        today: NaiveDate,
+    ) -> Option<Expiry> {
+        let upcoming: Vec<Expiry> = self
+            .expiries(underlying_key)
+            .into_iter()
+            .filter(|expiry| expiry.date >= today)
+            .collect();
+        let mut monthly = upcoming
+            .iter()
+            .filter(|expiry| expiry.kind != ExpiryKind::Weekly);
+        match relative_expiry {
+            RelativeExpiry::CurrentWeek => upcoming.first().copied(),
+            RelativeExpiry::NextWeek => upcoming.get(1).copied(),
+            RelativeExpiry::CurrentMonth => monthly.next().copied(),
+            RelativeExpiry::NextMonth => monthly.nth(1).copied(),
+            RelativeExpiry::FarMonth => monthly.nth(2).copied(),
+            RelativeExpiry::CurrentQuarter => upcoming
+                .iter()
+                .find(|expiry| expiry.kind == ExpiryKind::Quarterly)
+                .copied(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::models::instruments::{
+            fixtures::derivative, instruments_response::InstrumentsResponse,
+        },
+    };
+
+    const UNDERLYING_KEY: &str = "NSE_INDEX|Nifty 50";
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn option(token: &str, expiry: NaiveDate, weekly: bool) -> InstrumentsResponse {
+        derivative(token, "CE", UNDERLYING_KEY, expiry, weekly)
+    }
+
+    fn calendar() -> ExpiryCalendar {
+        ExpiryCalendar::new(&InstrumentStore::new(vec![
+            option("1", date(2025, 5, 22), true),
+            option("2", date(2025, 5, 29), false),
+            option("3", date(2025, 6, 5), true),
+            option("4", date(2025, 6, 26), false),
+            // Flagged weekly even though it is the last expiry of its month
+            option("5", date(2025, 7, 31), true),
+            option("6", date(2025, 8, 28), false),
+        ]))
+    }
+
+    #[test]
+    fn classifies_weekly_monthly_and_quarterly_expiries() {
+        let underlying_key: InstrumentKey = UNDERLYING_KEY.parse().unwrap();
+        let kinds: Vec<(NaiveDate, ExpiryKind)> = calendar()
+            .expiries(&underlying_key)
+            .into_iter()
+            .map(|expiry| (expiry.date, expiry.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (date(2025, 5, 22), ExpiryKind::Weekly),
+                (date(2025, 5, 29), ExpiryKind::Monthly),
+                (date(2025, 6, 5), ExpiryKind::Weekly),
+                (date(2025, 6, 26), ExpiryKind::Quarterly),
+                (date(2025, 7, 31), ExpiryKind::Weekly),
+                (date(2025, 8, 28), ExpiryKind::Monthly),
+            ]
+        );
+    }
+
+    #[test]
+    fn unflagged_expiries_are_weekly_unless_last_of_month() {
+        let underlying_key: InstrumentKey = UNDERLYING_KEY.parse().unwrap();
+        let mut calendar: ExpiryCalendar = ExpiryCalendar::default();
+        calendar
+            .add_expiries(
+                &underlying_key,
+                &["2025-09-23".to_string(), "2025-09-30".to_string()],
+            )
+            .unwrap();
+        let kinds: Vec<ExpiryKind> = calendar
+            .expiries(&underlying_key)
+            .into_iter()
+            .map(|expiry| expiry.kind)
+            .collect();
+        assert_eq!(kinds, vec![ExpiryKind::Weekly, ExpiryKind::Quarterly]);
+    }
+
+    #[test]
+    fn monthly_expiries_include_quarterly_ones() {
+        let underlying_key: InstrumentKey = UNDERLYING_KEY.parse().unwrap();
+        let dates: Vec<NaiveDate> = calendar()
+            .expiries_of_kind(&underlying_key, ExpiryKind::Monthly)
+            .into_iter()
+            .map(|expiry| expiry.date)
+            .collect();
+        assert_eq!(
+            dates,
+            vec![date(2025, 5, 29), date(2025, 6, 26), date(2025, 8, 28)]
+        );
+    }
+
+    #[test]
+    fn resolves_relative_expiries_from_today() {
+        let underlying_key: InstrumentKey = UNDERLYING_KEY.parse().unwrap();
+        let calendar: ExpiryCalendar = calendar();
+        let resolve = |relative_expiry: RelativeExpiry| -> Option<NaiveDate> {
+            calendar
+                .resolve(&underlying_key, relative_expiry, date(2025, 5, 29))
+                .map(|expiry| expiry.date)
+        };
+        assert_eq!(
+            resolve(RelativeExpiry::CurrentWeek),
+            Some(date(2025, 5, 29))
+        );
+        assert_eq!(resolve(RelativeExpiry::NextWeek), Some(date(2025, 6, 5)));
+        assert_eq!(resolve(RelativeExpiry::NextMonth), Some(date(2025, 6, 26)));
+        assert_eq!(resolve(RelativeExpiry::FarMonth), Some(date(2025, 8, 28)));
+        assert_eq!(
+            resolve(RelativeExpiry::CurrentQuarter),
+            Some(date(2025, 6, 26))
+        );
+    }
+}
@@ -0,0 +1,4 @@
+pub mod diff;
+pub mod expiry_calendar;
+pub mod search;
+pub mod store;
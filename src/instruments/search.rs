@@ -0,0 +1,245 @@
+use {
+    crate::{
+        models::{ExchangeSegment, instruments::instruments_response::InstrumentsResponse},
+        utils::ist_offset,
+    },
+    chrono::{NaiveDate, Utc},
+    std::{
+        cmp::Reverse,
+        collections::{BTreeMap, HashMap},
+    },
+};
+
+const EXACT_TOKEN_SCORE: u32 = 3;
+const PREFIX_TOKEN_SCORE: u32 = 1;
+const EXACT_SYMBOL_SCORE: u32 = 5;
+
+// Common shorthands for index derivatives
+const ALIASES: [(&str, &str); 5] = [
+    ("BNF", "BANKNIFTY"),
+    ("NF", "NIFTY"),
+    ("FNF", "FINNIFTY"),
+    ("MCN", "MIDCPNIFTY"),
+    ("SNX", "SENSEX"),
+];
+
+// Inverted index from tokens of name, trading_symbol, short_name and underlying_symbol to positions in the store
+#[derive(Debug, Default, Clone)]
+pub struct SearchIndex {
+    tokens: BTreeMap<String, Vec<usize>>,
+}
+
+impl SearchIndex {
+    pub fn insert(&mut self, index: usize, instrument: &InstrumentsResponse) {
+        let mut tokens: Vec<String> = [
+            Some(instrument.name()),
+            Some(instrument.trading_symbol().as_str()),
+            instrument.short_name(),
+            instrument.underlying_symbol(),
+        ]
+        .into_iter()
+        .flatten()
+        .flat_map(tokenize)
+        .collect();
+        tokens.sort();
+        tokens.dedup();
+        for token in tokens {
+            self.tokens.entry(token).or_default().push(index);
+        }
+    }
+
+    // Every token of the query has to match a token of the instrument exactly or as a prefix. Results are ranked by match
+    // quality, then by segment so that cash and index instruments come before derivatives, then by nearest expiry.
+    pub fn search<'a>(
+        &self,
+        instruments: &'a [InstrumentsResponse],
+        query: &str,
+        limit: usize,
+    ) -> Vec<&'a InstrumentsResponse> {
+        let query_tokens: Vec<String> = tokenize(query)
+            .into_iter()
+            .map(|token| {
+                ALIASES
+                    .iter()
+                    .find(|(alias, _)| *alias == token)
+                    .map(|(_, expansion)| expansion.to_string())
+                    .unwrap_or(token)
+            })
+            .collect();
+        if query_tokens.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+
+        let mut scores: Option<HashMap<usize, u32>> = None;
+        for query_token in &query_tokens {
+            let mut token_scores: HashMap<usize, u32> = HashMap::new();
+            for (token, indexes) in self
+                .tokens
+                .range(query_token.clone()..)
+                .take_while(|(token, _)| token.starts_with(query_token.as_str()))
+            {
+                let token_score: u32 = match token == query_token {
+                    true => EXACT_TOKEN_SCORE,
+                    false => PREFIX_TOKEN_SCORE,
+                };
+                for &index in indexes {
+                    let score: &mut u32 = token_scores.entry(index).or_default();
+                    *score = (*score).max(token_score);
+                }
+            }
+            scores = Some(match scores {
+                None => token_scores,
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(index, score)| {
+                        token_scores
+                            .get(&index)
+                            .map(|token_score| (index, score + token_score))
+                    })
+                    .collect(),
+            });
+        }
+
+        let joined_query: String = query_tokens.concat();
+        let today: NaiveDate = Utc::now().with_timezone(&ist_offset()).date_naive();
+        let mut results: Vec<(u32, &InstrumentsResponse)> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(index, score)| {
+                let instrument: &InstrumentsResponse = &instruments[index];
+                match instrument.trading_symbol().as_str().to_uppercase() == joined_query {
+                    true => (score + EXACT_SYMBOL_SCORE, instrument),
+                    false => (score, instrument),
+                }
+            })
+            .collect();
+        results.sort_by_key(|(score, instrument)| {
+            (
+                Reverse(*score),
+                segment_priority(instrument.segment()),
+                // Contracts that have expired sort after the live ones
+                instrument
+                    .expiry_date()
+                    .map(|expiry| (expiry < today, expiry)),
+                instrument.trading_symbol().clone(),
+            )
+        });
+        results
+            .into_iter()
+            .take(limit)
+            .map(|(_, instrument)| instrument)
+            .collect()
+    }
+}
+
+// Uppercased alphanumeric runs, split further where letters and digits meet so that BANKNIFTY24DEC48000PE yields
+// BANKNIFTY, 24, DEC, 48000 and PE
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut current: String = String::new();
+    for c in text.chars() {
+        let boundary: bool = match current.chars().last() {
+            Some(last) => !c.is_alphanumeric() || last.is_ascii_digit() != c.is_ascii_digit(),
+            None => false,
+        };
+        if boundary {
+            tokens.push(std::mem::take(&mut current));
+        }
+        if c.is_alphanumeric() {
+            current.extend(c.to_uppercase());
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn segment_priority(segment: ExchangeSegment) -> u8 {
+    match segment {
+        ExchangeSegment::NseEq => 0,
+        ExchangeSegment::NseIndex => 1,
+        ExchangeSegment::BseEq => 2,
+        ExchangeSegment::BseIndex => 3,
+        ExchangeSegment::NseFo => 4,
+        ExchangeSegment::BseFo => 5,
+        ExchangeSegment::McxFo => 6,
+        ExchangeSegment::McxIndex => 7,
+        ExchangeSegment::NseCom => 8,
+        ExchangeSegment::NcdFo => 9,
+        ExchangeSegment::BcdFo => 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::models::instruments::fixtures::equity};
+
+    fn search(trading_symbols: &[&str], query: &str) -> Vec<String> {
+        let instruments: Vec<InstrumentsResponse> = trading_symbols
+            .iter()
+            .enumerate()
+            .map(|(index, trading_symbol)| {
+                equity(
+                    &index.to_string(),
+                    &format!("INE{:09}", index),
+                    trading_symbol,
+                    1,
+                )
+            })
+            .collect();
+        let mut search_index: SearchIndex = SearchIndex::default();
+        for (index, instrument) in instruments.iter().enumerate() {
+            search_index.insert(index, instrument);
+        }
+        search_index
+            .search(&instruments, query, 10)
+            .into_iter()
+            .map(|instrument| instrument.trading_symbol().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn tokenizes_alphanumeric_runs_split_at_digits() {
+        assert_eq!(
+            tokenize("BANKNIFTY24DEC48000PE"),
+            vec!["BANKNIFTY", "24", "DEC", "48000", "PE"]
+        );
+        assert_eq!(tokenize("m&m fin-serv"), vec!["M", "M", "FIN", "SERV"]);
+        assert!(tokenize(" -&").is_empty());
+    }
+
+    #[test]
+    fn matches_token_prefixes() {
+        assert_eq!(
+            search(&["RELIANCE", "RELAXO", "INFY"], "rel"),
+            vec!["RELAXO", "RELIANCE"]
+        );
+        assert_eq!(
+            search(&["RELIANCE", "RELAXO", "INFY"], "relia"),
+            vec!["RELIANCE"]
+        );
+    }
+
+    #[test]
+    fn requires_every_query_token_to_match() {
+        assert!(search(&["RELIANCE", "RELAXO"], "reliance xyz").is_empty());
+        assert!(search(&["RELIANCE"], "").is_empty());
+    }
+
+    #[test]
+    fn ranks_exact_matches_before_prefix_matches() {
+        assert_eq!(
+            search(&["TATAMOTORS", "TATA"], "tata"),
+            vec!["TATA", "TATAMOTORS"]
+        );
+    }
+
+    #[test]
+    fn expands_index_aliases() {
+        let trading_symbols: [&str; 3] = ["BANKNIFTY", "NIFTY", "FINNIFTY"];
+        assert_eq!(search(&trading_symbols, "bnf"), vec!["BANKNIFTY"]);
+        assert_eq!(search(&trading_symbols, "nf"), vec!["NIFTY"]);
+        assert_eq!(search(&trading_symbols, "FNF"), vec!["FINNIFTY"]);
+    }
+}
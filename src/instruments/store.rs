@@ -0,0 +1,270 @@
+use {
+    crate::{
+        instruments::search::SearchIndex,
+        models::{
+            Exchange, ExchangeSegment, instrument_key::InstrumentKey,
+            instruments::instruments_response::InstrumentsResponse, trading_symbol::TradingSymbol,
+        },
+    },
+    chrono::NaiveDate,
+    std::collections::HashMap,
+};
+
+// Instrument master indexed for constant time lookups. Indexes hold positions into instruments, which is never reordered.
+#[derive(Debug, Default, Clone)]
+pub struct InstrumentStore {
+    instruments: Vec<InstrumentsResponse>,
+    by_key: HashMap<InstrumentKey, usize>,
+    by_trading_symbol: HashMap<(Exchange, TradingSymbol), Vec<usize>>,
+    by_isin: HashMap<String, Vec<usize>>,
+    by_exchange_token: HashMap<(ExchangeSegment, String), usize>,
+    by_underlying: HashMap<InstrumentKey, Vec<usize>>,
+    by_segment: HashMap<ExchangeSegment, HashMap<String, Vec<usize>>>,
+    search_index: SearchIndex,
+}
+
+impl InstrumentStore {
+    pub fn new(instruments: Vec<InstrumentsResponse>) -> Self {
+        let mut store: InstrumentStore = InstrumentStore::default();
+        store.extend(instruments);
+        store
+    }
+
+    // Instruments already present with the same key are replaced. New instruments are indexed as they are added, the
+    // indexes are only rebuilt when an instrument is replaced.
+    pub fn extend(&mut self, instruments: impl IntoIterator<Item = InstrumentsResponse>) {
+        let mut replaced: bool = false;
+        for instrument in instruments {
+            match self.by_key.get(instrument.instrument_key()) {
+                Some(&index) => {
+                    self.instruments[index] = instrument;
+                    replaced = true;
+                }
+                None => {
+                    let index: usize = self.instruments.len();
+                    self.index(index, &instrument);
+                    self.instruments.push(instrument);
+                }
+            }
+        }
+        if replaced {
+            self.rebuild_indexes();
+        }
+    }
+
+    fn rebuild_indexes(&mut self) {
+        let instruments: Vec<InstrumentsResponse> = std::mem::take(&mut self.instruments);
+        *self = InstrumentStore::default();
+        for (index, instrument) in instruments.iter().enumerate() {
+            self.index(index, instrument);
+        }
+        self.instruments = instruments;
+    }
+
+    fn index(&mut self, index: usize, instrument: &InstrumentsResponse) {
+        self.by_key
+            .insert(instrument.instrument_key().clone(), index);
+        self.by_trading_symbol
+            .entry((instrument.exchange(), instrument.trading_symbol().clone()))
+            .or_default()
+            .push(index);
+        if let Some(isin) = instrument.isin() {
+            self.by_isin
+                .entry(isin.to_uppercase())
+                .or_default()
+                .push(index);
+        }
+        if let Some(exchange_token) = instrument.exchange_token() {
+            self.by_exchange_token
+                .insert((instrument.segment(), exchange_token.to_string()), index);
+        }
+        if let Some(underlying_key) = instrument.underlying_key() {
+            self.by_underlying
+                .entry(underlying_key)
+                .or_default()
+                .push(index);
+        }
+        self.by_segment
+            .entry(instrument.segment())
+            .or_default()
+            .entry(instrument.instrument_type().to_string())
+            .or_default()
+            .push(index);
+        self.search_index.insert(index, instrument);
+    }
+
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &InstrumentsResponse> {
+        self.instruments.iter()
+    }
+
+    pub fn get(&self, instrument_key: &InstrumentKey) -> Option<&InstrumentsResponse> {
+        self.by_key
+            .get(instrument_key)
+            .map(|&index| &self.instruments[index])
+    }
+
+    // The same symbol can be listed in more than one segment of an exchange, e.g. as an index and an equity.
+    // The one loaded first is returned, use get_all_by_trading_symbol to get all of them.
+    pub fn get_by_trading_symbol(
+        &self,
+        exchange: Exchange,
+        trading_symbol: &TradingSymbol,
+    ) -> Option<&InstrumentsResponse> {
+        self.get_all_by_trading_symbol(exchange, trading_symbol)
+            .into_iter()
+            .next()
+    }
+
+    pub fn get_all_by_trading_symbol(
+        &self,
+        exchange: Exchange,
+        trading_symbol: &TradingSymbol,
+    ) -> Vec<&InstrumentsResponse> {
+        self.collect(
+            self.by_trading_symbol
+                .get(&(exchange, trading_symbol.clone())),
+        )
+    }
+
+    // Equities listed on both NSE and BSE share the ISIN
+    pub fn get_by_isin(&self, isin: &str) -> Vec<&InstrumentsResponse> {
+        self.collect(self.by_isin.get(&isin.to_uppercase()))
+    }
+
+    pub fn get_by_exchange_token(
+        &self,
+        segment: ExchangeSegment,
+        exchange_token: &str,
+    ) -> Option<&InstrumentsResponse> {
+        self.by_exchange_token
+            .get(&(segment, exchange_token.to_string()))
+            .map(|&index| &self.instruments[index])
+    }
+
+    pub fn get_by_segment(
+        &self,
+        segment: ExchangeSegment,
+        instrument_type: &str,
+    ) -> Vec<&InstrumentsResponse> {
+        self.collect(
+            self.by_segment
+                .get(&segment)
+                .and_then(|segment_map| segment_map.get(instrument_type)),
+        )
+    }
+
+    // Futures and options on the underlying, e.g. NSE_INDEX|Nifty 50 or NSE_EQ|INE002A01018
+    pub fn derivatives(&self, underlying_key: &InstrumentKey) -> Vec<&InstrumentsResponse> {
+        self.collect(self.by_underlying.get(underlying_key))
+    }
+
+    // Sorted expiry dates of the derivatives on the underlying
+    pub fn expiries(&self, underlying_key: &InstrumentKey) -> Vec<NaiveDate> {
+        let mut expiries: Vec<NaiveDate> = self
+            .derivatives(underlying_key)
+            .iter()
+            .filter_map(|instrument| instrument.expiry_date())
+            .collect();
+        expiries.sort();
+        expiries.dedup();
+        expiries
+    }
+
+    pub fn contracts_by_expiry(
+        &self,
+        underlying_key: &InstrumentKey,
+        expiry: NaiveDate,
+    ) -> Vec<&InstrumentsResponse> {
+        self.derivatives(underlying_key)
+            .into_iter()
+            .filter(|instrument| instrument.expiry_date() == Some(expiry))
+            .collect()
+    }
+
+    // Option of the given instrument type (CE or PE) whose strike is closest to the price. Ties go to the lower strike.
+    pub fn nearest_strike(
+        &self,
+        underlying_key: &InstrumentKey,
+        expiry: NaiveDate,
+        instrument_type: &str,
+        price: f64,
+    ) -> Option<&InstrumentsResponse> {
+        self.contracts_by_expiry(underlying_key, expiry)
+            .into_iter()
+            .filter(|instrument| {
+                instrument
+                    .instrument_type()
+                    .eq_ignore_ascii_case(instrument_type)
+            })
+            .filter_map(|instrument| {
+                instrument
+                    .strike_price()
+                    .map(|strike_price| (instrument, strike_price))
+            })
+            .min_by(|(_, a), (_, b)| {
+                (a - price)
+                    .abs()
+                    .total_cmp(&(b - price).abs())
+                    .then(a.total_cmp(b))
+            })
+            .map(|(instrument, _)| instrument)
+    }
+
+    // Free text search such as "hdfc bank" or "bnf 48000 pe", best matches first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&InstrumentsResponse> {
+        self.search_index.search(&self.instruments, query, limit)
+    }
+
+    fn collect(&self, indexes: Option<&Vec<usize>>) -> Vec<&InstrumentsResponse> {
+        indexes
+            .map(|indexes| {
+                indexes
+                    .iter()
+                    .map(|&index| &self.instruments[index])
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Extend<InstrumentsResponse> for InstrumentStore {
+    fn extend<T: IntoIterator<Item = InstrumentsResponse>>(&mut self, instruments: T) {
+        InstrumentStore::extend(self, instruments);
+    }
+}
+
+impl IntoIterator for InstrumentStore {
+    type Item = InstrumentsResponse;
+    type IntoIter = std::vec::IntoIter<InstrumentsResponse>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.instruments.into_iter()
+    }
+}
+
+impl From<Vec<InstrumentsResponse>> for InstrumentStore {
+    fn from(instruments: Vec<InstrumentsResponse>) -> Self {
+        InstrumentStore::new(instruments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::models::instruments::fixtures::equity};
+
+    #[test]
+    fn finds_isin_ignoring_case() {
+        let store: InstrumentStore =
+            InstrumentStore::new(vec![equity("2885", "ine002a01018", "RELIANCE", 1)]);
+        assert_eq!(store.get_by_isin("INE002A01018").len(), 1);
+        assert_eq!(store.get_by_isin("ine002a01018").len(), 1);
+    }
+}
@@ -3,11 +3,17 @@
 //! Upstox API is a set of rest APIs that provide data required to build a complete investment and trading platform. Execute orders in real time, manage user portfolio, stream live market data (using Websockets), and a lot more with this crate.
 
 //! Refer to [`client`] for usage guides.
+pub mod account_manager;
 mod apis;
+pub mod audit;
 pub mod client;
 pub mod constants;
+pub mod instruments;
 pub mod models;
+pub mod orders;
 pub mod protos;
 pub mod rate_limiter;
+pub mod redirect_listener;
+pub mod token_store;
 mod utils;
 pub mod ws_client;
@@ -3,7 +3,15 @@ use {
     serde::{Deserialize, Serialize},
 };
 
-#[derive(Deserialize, Serialize, Debug)]
+// Error code of errors raised by the client itself before any request is sent
+pub const CLIENT_ERROR_CODE: &str = "CLIENT_ERROR";
+// Error code of requests which failed to be sent or whose response could not be read, so the server may or may not have
+// acted on them
+pub const TRANSPORT_ERROR_CODE: &str = "TRANSPORT_ERROR";
+// Not sent by servers, marks responses made up for transport errors
+pub const TRANSPORT_ERROR_STATUS: u16 = 599;
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Errors {
     pub error_code: String,
     pub message: String,
@@ -11,6 +19,7 @@ pub struct Errors {
     pub invalid_value: Option<String>,
     pub order_id: Option<String>,
     pub instrument_key: Option<String>,
+    pub correlation_id: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -20,3 +29,44 @@ pub struct ErrorResponse {
     pub errors: Vec<Errors>,
     pub summary: Option<ResponseSummary>,
 }
+
+impl ErrorResponse {
+    pub fn client_error(message: String) -> Self {
+        Self::with_code(CLIENT_ERROR_CODE, message)
+    }
+
+    pub fn transport_error(message: String) -> Self {
+        Self::with_code(TRANSPORT_ERROR_CODE, message)
+    }
+
+    pub fn is_transport_error(&self) -> bool {
+        self.errors
+            .iter()
+            .any(|error| error.error_code == TRANSPORT_ERROR_CODE)
+    }
+
+    fn with_code(error_code: &str, message: String) -> Self {
+        ErrorResponse {
+            status: "error".to_string(),
+            data: None,
+            errors: vec![Errors {
+                error_code: error_code.to_string(),
+                message,
+                property_path: None,
+                invalid_value: None,
+                order_id: None,
+                instrument_key: None,
+                correlation_id: None,
+            }],
+            summary: None,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        self.errors
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect::<Vec<&str>>()
+            .join("; ")
+    }
+}
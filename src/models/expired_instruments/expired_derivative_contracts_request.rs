@@ -1,12 +1,8 @@
-use {serde::Serialize, serde_valid::Validate};
+use {crate::models::instrument_key::InstrumentKey, serde::Serialize, serde_valid::Validate};
 
 #[derive(Serialize, Debug, Validate)]
 pub struct ExpiredDerivativeContractsRequest {
-    #[validate(
-        pattern = r"^(?:^NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+(,(?:NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+)*?$",
-        message = "Invalid instrument_key"
-    )]
-    pub instrument_key: String,
+    pub instrument_key: InstrumentKey,
     #[validate(
         pattern = r"^\d{4}\-(0[1-9]|1[012])\-(0[1-9]|[12][0-9]|3[01])$",
         message = "expiry_date format must be yyyy-mm-dd"
@@ -1,10 +1,6 @@
-use {serde::Serialize, serde_valid::Validate};
+use {crate::models::instrument_key::InstrumentKey, serde::Serialize, serde_valid::Validate};
 
 #[derive(Serialize, Debug, Validate)]
 pub struct ExpiriesRequest {
-    #[validate(
-        pattern = r"^(?:^NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+(,(?:NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+)*?$",
-        message = "Invalid instrument_key"
-    )]
-    pub instrument_key: String,
+    pub instrument_key: InstrumentKey,
 }
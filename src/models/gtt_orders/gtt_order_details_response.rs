@@ -2,6 +2,8 @@ use {
     crate::models::{
         ProductType,
         gtt_orders::{GTTOrderDetailsRule, GTTOrderType},
+        instrument_key::InstrumentKey,
+        trading_symbol::TradingSymbol,
     },
     serde::{Deserialize, Serialize},
 };
@@ -13,8 +15,8 @@ pub struct GTTOrderDetailsResponse {
     pub exchange: String,
     pub quantity: u32,
     pub product: ProductType,
-    pub instrument_token: String,
-    pub trading_symbol: String,
+    pub instrument_token: InstrumentKey,
+    pub trading_symbol: TradingSymbol,
     pub gtt_order_id: String,
     pub expires_at: u64,
     pub created_at: u64,
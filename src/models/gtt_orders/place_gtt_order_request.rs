@@ -1,7 +1,8 @@
 use {
     crate::models::{
-        gtt_orders::{validate_product_type, GTTOrderRule, GTTOrderType},
         ProductType, TransactionType,
+        gtt_orders::{GTTOrderRule, GTTOrderType, validate_product_type},
+        instrument_key::InstrumentKey,
     },
     serde::Serialize,
     serde_valid::Validate,
@@ -15,11 +16,7 @@ pub struct PlaceGTTOrderRequest {
     pub quantity: u32,
     #[validate(custom(validate_product_type))]
     pub product: ProductType,
-    #[validate(
-        pattern = r"^(?:^NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+(,(?:NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+)*?$",
-        message = "Invalid instrument_token"
-    )]
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
     pub transaction_type: TransactionType,
     pub rules: Vec<GTTOrderRule>,
 }
@@ -1,5 +1,8 @@
 use {
-    crate::models::historical_data::{Unit, validate_interval},
+    crate::models::{
+        historical_data::{Unit, validate_interval},
+        instrument_key::InstrumentKey,
+    },
     serde::Serialize,
     serde_valid::Validate,
 };
@@ -7,11 +10,7 @@ use {
 #[derive(Serialize, Debug, Validate)]
 #[validate(custom = |s| validate_interval(&s.unit, &s.interval))]
 pub struct HistoricalCandleDataV3Request {
-    #[validate(
-        pattern = r"^(?:^NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+(,(?:NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+)*?$",
-        message = "Invalid instrument_key"
-    )]
-    pub instrument_key: String,
+    pub instrument_key: InstrumentKey,
     pub unit: Unit,
     pub interval: String,
     #[validate(
@@ -0,0 +1,113 @@
+use {
+    crate::models::ExchangeSegment,
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    std::{
+        fmt::{self, Display},
+        str::FromStr,
+    },
+};
+
+// Instrument key of the form SEGMENT|token, e.g. NSE_EQ|INE848E01016 or NSE_INDEX|Nifty 50
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InstrumentKey {
+    segment: ExchangeSegment,
+    token: String,
+}
+
+impl InstrumentKey {
+    pub fn new(segment: ExchangeSegment, token: impl Into<String>) -> Result<Self, &'static str> {
+        let token: String = token.into();
+        if token.trim().is_empty() {
+            return Err("Instrument key token must not be empty");
+        }
+        if token.contains(['|', ',']) {
+            return Err("Instrument key token must not contain '|' or ','");
+        }
+        Ok(Self { segment, token })
+    }
+
+    pub fn segment(&self) -> ExchangeSegment {
+        self.segment
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl Display for InstrumentKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}|{}", self.segment, self.token)
+    }
+}
+
+impl FromStr for InstrumentKey {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (segment, token) = s
+            .split_once('|')
+            .ok_or("Instrument key must be of the form SEGMENT|token")?;
+        InstrumentKey::new(segment.parse::<ExchangeSegment>()?, token)
+    }
+}
+
+impl Serialize for InstrumentKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for InstrumentKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_segment_and_token() {
+        let instrument_key: InstrumentKey = "NSE_INDEX|Nifty 50".parse().unwrap();
+        assert_eq!(instrument_key.segment(), ExchangeSegment::NseIndex);
+        assert_eq!(instrument_key.token(), "Nifty 50");
+        assert_eq!(instrument_key.to_string(), "NSE_INDEX|Nifty 50");
+    }
+
+    #[test]
+    fn rejects_separator_in_token() {
+        assert_eq!(
+            "NSE_EQ|INE848E01016|X".parse::<InstrumentKey>(),
+            Err("Instrument key token must not contain '|' or ','")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert_eq!(
+            "INE848E01016".parse::<InstrumentKey>(),
+            Err("Instrument key must be of the form SEGMENT|token")
+        );
+        assert_eq!(
+            "NSE|INE848E01016".parse::<InstrumentKey>(),
+            Err("Invalid exchange segment")
+        );
+        assert_eq!(
+            "NSE_EQ| ".parse::<InstrumentKey>(),
+            Err("Instrument key token must not be empty")
+        );
+        assert_eq!(
+            "NSE_EQ|A,B".parse::<InstrumentKey>(),
+            Err("Instrument key token must not contain '|' or ','")
+        );
+    }
+}
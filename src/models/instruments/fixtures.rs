@@ -0,0 +1,58 @@
+use {
+    crate::models::instruments::instruments_response::InstrumentsResponse, chrono::NaiveDate,
+    serde_json::json,
+};
+
+// NSE equity with a tick size of 10 paise
+pub(crate) fn equity(
+    token: &str,
+    isin: &str,
+    trading_symbol: &str,
+    lot_size: u32,
+) -> InstrumentsResponse {
+    serde_json::from_value(json!({
+        "segment": "NSE_EQ",
+        "name": trading_symbol,
+        "exchange": "NSE",
+        "isin": isin,
+        "instrument_type": "EQ",
+        "instrument_key": format!("NSE_EQ|{}", token),
+        "lot_size": lot_size,
+        "freeze_quantity": 100000.0,
+        "exchange_token": token,
+        "tick_size": 10.0,
+        "trading_symbol": trading_symbol
+    }))
+    .unwrap()
+}
+
+// NSE F&O contract with a lot size of 75 and freeze quantity of 1800, expiring at 15:30 IST on the date
+pub(crate) fn derivative(
+    token: &str,
+    instrument_type: &str,
+    underlying_key: &str,
+    expiry: NaiveDate,
+    weekly: bool,
+) -> InstrumentsResponse {
+    serde_json::from_value(json!({
+        "weekly": weekly,
+        "segment": "NSE_FO",
+        "name": "NIFTY",
+        "exchange": "NSE",
+        "expiry": expiry.and_hms_opt(10, 0, 0).unwrap().and_utc().timestamp_millis(),
+        "instrument_type": instrument_type,
+        "asset_symbol": "NIFTY",
+        "underlying_symbol": "NIFTY",
+        "instrument_key": format!("NSE_FO|{}", token),
+        "lot_size": 75,
+        "freeze_quantity": 1800.0,
+        "exchange_token": token,
+        "underlying_key": underlying_key,
+        "tick_size": 10.0,
+        "asset_type": "INDEX",
+        "underlying_type": "INDEX",
+        "trading_symbol": format!("NIFTY {} {}", token, instrument_type),
+        "strike_price": 0.0
+    }))
+    .unwrap()
+}
@@ -1,5 +1,12 @@
 use {
-    crate::models::{AssetType, Exchange, ExchangeSegment},
+    crate::{
+        models::{
+            AssetType, Exchange, ExchangeSegment, instrument_key::InstrumentKey,
+            trading_symbol::TradingSymbol,
+        },
+        utils::ist_offset,
+    },
+    chrono::{DateTime, NaiveDate},
     serde::{Deserialize, Serialize},
 };
 
@@ -12,12 +19,12 @@ pub enum InstrumentsResponse {
         exchange: Exchange,
         isin: String,
         instrument_type: String,
-        instrument_key: String,
+        instrument_key: InstrumentKey,
         lot_size: u32,
         freeze_quantity: f64,
         exchange_token: String,
         tick_size: f64,
-        trading_symbol: String,
+        trading_symbol: TradingSymbol,
         short_name: Option<String>,
         security_type: Option<String>,
         qty_multiplier: Option<f64>,
@@ -31,7 +38,7 @@ pub enum InstrumentsResponse {
         instrument_type: String,
         asset_symbol: String,
         underlying_symbol: String,
-        instrument_key: String,
+        instrument_key: InstrumentKey,
         lot_size: u32,
         freeze_quantity: f64,
         exchange_token: String,
@@ -41,7 +48,7 @@ pub enum InstrumentsResponse {
         tick_size: f64,
         asset_type: AssetType,
         underlying_type: AssetType,
-        trading_symbol: String,
+        trading_symbol: TradingSymbol,
         strike_price: f64,
         last_trading_date: Option<u64>,
         price_quote_unit: Option<String>,
@@ -52,23 +59,192 @@ pub enum InstrumentsResponse {
         name: String,
         exchange: Exchange,
         instrument_type: String,
-        instrument_key: String,
+        instrument_key: InstrumentKey,
         exchange_token: Option<String>,
-        trading_symbol: String,
+        trading_symbol: TradingSymbol,
     },
     CommodityResponse {
         segment: ExchangeSegment,
         name: String,
         exchange: Exchange,
         instrument_type: String,
-        instrument_key: String,
+        instrument_key: InstrumentKey,
         lot_size: u32,
         freeze_quantity: f64,
         exchange_token: String,
         tick_size: f64,
-        trading_symbol: String,
+        trading_symbol: TradingSymbol,
         strike_price: f64,
         price_quote_unit: Option<String>,
         qty_multiplier: f64,
     },
 }
+
+impl InstrumentsResponse {
+    pub fn segment(&self) -> ExchangeSegment {
+        match self {
+            InstrumentsResponse::EquityResponse { segment, .. }
+            | InstrumentsResponse::DerivativeResponse { segment, .. }
+            | InstrumentsResponse::IndexResponse { segment, .. }
+            | InstrumentsResponse::CommodityResponse { segment, .. } => *segment,
+        }
+    }
+
+    pub fn exchange(&self) -> Exchange {
+        match self {
+            InstrumentsResponse::EquityResponse { exchange, .. }
+            | InstrumentsResponse::DerivativeResponse { exchange, .. }
+            | InstrumentsResponse::IndexResponse { exchange, .. }
+            | InstrumentsResponse::CommodityResponse { exchange, .. } => *exchange,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            InstrumentsResponse::EquityResponse { name, .. }
+            | InstrumentsResponse::DerivativeResponse { name, .. }
+            | InstrumentsResponse::IndexResponse { name, .. }
+            | InstrumentsResponse::CommodityResponse { name, .. } => name,
+        }
+    }
+
+    pub fn instrument_type(&self) -> &str {
+        match self {
+            InstrumentsResponse::EquityResponse {
+                instrument_type, ..
+            }
+            | InstrumentsResponse::DerivativeResponse {
+                instrument_type, ..
+            }
+            | InstrumentsResponse::IndexResponse {
+                instrument_type, ..
+            }
+            | InstrumentsResponse::CommodityResponse {
+                instrument_type, ..
+            } => instrument_type,
+        }
+    }
+
+    pub fn instrument_key(&self) -> &InstrumentKey {
+        match self {
+            InstrumentsResponse::EquityResponse { instrument_key, .. }
+            | InstrumentsResponse::DerivativeResponse { instrument_key, .. }
+            | InstrumentsResponse::IndexResponse { instrument_key, .. }
+            | InstrumentsResponse::CommodityResponse { instrument_key, .. } => instrument_key,
+        }
+    }
+
+    pub fn trading_symbol(&self) -> &TradingSymbol {
+        match self {
+            InstrumentsResponse::EquityResponse { trading_symbol, .. }
+            | InstrumentsResponse::DerivativeResponse { trading_symbol, .. }
+            | InstrumentsResponse::IndexResponse { trading_symbol, .. }
+            | InstrumentsResponse::CommodityResponse { trading_symbol, .. } => trading_symbol,
+        }
+    }
+
+    pub fn exchange_token(&self) -> Option<&str> {
+        match self {
+            InstrumentsResponse::EquityResponse { exchange_token, .. }
+            | InstrumentsResponse::DerivativeResponse { exchange_token, .. }
+            | InstrumentsResponse::CommodityResponse { exchange_token, .. } => Some(exchange_token),
+            InstrumentsResponse::IndexResponse { exchange_token, .. } => exchange_token.as_deref(),
+        }
+    }
+
+    pub fn isin(&self) -> Option<&str> {
+        match self {
+            InstrumentsResponse::EquityResponse { isin, .. } => Some(isin),
+            _ => None,
+        }
+    }
+
+    pub fn short_name(&self) -> Option<&str> {
+        match self {
+            InstrumentsResponse::EquityResponse { short_name, .. } => short_name.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn underlying_symbol(&self) -> Option<&str> {
+        match self {
+            InstrumentsResponse::DerivativeResponse {
+                underlying_symbol, ..
+            } => Some(underlying_symbol),
+            _ => None,
+        }
+    }
+
+    // Underlying keys that are not valid instrument keys are treated as missing
+    pub fn underlying_key(&self) -> Option<InstrumentKey> {
+        match self {
+            InstrumentsResponse::DerivativeResponse { underlying_key, .. } => underlying_key
+                .as_deref()
+                .and_then(|underlying_key| underlying_key.parse().ok()),
+            _ => None,
+        }
+    }
+
+    // Expiry in milliseconds since epoch
+    pub fn expiry(&self) -> Option<u64> {
+        match self {
+            InstrumentsResponse::DerivativeResponse { expiry, .. } => Some(*expiry),
+            _ => None,
+        }
+    }
+
+    // Expiry date in IST
+    pub fn expiry_date(&self) -> Option<NaiveDate> {
+        self.expiry()
+            .and_then(|expiry| DateTime::from_timestamp_millis(expiry as i64))
+            .map(|expiry| expiry.with_timezone(&ist_offset()).date_naive())
+    }
+
+    pub fn weekly(&self) -> Option<bool> {
+        match self {
+            InstrumentsResponse::DerivativeResponse { weekly, .. } => *weekly,
+            _ => None,
+        }
+    }
+
+    pub fn strike_price(&self) -> Option<f64> {
+        match self {
+            InstrumentsResponse::DerivativeResponse { strike_price, .. }
+            | InstrumentsResponse::CommodityResponse { strike_price, .. } => Some(*strike_price),
+            _ => None,
+        }
+    }
+
+    pub fn lot_size(&self) -> Option<u32> {
+        match self {
+            InstrumentsResponse::EquityResponse { lot_size, .. }
+            | InstrumentsResponse::DerivativeResponse { lot_size, .. }
+            | InstrumentsResponse::CommodityResponse { lot_size, .. } => Some(*lot_size),
+            InstrumentsResponse::IndexResponse { .. } => None,
+        }
+    }
+
+    pub fn tick_size(&self) -> Option<f64> {
+        match self {
+            InstrumentsResponse::EquityResponse { tick_size, .. }
+            | InstrumentsResponse::DerivativeResponse { tick_size, .. }
+            | InstrumentsResponse::CommodityResponse { tick_size, .. } => Some(*tick_size),
+            InstrumentsResponse::IndexResponse { .. } => None,
+        }
+    }
+
+    pub fn freeze_quantity(&self) -> Option<f64> {
+        match self {
+            InstrumentsResponse::EquityResponse {
+                freeze_quantity, ..
+            }
+            | InstrumentsResponse::DerivativeResponse {
+                freeze_quantity, ..
+            }
+            | InstrumentsResponse::CommodityResponse {
+                freeze_quantity, ..
+            } => Some(*freeze_quantity),
+            InstrumentsResponse::IndexResponse { .. } => None,
+        }
+    }
+}
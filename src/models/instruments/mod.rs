@@ -1 +1,3 @@
+#[cfg(test)]
+pub(crate) mod fixtures;
 pub mod instruments_response;
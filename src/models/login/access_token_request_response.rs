@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AccessTokenRequestResponse {
+    pub authorization_expiry: String, // Epoch milliseconds until which the request can be approved
+    pub notifier_url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageType {
+    AccessToken,
+}
+
+// Payload posted to the notifier webhook once the request is approved in the Upstox app
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AccessTokenNotification {
+    pub client_id: String,
+    pub user_id: String,
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_at: String, // Epoch milliseconds
+    pub issued_at: String,  // Epoch milliseconds
+    pub message_type: MessageType,
+}
@@ -0,0 +1,63 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseType {
+    Code,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseMode {
+    Query,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Prompt {
+    Login,
+    Consent,
+    SelectAccount,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum GrantType {
+    AuthorizationCode,
+    RefreshToken,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MicrosoftOAuth2AuthRequest {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub response_type: ResponseType,
+    pub scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_mode: Option<ResponseMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub login_hint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<Prompt>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MicrosoftOAuth2CodeTokenRequest {
+    pub client_id: String,
+    pub client_secret: String,
+    pub code: String,
+    pub grant_type: GrantType,
+    pub redirect_uri: String,
+    pub scope: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MicrosoftOAuth2RefreshTokenRequest {
+    pub client_id: String,
+    pub client_secret: String,
+    pub grant_type: GrantType,
+    pub refresh_token: String,
+    pub scope: String,
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub enum TokenType {
+    Bearer,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MicrosoftOAuth2TokenResponse {
+    pub access_token: String,
+    pub expires_in: i64,
+    pub ext_expires_in: Option<i64>,
+    pub id_token: Option<String>,
+    pub refresh_token: Option<String>,
+    pub scope: String,
+    pub token_type: TokenType,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MicrosoftOAuth2TokenErrorResponse {
+    pub error: String,
+    pub error_description: String,
+    pub error_codes: Option<Vec<u32>>,
+    pub timestamp: Option<String>,
+    pub trace_id: Option<String>,
+    pub correlation_id: Option<String>,
+}
@@ -1,5 +1,9 @@
+pub mod access_token_request;
+pub mod access_token_request_response;
 pub mod dialog_request;
 pub mod google_oauth2_request;
 pub mod google_oauth2_response;
+pub mod microsoft_oauth2_request;
+pub mod microsoft_oauth2_response;
 pub mod token_request;
 pub mod token_response;
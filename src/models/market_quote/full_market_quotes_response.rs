@@ -1,5 +1,5 @@
 use {
-    crate::models::market_quote::OHLC,
+    crate::models::{instrument_key::InstrumentKey, market_quote::OHLC},
     serde::{Deserialize, Serialize},
 };
 
@@ -21,7 +21,7 @@ pub struct FullMarketQuotesResponse {
     pub ohlc: OHLC,
     pub depth: Depth,
     pub timestamp: String,
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
     pub symbol: String,
     pub last_price: f64,
     pub volume: u32,
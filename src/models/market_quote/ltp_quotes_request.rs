@@ -1,10 +1,12 @@
-use {serde::Serialize, serde_valid::Validate};
+use {
+    crate::{models::instrument_key::InstrumentKey, utils::serde_comma_separated},
+    serde::Serialize,
+    serde_valid::Validate,
+};
 
 #[derive(Serialize, Debug, Validate)]
 pub struct LTPQuotesRequest {
-    #[validate(
-        pattern = r"^(?:^NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+(,(?:NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+)*?$",
-        message = "Invalid instrument_key"
-    )]
-    pub instrument_key: String,
+    #[serde(serialize_with = "serde_comma_separated::serialize")]
+    #[validate(min_items = 1, message = "At least one instrument_key is required")]
+    pub instrument_key: Vec<InstrumentKey>,
 }
@@ -1,7 +1,10 @@
-use serde::{Deserialize, Serialize};
+use {
+    crate::models::instrument_key::InstrumentKey,
+    serde::{Deserialize, Serialize},
+};
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct LTPQuotesResponse {
     pub last_price: f64,
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
 }
@@ -1,5 +1,5 @@
 use {
-    crate::models::market_quote::OHLC,
+    crate::models::{instrument_key::InstrumentKey, market_quote::OHLC},
     serde::{Deserialize, Serialize},
 };
 
@@ -7,5 +7,5 @@ use {
 pub struct OHLCQuotesResponse {
     pub ohlc: OHLC,
     pub last_price: f64,
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
 }
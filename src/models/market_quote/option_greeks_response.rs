@@ -1,9 +1,12 @@
-use serde::{Deserialize, Serialize};
+use {
+    crate::models::instrument_key::InstrumentKey,
+    serde::{Deserialize, Serialize},
+};
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct OptionGreeksResponse {
     pub last_price: f64,
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
     pub ltq: u64,
     pub volume: u64,
     pub cp: f64,
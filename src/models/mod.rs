@@ -3,6 +3,7 @@ pub mod error_response;
 pub mod expired_instruments;
 pub mod gtt_orders;
 pub mod historical_data;
+pub mod instrument_key;
 pub mod instruments;
 pub mod login;
 pub mod margins;
@@ -13,6 +14,7 @@ pub mod orders;
 pub mod portfolio;
 pub mod success_response;
 pub mod trade_profit_and_loss;
+pub mod trading_symbol;
 pub mod user;
 pub mod ws;
 
@@ -43,7 +45,7 @@ pub enum AssetType {
     IRD,
 }
 
-#[derive(Deserialize, Serialize, Debug, Eq, Hash, Clone, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Eq, Hash, Clone, Copy, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ExchangeSegment {
     NseEq,
@@ -56,9 +58,50 @@ pub enum ExchangeSegment {
     BseFo,
     BcdFo,
     McxFo,
+    McxIndex,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+impl Display for ExchangeSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: &str = match self {
+            ExchangeSegment::NseEq => "NSE_EQ",
+            ExchangeSegment::NseIndex => "NSE_INDEX",
+            ExchangeSegment::NseFo => "NSE_FO",
+            ExchangeSegment::NseCom => "NSE_COM",
+            ExchangeSegment::NcdFo => "NCD_FO",
+            ExchangeSegment::BseEq => "BSE_EQ",
+            ExchangeSegment::BseIndex => "BSE_INDEX",
+            ExchangeSegment::BseFo => "BSE_FO",
+            ExchangeSegment::BcdFo => "BCD_FO",
+            ExchangeSegment::McxFo => "MCX_FO",
+            ExchangeSegment::McxIndex => "MCX_INDEX",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for ExchangeSegment {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NSE_EQ" => Ok(ExchangeSegment::NseEq),
+            "NSE_INDEX" => Ok(ExchangeSegment::NseIndex),
+            "NSE_FO" => Ok(ExchangeSegment::NseFo),
+            "NSE_COM" => Ok(ExchangeSegment::NseCom),
+            "NCD_FO" => Ok(ExchangeSegment::NcdFo),
+            "BSE_EQ" => Ok(ExchangeSegment::BseEq),
+            "BSE_INDEX" => Ok(ExchangeSegment::BseIndex),
+            "BSE_FO" => Ok(ExchangeSegment::BseFo),
+            "BCD_FO" => Ok(ExchangeSegment::BcdFo),
+            "MCX_FO" => Ok(ExchangeSegment::McxFo),
+            "MCX_INDEX" => Ok(ExchangeSegment::McxIndex),
+            _ => Err("Invalid exchange segment"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum ProductType {
     I,
@@ -67,13 +110,22 @@ pub enum ProductType {
     MTF,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum TransactionType {
     Buy,
     Sell,
 }
 
+impl TransactionType {
+    pub fn opposite(&self) -> Self {
+        match self {
+            TransactionType::Buy => TransactionType::Sell,
+            TransactionType::Sell => TransactionType::Buy,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum SegmentType {
@@ -84,7 +136,7 @@ pub enum SegmentType {
     MF,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Exchange {
     NSE,
@@ -120,7 +172,7 @@ pub enum OrderVariety {
     OCO,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OrderStatus {
     ValidationPending,
     ModifyPending,
@@ -141,6 +193,19 @@ pub enum OrderStatus {
     NotModified,
 }
 
+impl OrderStatus {
+    // No further updates are expected for orders in these states
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Complete
+                | OrderStatus::Rejected
+                | OrderStatus::Cancelled
+                | OrderStatus::CancelledAfterMarketOrder
+        )
+    }
+}
+
 impl Display for OrderStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s: &str = match self {
@@ -1,5 +1,8 @@
 use {
-    crate::models::{AssetType, Exchange, ExchangeSegment},
+    crate::models::{
+        AssetType, Exchange, ExchangeSegment, instrument_key::InstrumentKey,
+        trading_symbol::TradingSymbol,
+    },
     serde::{Deserialize, Serialize},
 };
 
@@ -16,9 +19,9 @@ pub struct OptionContractsResponse {
     pub segment: ExchangeSegment,
     pub exchange: Exchange,
     pub expiry: String,
-    pub instrument_key: String,
+    pub instrument_key: InstrumentKey,
     pub exchange_token: String,
-    pub trading_symbol: String,
+    pub trading_symbol: TradingSymbol,
     pub tick_size: f64,
     pub lot_size: f64,
     pub instrument_type: InstrumentType,
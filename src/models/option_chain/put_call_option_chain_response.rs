@@ -1,4 +1,7 @@
-use serde::{Deserialize, Serialize};
+use {
+    crate::models::instrument_key::InstrumentKey,
+    serde::{Deserialize, Serialize},
+};
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct OptionMarketData {
@@ -24,7 +27,7 @@ pub struct OptionGreeks {
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct OptionData {
-    pub instrument_key: String,
+    pub instrument_key: InstrumentKey,
     pub market_data: OptionMarketData,
     pub option_greeks: OptionGreeks,
 }
@@ -18,7 +18,7 @@ pub mod trade_history_response;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum ValidityType {
     DAY,
@@ -38,7 +38,7 @@ pub enum ExchangeSegment {
     McxFo,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING-KEBAB-CASE")]
 pub enum OrderType {
     Market,
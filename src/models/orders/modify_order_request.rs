@@ -11,7 +11,8 @@ pub struct ModifyOrderRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quantity: Option<u32>,
     pub validity: ValidityType,
-    #[validate(exclusive_minimum = 0.0, message = "price must be greater than 0.0")]
+    // 0 for MARKET and SL-M orders
+    #[validate(minimum = 0.0, message = "price cannot be negative")]
     pub price: f64,
     #[validate(pattern = r"^[-a-zA-Z0-9]+", message = "Invalid order_id")]
     pub order_id: String,
@@ -1,7 +1,9 @@
 use {
     crate::models::{
-        orders::{OrderType, ValidityType},
         OrderStatus, OrderVariety, ProductType, TransactionType,
+        instrument_key::InstrumentKey,
+        orders::{OrderType, ValidityType},
+        trading_symbol::TradingSymbol,
     },
     serde::{Deserialize, Serialize},
 };
@@ -15,9 +17,9 @@ pub struct OrderDetailsResponse {
     pub status: OrderStatus,
     pub guid: Option<String>,
     pub tag: Option<String>,
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
     pub placed_by: String,
-    pub trading_symbol: String,
+    pub trading_symbol: TradingSymbol,
     pub order_type: OrderType,
     pub validity: ValidityType,
     pub trigger_price: Option<f64>,
@@ -1,13 +1,14 @@
 use {
     crate::models::{
-        orders::{OrderType, ValidityType},
         ProductType, TransactionType,
+        instrument_key::InstrumentKey,
+        orders::{OrderType, ValidityType},
     },
     serde::Serialize,
     serde_valid::Validate,
 };
 
-#[derive(Serialize, Debug, Validate)]
+#[derive(Serialize, Clone, Debug, Validate)]
 pub struct PlaceMultiOrderRequest {
     pub correlation_id: String,
     // For commodity - number of lots is accepted. For other Futures & Options and equities - number of units is accepted in multiples of the tick size.
@@ -15,15 +16,12 @@ pub struct PlaceMultiOrderRequest {
     pub quantity: u32,
     pub product: ProductType,
     pub validity: ValidityType,
-    #[validate(exclusive_minimum = 0.0, message = "price must be greater than 0.0")]
+    // 0 for MARKET and SL-M orders
+    #[validate(minimum = 0.0, message = "price cannot be negative")]
     pub price: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tag: Option<String>,
-    #[validate(
-        pattern = r"^(?:^NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+(,(?:NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+)*?$",
-        message = "Invalid instrument_token"
-    )]
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
     pub order_type: OrderType,
     pub transaction_type: TransactionType,
     pub disclosed_quantity: u32,
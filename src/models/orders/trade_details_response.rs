@@ -1,5 +1,8 @@
 use {
-    crate::models::{orders::OrderType, ProductType, TransactionType},
+    crate::models::{
+        ProductType, TransactionType, instrument_key::InstrumentKey, orders::OrderType,
+        trading_symbol::TradingSymbol,
+    },
     serde::{Deserialize, Serialize},
 };
 
@@ -7,8 +10,8 @@ use {
 pub struct TradeDetailsResponse {
     pub exchange: String,
     pub product: ProductType,
-    pub trading_symbol: String,
-    pub instrument_token: String,
+    pub trading_symbol: TradingSymbol,
+    pub instrument_token: InstrumentKey,
     pub order_type: OrderType,
     pub transaction_type: TransactionType,
     pub quantity: u32,
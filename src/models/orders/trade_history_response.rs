@@ -1,5 +1,5 @@
 use {
-    crate::models::{SegmentType, TransactionType},
+    crate::models::{SegmentType, TransactionType, instrument_key::InstrumentKey},
     serde::{Deserialize, Serialize},
 };
 
@@ -19,5 +19,5 @@ pub struct TradeHistoryResponse {
     pub price: f64,
     pub isin: String,
     pub symbol: String,
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
 }
@@ -1,16 +1,12 @@
 use {
-    crate::models::{ProductType, TransactionType},
+    crate::models::{ProductType, TransactionType, instrument_key::InstrumentKey},
     serde::Serialize,
-    serde_valid::{validation, Validate},
+    serde_valid::{Validate, validation},
 };
 
 #[derive(Serialize, Debug, Validate)]
 pub struct ConvertPositionsRequest {
-    #[validate(
-        pattern = r"^(?:^NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+(,(?:NSE_EQ|NSE_FO|NCD_FO|BSE_EQ|BSE_FO|BCD_FO|MCX_FO|NSE_INDEX|BSE_INDEX|MCX_INDEX)\|[\w ]+)*?$",
-        message = "Invalid instrument_token"
-    )]
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
     #[validate(custom = |p| product_validation(p, "new_product"))]
     pub new_product: ProductType,
     #[validate(custom = |p| product_validation(p, "old_product"))]
@@ -1,5 +1,5 @@
 use {
-    crate::models::ProductType,
+    crate::models::{ProductType, instrument_key::InstrumentKey, trading_symbol::TradingSymbol},
     serde::{Deserialize, Serialize},
 };
 
@@ -12,12 +12,12 @@ pub struct HoldingsResponse {
     pub haircut: f64,
     pub product: ProductType,
     pub quantity: u32,
-    pub trading_symbol: String,
+    pub trading_symbol: TradingSymbol,
     pub last_price: f64,
     pub pnl: f64,
     pub day_change: f64,
     pub day_change_percentage: f64,
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
     pub average_price: f64,
     pub collateral_quantity: u32,
     pub collateral_update_quantity: u32,
@@ -1,5 +1,7 @@
 use {
-    crate::models::{Exchange, ProductType},
+    crate::models::{
+        Exchange, ProductType, instrument_key::InstrumentKey, trading_symbol::TradingSymbol,
+    },
     serde::{Deserialize, Serialize},
 };
 
@@ -10,7 +12,7 @@ pub struct PositionsResponse {
     pub value: f64,
     pub pnl: f64,
     pub product: ProductType,
-    pub instrument_token: String,
+    pub instrument_token: InstrumentKey,
     pub average_price: Option<f64>,
     pub buy_value: f64,
     pub overnight_quantity: u32,
@@ -29,7 +31,7 @@ pub struct PositionsResponse {
     pub unrealised: f64,
     pub realised: f64,
     pub sell_value: f64,
-    pub trading_symbol: String,
+    pub trading_symbol: TradingSymbol,
     pub close_price: f64,
     pub buy_price: f64,
     pub sell_price: f64,
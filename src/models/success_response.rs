@@ -1,5 +1,5 @@
 use {
-    crate::models::ResponseSummary,
+    crate::models::{ResponseSummary, error_response::Errors},
     serde::{Deserialize, Serialize},
 };
 
@@ -21,7 +21,8 @@ pub struct Metadata {
 pub struct SuccessResponse<T> {
     pub status: String,
     pub data: T,
-    pub errors: Option<()>,
+    // Errors of the failed orders when multiple orders are placed or cancelled and some of them fail
+    pub errors: Option<Vec<Errors>>,
     #[serde(alias = "metaData")]
     pub metadata: Option<Metadata>,
     pub summary: Option<ResponseSummary>,
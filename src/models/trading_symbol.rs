@@ -0,0 +1,121 @@
+use {
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    std::{
+        cmp::Ordering,
+        fmt::{self, Display},
+        hash::{Hash, Hasher},
+        str::FromStr,
+    },
+};
+
+// Trading symbol as listed by the exchange, e.g. RELIANCE or NIFTY25JAN24000CE. Kept as received, while comparisons and
+// hashing ignore case so lookups are case insensitive.
+#[derive(Debug, Clone)]
+pub struct TradingSymbol(String);
+
+impl TradingSymbol {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn uppercase_chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.0.chars().flat_map(char::to_uppercase)
+    }
+}
+
+impl PartialEq for TradingSymbol {
+    fn eq(&self, other: &Self) -> bool {
+        self.uppercase_chars().eq(other.uppercase_chars())
+    }
+}
+
+impl Eq for TradingSymbol {}
+
+impl Hash for TradingSymbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.uppercase_chars().for_each(|c| c.hash(state));
+    }
+}
+
+impl PartialOrd for TradingSymbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TradingSymbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.uppercase_chars().cmp(other.uppercase_chars())
+    }
+}
+
+impl Display for TradingSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for TradingSymbol {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: &str = s.trim();
+        if s.is_empty() {
+            return Err("Trading symbol must not be empty");
+        }
+        Ok(TradingSymbol(s.to_string()))
+    }
+}
+
+impl AsRef<str> for TradingSymbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Serialize for TradingSymbol {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for TradingSymbol {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        match s.trim().is_empty() {
+            true => Err(serde::de::Error::custom("Trading symbol must not be empty")),
+            false => Ok(TradingSymbol(s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::collections::HashMap};
+
+    #[test]
+    fn keeps_case_as_received() {
+        let trading_symbol: TradingSymbol = serde_json::from_str("\"Nifty 50\"").unwrap();
+        assert_eq!(trading_symbol.as_str(), "Nifty 50");
+        assert_eq!(
+            serde_json::to_string(&trading_symbol).unwrap(),
+            "\"Nifty 50\""
+        );
+    }
+
+    #[test]
+    fn looks_up_ignoring_case() {
+        let trading_symbols: HashMap<TradingSymbol, u32> =
+            HashMap::from([("Nifty 50".parse().unwrap(), 1)]);
+        assert_eq!(
+            trading_symbols.get(&"NIFTY 50".parse::<TradingSymbol>().unwrap()),
+            Some(&1)
+        );
+    }
+}
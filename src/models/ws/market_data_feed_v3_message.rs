@@ -1,4 +1,7 @@
-use serde::{Deserialize, Serialize};
+use {
+    crate::models::instrument_key::InstrumentKey,
+    serde::{Deserialize, Serialize},
+};
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "snake_case")]
@@ -27,5 +30,5 @@ pub struct MarketDataFeedV3Message {
 pub struct MessageDataV3 {
     pub mode: ModeTypeV3,
     #[serde(rename = "instrumentKeys")]
-    pub instrument_keys: Vec<String>,
+    pub instrument_keys: Vec<InstrumentKey>,
 }
@@ -2,7 +2,9 @@ use {
     crate::models::{
         Exchange, ExchangeSegment, OrderStatus, OrderVariety, ProductType, TransactionType,
         gtt_orders::{GTTOrderDetailsRule, GTTOrderType},
+        instrument_key::InstrumentKey,
         orders::{OrderType, ValidityType},
+        trading_symbol::TradingSymbol,
         ws::portfolio_feed_request::PortfolioUpdateType,
     },
     serde::{Deserialize, Serialize},
@@ -18,8 +20,8 @@ pub enum PortfolioFeedResponse {
         exchange: String,
         quantity: u32,
         product: ProductType,
-        instrument_token: String,
-        trading_symbol: String,
+        instrument_token: InstrumentKey,
+        trading_symbol: TradingSymbol,
         gtt_order_id: String,
         expires_at: u64,
         created_at: u64,
@@ -34,8 +36,8 @@ pub enum PortfolioFeedResponse {
         haircut: f64,
         product: ProductType,
         quantity: u32,
-        instrument_token: String,
-        instrument_key: String,
+        instrument_token: InstrumentKey,
+        instrument_key: InstrumentKey,
         average_price: f64,
         collateral_quantity: u32,
         collateral_update_quantity: u32,
@@ -51,10 +53,10 @@ pub enum PortfolioFeedResponse {
         quantity: u32,
         status: OrderStatus,
         tag: Option<String>,
-        instrument_token: String,
-        instrument_key: String,
+        instrument_token: InstrumentKey,
+        instrument_key: InstrumentKey,
         placed_by: String,
-        trading_symbol: String,
+        trading_symbol: TradingSymbol,
         order_type: OrderType,
         validity: ValidityType,
         trigger_price: f64,
@@ -81,8 +83,8 @@ pub enum PortfolioFeedResponse {
         exchange: ExchangeSegment,
         multiplier: f64,
         product: ProductType,
-        instrument_token: String,
-        instrument_key: String,
+        instrument_token: InstrumentKey,
+        instrument_key: InstrumentKey,
         average_price: f64,
         buy_value: f64,
         overnight_quantity: u32,
@@ -0,0 +1,331 @@
+use {
+    crate::{
+        client::ApiClient,
+        constants::MULTI_ORDER_MAX_ORDERS,
+        models::{
+            OrderStatus,
+            error_response::ErrorResponse,
+            orders::{
+                OrderType, ValidityType, order_details_request::OrderDetailsRequest,
+                place_multi_order_request::PlaceMultiOrderRequest,
+                place_multi_order_response::PlaceMultiOrderResponse,
+                place_order_v3_request::PlaceOrderV3Request,
+            },
+            success_response::SuccessResponse,
+        },
+        rate_limiter::retry_on_rate_limit,
+    },
+    std::collections::HashSet,
+    tokio::time::{Duration, Instant, sleep},
+    tracing::warn,
+};
+
+// Reversal orders are tagged with the tag of the leg, or its correlation id, and this suffix so tag lookups do not take
+// them for the leg
+const REVERSAL_TAG_SUFFIX: &str = "R";
+
+#[derive(Debug, Clone)]
+pub struct BasketLeg {
+    pub order: PlaceMultiOrderRequest,
+    // Hedge legs are placed and filled before the rest and rolled back last
+    pub hedge: bool,
+}
+
+impl BasketLeg {
+    pub fn new(order: PlaceMultiOrderRequest) -> Self {
+        BasketLeg {
+            order,
+            hedge: false,
+        }
+    }
+
+    pub fn hedge(order: PlaceMultiOrderRequest) -> Self {
+        BasketLeg { order, hedge: true }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RollbackPolicy {
+    // Leave the placed legs as they are
+    None,
+    // Cancel legs that are still open
+    #[default]
+    Cancel,
+    // Cancel legs that are still open and place market orders reversing whatever was filled
+    CancelAndReverse,
+}
+
+#[derive(Debug, Clone)]
+pub struct BasketConfig {
+    pub rollback_policy: RollbackPolicy,
+    // Legs not completely filled within this time fail the basket. Fills are not checked when None.
+    pub fill_timeout: Option<Duration>,
+    // How long cancelled legs are polled for their final filled quantity before being reversed
+    pub cancel_timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for BasketConfig {
+    fn default() -> Self {
+        BasketConfig {
+            rollback_policy: RollbackPolicy::default(),
+            fill_timeout: Some(Duration::from_secs(30)),
+            cancel_timeout: Duration::from_secs(10),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RollbackAction {
+    Cancelled,
+    Reversed { order_id: String, quantity: u32 },
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct BasketLegReport {
+    pub correlation_id: String,
+    pub hedge: bool,
+    pub order_id: Option<String>,
+    pub status: Option<OrderStatus>,
+    pub filled_quantity: u32,
+    pub average_price: f64,
+    pub error: Option<String>,
+    pub rollback: Vec<RollbackAction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BasketReport {
+    // In the order the legs were placed
+    pub legs: Vec<BasketLegReport>,
+    pub completed: bool,
+    pub rolled_back: bool,
+}
+
+impl ApiClient {
+    // Places the legs with all or nothing semantics. Hedge legs are placed and filled first, and when any leg fails the
+    // legs already placed are rolled back in reverse order according to the rollback policy.
+    pub async fn place_basket(
+        &self,
+        legs: Vec<BasketLeg>,
+        sandbox: bool,
+        config: &BasketConfig,
+    ) -> Result<BasketReport, String> {
+        if legs.is_empty() || legs.len() > MULTI_ORDER_MAX_ORDERS {
+            return Err(format!(
+                "Baskets must have between 1 and {} legs",
+                MULTI_ORDER_MAX_ORDERS
+            ));
+        }
+        let correlation_ids: HashSet<&str> = legs
+            .iter()
+            .map(|leg| leg.order.correlation_id.as_str())
+            .collect();
+        if correlation_ids.len() != legs.len() {
+            return Err("Correlation ids of basket legs must be unique".to_string());
+        }
+
+        let (hedges, others): (Vec<BasketLeg>, Vec<BasketLeg>) =
+            legs.into_iter().partition(|leg| leg.hedge);
+        let hedge_count: usize = hedges.len();
+        let legs: Vec<PlaceMultiOrderRequest> = hedges
+            .into_iter()
+            .chain(others)
+            .map(|leg| leg.order)
+            .collect();
+        let mut reports: Vec<BasketLegReport> = legs
+            .iter()
+            .enumerate()
+            .map(|(index, leg)| BasketLegReport {
+                correlation_id: leg.correlation_id.clone(),
+                hedge: index < hedge_count,
+                order_id: None,
+                status: None,
+                filled_quantity: 0,
+                average_price: 0.0,
+                error: None,
+                rollback: Vec::new(),
+            })
+            .collect();
+
+        let mut completed: bool = true;
+        for group in [0..hedge_count, hedge_count..legs.len()] {
+            if group.is_empty() {
+                continue;
+            }
+            let group_legs: Vec<PlaceMultiOrderRequest> = legs[group.clone()].to_vec();
+            let result: Result<SuccessResponse<PlaceMultiOrderResponse>, ErrorResponse> =
+                retry_on_rate_limit(|| self.place_multi_order(group_legs.clone(), sandbox)).await;
+
+            for report in &mut reports[group.clone()] {
+                match &result {
+                    Ok(response) => {
+                        report.order_id = response
+                            .data
+                            .order_ids
+                            .iter()
+                            .find(|order| order.correlation_id == report.correlation_id)
+                            .map(|order| order.order_id.clone());
+                        if report.order_id.is_none() {
+                            report.error = Some(
+                                response
+                                    .errors
+                                    .iter()
+                                    .flatten()
+                                    .find(|error| {
+                                        error.correlation_id.as_ref()
+                                            == Some(&report.correlation_id)
+                                    })
+                                    .map(|error| error.message.clone())
+                                    .unwrap_or_else(|| "Order was not placed".to_string()),
+                            );
+                        }
+                    }
+                    Err(error_response) => report.error = Some(error_response.message()),
+                }
+            }
+
+            let placed: bool = reports[group.clone()]
+                .iter()
+                .all(|report| report.error.is_none());
+            if let Some(fill_timeout) = config.fill_timeout
+                && placed
+            {
+                self.await_basket_legs(&mut reports[group.clone()], fill_timeout, config)
+                    .await;
+                for report in &mut reports[group.clone()] {
+                    if report.order_id.is_some() && report.status != Some(OrderStatus::Complete) {
+                        report.error = Some(match report.status {
+                            Some(status) => format!("Leg was not filled, {}", status),
+                            None => "Leg status is unknown".to_string(),
+                        });
+                    }
+                }
+            }
+
+            if reports[group].iter().any(|report| report.error.is_some()) {
+                completed = false;
+                break;
+            }
+        }
+
+        let rolled_back: bool = !completed && config.rollback_policy != RollbackPolicy::None;
+        if rolled_back {
+            warn!("Basket failed, rolling back placed legs");
+            self.rollback_basket(&legs, &mut reports, sandbox, config)
+                .await;
+        }
+        Ok(BasketReport {
+            legs: reports,
+            completed,
+            rolled_back,
+        })
+    }
+
+    async fn rollback_basket(
+        &self,
+        legs: &[PlaceMultiOrderRequest],
+        reports: &mut [BasketLegReport],
+        sandbox: bool,
+        config: &BasketConfig,
+    ) {
+        // Legs placed last are rolled back first so hedges are removed last
+        for (leg, report) in legs.iter().zip(reports.iter_mut()).rev() {
+            let Some(order_id) = report.order_id.clone() else {
+                continue;
+            };
+            self.await_basket_legs(std::slice::from_mut(report), Duration::ZERO, config)
+                .await;
+            if !report.status.is_some_and(|status| status.is_terminal()) {
+                match retry_on_rate_limit(|| self.cancel_order_v3(order_id.clone(), sandbox)).await
+                {
+                    Ok(_) => report.rollback.push(RollbackAction::Cancelled),
+                    Err(error_response) => report
+                        .rollback
+                        .push(RollbackAction::Failed(error_response.message())),
+                }
+                self.await_basket_legs(std::slice::from_mut(report), config.cancel_timeout, config)
+                    .await;
+            }
+
+            if config.rollback_policy != RollbackPolicy::CancelAndReverse
+                || report.filled_quantity == 0
+            {
+                continue;
+            }
+            let reverse_order_body: PlaceOrderV3Request = PlaceOrderV3Request {
+                quantity: report.filled_quantity,
+                product: leg.product,
+                validity: ValidityType::DAY,
+                price: 0.0,
+                tag: Some(format!(
+                    "{}-{}",
+                    leg.tag.as_ref().unwrap_or(&leg.correlation_id),
+                    REVERSAL_TAG_SUFFIX
+                )),
+                instrument_token: leg.instrument_token.clone(),
+                order_type: OrderType::Market,
+                transaction_type: leg.transaction_type.opposite(),
+                disclosed_quantity: 0,
+                trigger_price: None,
+                is_amo: false,
+                slice: Some(true),
+            };
+            report.rollback.push(
+                match retry_on_rate_limit(|| {
+                    self.place_order_v3(reverse_order_body.clone(), sandbox)
+                })
+                .await
+                {
+                    Ok(response) => RollbackAction::Reversed {
+                        order_id: response.data.order_ids.join(","),
+                        quantity: report.filled_quantity,
+                    },
+                    Err(error_response) => RollbackAction::Failed(error_response.message()),
+                },
+            );
+        }
+    }
+
+    // Polls the placed legs until all of them are terminal or the timeout passes, fetching them at least once
+    async fn await_basket_legs(
+        &self,
+        reports: &mut [BasketLegReport],
+        timeout: Duration,
+        config: &BasketConfig,
+    ) {
+        let deadline: Instant = Instant::now() + timeout;
+        loop {
+            for report in reports.iter_mut() {
+                let Some(order_id) = &report.order_id else {
+                    continue;
+                };
+                if report.status.is_some_and(|status| status.is_terminal()) {
+                    continue;
+                }
+                if let Ok(response) = retry_on_rate_limit(|| {
+                    self.get_order_details(OrderDetailsRequest {
+                        order_id: Some(order_id.clone()),
+                        tag: None,
+                    })
+                })
+                .await
+                {
+                    report.status = Some(response.data.status);
+                    report.filled_quantity = response.data.filled_quantity;
+                    report.average_price = response.data.average_price;
+                }
+            }
+
+            let pending: bool = reports.iter().any(|report| {
+                report.order_id.is_some()
+                    && !report.status.is_some_and(|status| status.is_terminal())
+            });
+            if !pending || Instant::now() >= deadline {
+                return;
+            }
+            sleep(config.poll_interval).await;
+        }
+    }
+}
@@ -0,0 +1,550 @@
+use {
+    crate::{
+        client::ApiClient,
+        models::{
+            OrderStatus, ProductType, TransactionType,
+            instrument_key::InstrumentKey,
+            orders::{
+                OrderType, ValidityType, modify_order_request::ModifyOrderRequest,
+                order_details_request::OrderDetailsRequest,
+                order_details_response::OrderDetailsResponse,
+                place_order_v3_request::PlaceOrderV3Request,
+            },
+        },
+        rate_limiter::retry_on_rate_limit,
+        utils::{read_value_from_file, write_value_to_file},
+    },
+    serde::{Deserialize, Serialize},
+    std::{collections::BTreeMap, fs, io::ErrorKind, path::PathBuf},
+    tracing::{info, warn},
+};
+
+// Legs are tagged with the order id and these suffixes
+const ENTRY_TAG_SUFFIX: &str = "E";
+const TARGET_TAG_SUFFIX: &str = "T";
+const STOP_LOSS_TAG_SUFFIX: &str = "S";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BracketExit {
+    Target,
+    StopLoss,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub enum BracketState {
+    Pending,
+    EntryOpen,
+    ExitsOpen,
+    Closed { exit: BracketExit },
+    Cancelled,
+    // Management stopped and the order needs attention, e.g. an exit leg was rejected or both exit legs filled
+    Failed { reason: String },
+}
+
+impl BracketState {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            BracketState::Closed { .. } | BracketState::Cancelled | BracketState::Failed { .. }
+        )
+    }
+}
+
+// Bracket order emulated with regular orders. The entry is placed first and once it fills a target limit order and a
+// stop loss market order are placed on the other side, each cancelling the other when filled. OCO orders skip the
+// entry and protect an existing position of the given side.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct BracketOrder {
+    pub id: String,
+    pub instrument_key: InstrumentKey,
+    // Side of the entry or of the position protected by an OCO order
+    pub transaction_type: TransactionType,
+    pub product: ProductType,
+    pub quantity: u32,
+    pub oco: bool,
+    // Entry is a market order when None
+    pub entry_price: Option<f64>,
+    pub target_price: f64,
+    pub stop_loss_trigger_price: f64,
+    pub entry_order_id: Option<String>,
+    pub target_order_id: Option<String>,
+    pub stop_loss_order_id: Option<String>,
+    // Quantity the exit legs close, which is less than quantity when the entry was only partly filled
+    pub exit_quantity: u32,
+    pub state: BracketState,
+}
+
+impl BracketOrder {
+    pub fn new(
+        id: impl Into<String>,
+        instrument_key: InstrumentKey,
+        transaction_type: TransactionType,
+        quantity: u32,
+    ) -> Self {
+        BracketOrder {
+            id: id.into(),
+            instrument_key,
+            transaction_type,
+            product: ProductType::I,
+            quantity,
+            oco: false,
+            entry_price: None,
+            target_price: 0.0,
+            stop_loss_trigger_price: 0.0,
+            entry_order_id: None,
+            target_order_id: None,
+            stop_loss_order_id: None,
+            exit_quantity: 0,
+            state: BracketState::Pending,
+        }
+    }
+
+    pub fn product(mut self, product: ProductType) -> Self {
+        self.product = product;
+        self
+    }
+
+    pub fn entry_limit(mut self, price: f64) -> Self {
+        self.entry_price = Some(price);
+        self
+    }
+
+    pub fn target(mut self, price: f64) -> Self {
+        self.target_price = price;
+        self
+    }
+
+    pub fn stop_loss(mut self, trigger_price: f64) -> Self {
+        self.stop_loss_trigger_price = trigger_price;
+        self
+    }
+
+    pub fn oco(mut self) -> Self {
+        self.oco = true;
+        self
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.quantity == 0 {
+            return Err("Quantity must be greater than 0".to_string());
+        }
+        if self.target_price <= 0.0 || self.stop_loss_trigger_price <= 0.0 {
+            return Err("Target and stop loss prices must be greater than 0".to_string());
+        }
+        let valid: bool = match self.transaction_type {
+            TransactionType::Buy => self.target_price > self.stop_loss_trigger_price,
+            TransactionType::Sell => self.target_price < self.stop_loss_trigger_price,
+        };
+        match valid {
+            true => Ok(()),
+            false => Err(format!(
+                "Target {} and stop loss {} are on the wrong sides for a {:?} order",
+                self.target_price, self.stop_loss_trigger_price, self.transaction_type
+            )),
+        }
+    }
+
+    fn leg_tag(&self, suffix: &str) -> String {
+        format!("{}-{}", self.id, suffix)
+    }
+
+    fn exit_order_body(&self, exit: BracketExit) -> PlaceOrderV3Request {
+        PlaceOrderV3Request {
+            quantity: self.exit_quantity,
+            product: self.product,
+            validity: ValidityType::DAY,
+            price: match exit {
+                BracketExit::Target => self.target_price,
+                BracketExit::StopLoss => 0.0,
+            },
+            tag: Some(self.leg_tag(match exit {
+                BracketExit::Target => TARGET_TAG_SUFFIX,
+                BracketExit::StopLoss => STOP_LOSS_TAG_SUFFIX,
+            })),
+            instrument_token: self.instrument_key.clone(),
+            order_type: match exit {
+                BracketExit::Target => OrderType::Limit,
+                BracketExit::StopLoss => OrderType::SlM,
+            },
+            transaction_type: self.transaction_type.opposite(),
+            disclosed_quantity: 0,
+            trigger_price: match exit {
+                BracketExit::Target => None,
+                BracketExit::StopLoss => Some(self.stop_loss_trigger_price),
+            },
+            is_amo: false,
+            slice: None,
+        }
+    }
+}
+
+// Manages bracket and OCO orders, saving them to the state file after every change so that a restarted process picks
+// up where it left off. Orders advance when poll is called.
+#[derive(Debug)]
+pub struct BracketManager {
+    brackets: BTreeMap<String, BracketOrder>,
+    state_path: Option<PathBuf>,
+}
+
+impl BracketManager {
+    // Resumes the orders saved in state_path if it exists
+    pub fn new(state_path: Option<PathBuf>) -> Result<Self, String> {
+        let brackets: BTreeMap<String, BracketOrder> = match &state_path {
+            Some(path) => match read_value_from_file(path) {
+                Ok(state) => serde_json::from_str::<Vec<BracketOrder>>(&state)
+                    .map_err(|err| {
+                        format!("Failed to parse bracket orders in {:?}: {}", path, err)
+                    })?
+                    .into_iter()
+                    .map(|bracket| (bracket.id.clone(), bracket))
+                    .collect(),
+                Err(err) if err.kind() == ErrorKind::NotFound => BTreeMap::new(),
+                Err(err) => {
+                    return Err(format!(
+                        "Failed to read bracket orders from {:?}: {}",
+                        path, err
+                    ));
+                }
+            },
+            None => BTreeMap::new(),
+        };
+        if !brackets.is_empty() {
+            info!("Resuming {} bracket orders", brackets.len());
+        }
+        Ok(BracketManager {
+            brackets,
+            state_path,
+        })
+    }
+
+    pub fn add(&mut self, bracket: BracketOrder) -> Result<(), String> {
+        bracket.validate()?;
+        if self.brackets.contains_key(&bracket.id) {
+            return Err(format!("Bracket order {} already exists", bracket.id));
+        }
+        self.brackets.insert(bracket.id.clone(), bracket);
+        self.save()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&BracketOrder> {
+        self.brackets.get(id)
+    }
+
+    pub fn brackets(&self) -> impl Iterator<Item = &BracketOrder> {
+        self.brackets.values()
+    }
+
+    // Drops orders which are no longer managed from memory and the state file
+    pub fn remove_terminal(&mut self) -> Result<(), String> {
+        self.brackets
+            .retain(|_, bracket| !bracket.state.is_terminal());
+        self.save()
+    }
+
+    // Places pending orders and advances every managed order based on the status of its legs
+    pub async fn poll(&mut self, api_client: &ApiClient, sandbox: bool) -> Result<(), String> {
+        let ids: Vec<String> = self
+            .brackets
+            .values()
+            .filter(|bracket| !bracket.state.is_terminal())
+            .map(|bracket| bracket.id.clone())
+            .collect();
+        for id in ids {
+            let mut bracket: BracketOrder = self.brackets[&id].clone();
+            // Failed requests are retried on the next poll
+            if let Err(err) = advance(api_client, &mut bracket, sandbox).await {
+                warn!("Failed to update bracket order {}: {}", id, err);
+            }
+            if bracket == self.brackets[&id] {
+                continue;
+            }
+            if bracket.state != self.brackets[&id].state {
+                info!("Bracket order {} is now {:?}", id, bracket.state);
+            }
+            self.brackets.insert(id, bracket);
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    // Cancels the open legs of the order. Filled entries are not exited.
+    pub async fn cancel(
+        &mut self,
+        api_client: &ApiClient,
+        id: &str,
+        sandbox: bool,
+    ) -> Result<(), String> {
+        let mut bracket: BracketOrder = self
+            .brackets
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("Bracket order {} not found", id))?;
+        for order_id in [
+            &bracket.entry_order_id,
+            &bracket.target_order_id,
+            &bracket.stop_loss_order_id,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            cancel_if_open(api_client, order_id, sandbox).await?;
+        }
+        bracket.state = BracketState::Cancelled;
+        self.brackets.insert(id.to_string(), bracket);
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+        let state: String =
+            serde_json::to_string_pretty(&self.brackets.values().collect::<Vec<&BracketOrder>>())
+                .map_err(|err| format!("Failed to serialize bracket orders: {}", err))?;
+        // Written next to the state file and renamed over it so that a crash while writing keeps the previous state
+        let partial_path: PathBuf = path.with_extension("partial");
+        write_value_to_file(&partial_path, &state)
+            .and_then(|_| fs::rename(&partial_path, path))
+            .map_err(|err| format!("Failed to save bracket orders to {:?}: {}", path, err))
+    }
+}
+
+async fn advance(
+    api_client: &ApiClient,
+    bracket: &mut BracketOrder,
+    sandbox: bool,
+) -> Result<(), String> {
+    match bracket.state {
+        BracketState::Pending if bracket.oco => {
+            bracket.exit_quantity = bracket.quantity;
+            place_exits(api_client, bracket, sandbox).await
+        }
+        BracketState::Pending => {
+            let entry_order_body: PlaceOrderV3Request = PlaceOrderV3Request {
+                quantity: bracket.quantity,
+                product: bracket.product,
+                validity: ValidityType::DAY,
+                price: bracket.entry_price.unwrap_or(0.0),
+                tag: Some(bracket.leg_tag(ENTRY_TAG_SUFFIX)),
+                instrument_token: bracket.instrument_key.clone(),
+                order_type: match bracket.entry_price {
+                    Some(_) => OrderType::Limit,
+                    None => OrderType::Market,
+                },
+                transaction_type: bracket.transaction_type,
+                disclosed_quantity: 0,
+                trigger_price: None,
+                is_amo: false,
+                slice: None,
+            };
+            let result: Result<String, String> =
+                match placed_order_id(api_client, &entry_order_body).await? {
+                    Some(order_id) => Ok(order_id),
+                    None => place(api_client, entry_order_body, sandbox).await,
+                };
+            match result {
+                Ok(order_id) => {
+                    bracket.entry_order_id = Some(order_id);
+                    bracket.state = BracketState::EntryOpen;
+                }
+                Err(reason) => bracket.state = BracketState::Failed { reason },
+            }
+            Ok(())
+        }
+        BracketState::EntryOpen => {
+            let entry: OrderDetailsResponse =
+                order_details(api_client, bracket.entry_order_id.as_ref().unwrap()).await?;
+            match entry.status {
+                OrderStatus::Complete => {
+                    bracket.exit_quantity = entry.filled_quantity;
+                    place_exits(api_client, bracket, sandbox).await
+                }
+                // Whatever was filled before the entry was cancelled still needs exits
+                status if status.is_terminal() && entry.filled_quantity > 0 => {
+                    bracket.exit_quantity = entry.filled_quantity;
+                    place_exits(api_client, bracket, sandbox).await
+                }
+                status if status.is_terminal() => {
+                    bracket.state = BracketState::Cancelled;
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+        BracketState::ExitsOpen => manage_exits(api_client, bracket, sandbox).await,
+        _ => Ok(()),
+    }
+}
+
+async fn place_exits(
+    api_client: &ApiClient,
+    bracket: &mut BracketOrder,
+    sandbox: bool,
+) -> Result<(), String> {
+    for exit in [BracketExit::StopLoss, BracketExit::Target] {
+        let placed: bool = match exit {
+            BracketExit::Target => bracket.target_order_id.is_some(),
+            BracketExit::StopLoss => bracket.stop_loss_order_id.is_some(),
+        };
+        if placed {
+            continue;
+        }
+        let exit_order_body: PlaceOrderV3Request = bracket.exit_order_body(exit);
+        let result: Result<String, String> =
+            match placed_order_id(api_client, &exit_order_body).await? {
+                Some(order_id) => Ok(order_id),
+                None => place(api_client, exit_order_body, sandbox).await,
+            };
+        match result {
+            Ok(placed_order_id) => match exit {
+                BracketExit::Target => bracket.target_order_id = Some(placed_order_id),
+                BracketExit::StopLoss => bracket.stop_loss_order_id = Some(placed_order_id),
+            },
+            Err(reason) => {
+                bracket.state = BracketState::Failed {
+                    reason: format!("{:?} leg was not placed: {}", exit, reason),
+                };
+                return Ok(());
+            }
+        }
+    }
+    bracket.state = BracketState::ExitsOpen;
+    Ok(())
+}
+
+async fn manage_exits(
+    api_client: &ApiClient,
+    bracket: &mut BracketOrder,
+    sandbox: bool,
+) -> Result<(), String> {
+    let target_order_id: String = bracket.target_order_id.clone().unwrap();
+    let stop_loss_order_id: String = bracket.stop_loss_order_id.clone().unwrap();
+    let target: OrderDetailsResponse = order_details(api_client, &target_order_id).await?;
+    let stop_loss: OrderDetailsResponse = order_details(api_client, &stop_loss_order_id).await?;
+
+    for (exit, leg, sibling) in [
+        (BracketExit::Target, &target, &stop_loss),
+        (BracketExit::StopLoss, &stop_loss, &target),
+    ] {
+        match leg.status {
+            OrderStatus::Complete => {
+                cancel_if_open(api_client, &sibling.order_id, sandbox).await?;
+                // Both legs may have filled between polls, which reverses the position instead of closing it
+                let sibling_filled_quantity: u32 = order_details(api_client, &sibling.order_id)
+                    .await?
+                    .filled_quantity;
+                let overfill: u32 = (leg.filled_quantity + sibling_filled_quantity)
+                    .saturating_sub(bracket.exit_quantity);
+                bracket.state = match overfill {
+                    0 => BracketState::Closed { exit },
+                    _ => BracketState::Failed {
+                        reason: format!(
+                            "{:?} leg completed but the other leg also filled, {} more than the position was exited",
+                            exit, overfill
+                        ),
+                    },
+                };
+                return Ok(());
+            }
+            status if status.is_terminal() => {
+                bracket.state = BracketState::Failed {
+                    reason: format!(
+                        "{:?} leg is {}: {}",
+                        exit,
+                        status,
+                        leg.status_message.as_deref().unwrap_or("no status message")
+                    ),
+                };
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+
+    // A partly filled leg leaves less of the position for the other leg to close
+    let remaining: u32 = bracket
+        .exit_quantity
+        .saturating_sub(target.filled_quantity + stop_loss.filled_quantity);
+    for (leg, sibling) in [(&target, &stop_loss), (&stop_loss, &target)] {
+        if leg.filled_quantity > 0 && sibling.filled_quantity == 0 && sibling.quantity != remaining
+        {
+            retry_on_rate_limit(|| {
+                api_client.modify_order_v3(
+                    ModifyOrderRequest {
+                        quantity: Some(remaining),
+                        validity: sibling.validity,
+                        price: sibling.price,
+                        order_id: sibling.order_id.clone(),
+                        order_type: sibling.order_type,
+                        disclosed_quantity: None,
+                        trigger_price: sibling.trigger_price,
+                    },
+                    sandbox,
+                )
+            })
+            .await
+            .map_err(|error_response| error_response.message())?;
+        }
+    }
+    Ok(())
+}
+
+// Leg orders placed before a restart but not saved are found by their tag instead of being placed again
+async fn placed_order_id(
+    api_client: &ApiClient,
+    place_order_body: &PlaceOrderV3Request,
+) -> Result<Option<String>, String> {
+    Ok(api_client
+        .order_ids_by_tag(place_order_body.tag.as_ref().unwrap())
+        .await?
+        .into_iter()
+        .next())
+}
+
+async fn place(
+    api_client: &ApiClient,
+    place_order_body: PlaceOrderV3Request,
+    sandbox: bool,
+) -> Result<String, String> {
+    retry_on_rate_limit(|| api_client.place_order_v3(place_order_body.clone(), sandbox))
+        .await
+        .map_err(|error_response| error_response.message())?
+        .data
+        .order_ids
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No order id was returned".to_string())
+}
+
+async fn order_details(
+    api_client: &ApiClient,
+    order_id: &str,
+) -> Result<OrderDetailsResponse, String> {
+    retry_on_rate_limit(|| {
+        api_client.get_order_details(OrderDetailsRequest {
+            order_id: Some(order_id.to_string()),
+            tag: None,
+        })
+    })
+    .await
+    .map(|response| response.data)
+    .map_err(|error_response| error_response.message())
+}
+
+async fn cancel_if_open(
+    api_client: &ApiClient,
+    order_id: &str,
+    sandbox: bool,
+) -> Result<(), String> {
+    if order_details(api_client, order_id)
+        .await?
+        .status
+        .is_terminal()
+    {
+        return Ok(());
+    }
+    retry_on_rate_limit(|| api_client.cancel_order_v3(order_id.to_string(), sandbox))
+        .await
+        .map(|_| ())
+        .map_err(|error_response| error_response.message())
+}
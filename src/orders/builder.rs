@@ -0,0 +1,501 @@
+use {
+    crate::{
+        instruments::store::InstrumentStore,
+        models::{
+            ExchangeSegment, ProductType, TransactionType,
+            instrument_key::InstrumentKey,
+            instruments::instruments_response::InstrumentsResponse,
+            orders::{
+                OrderType, ValidityType, place_multi_order_request::PlaceMultiOrderRequest,
+                place_order_v3_request::PlaceOrderV3Request,
+            },
+        },
+    },
+    std::fmt,
+};
+
+// Prices are compared against tick sizes with this tolerance to allow for floating point error
+const PRICE_EPSILON: f64 = 1e-6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderValidationError {
+    UnknownInstrument(InstrumentKey),
+    NotTradable(InstrumentKey),
+    InvalidQuantity(String),
+    InvalidPrice(String),
+    InvalidTriggerPrice(String),
+    ProductNotAllowed {
+        product: ProductType,
+        segment: ExchangeSegment,
+    },
+}
+
+impl fmt::Display for OrderValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderValidationError::UnknownInstrument(instrument_key) => {
+                write!(f, "Instrument {} not found", instrument_key)
+            }
+            OrderValidationError::NotTradable(instrument_key) => {
+                write!(f, "Instrument {} cannot be traded", instrument_key)
+            }
+            OrderValidationError::InvalidQuantity(reason) => {
+                write!(f, "Invalid quantity: {}", reason)
+            }
+            OrderValidationError::InvalidPrice(reason) => write!(f, "Invalid price: {}", reason),
+            OrderValidationError::InvalidTriggerPrice(reason) => {
+                write!(f, "Invalid trigger price: {}", reason)
+            }
+            OrderValidationError::ProductNotAllowed { product, segment } => {
+                write!(f, "Product {:?} is not allowed in {}", product, segment)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OrderValidationError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Quantity {
+    Units(u32),
+    Lots(u32),
+}
+
+// Builds order requests validated against the instrument master, e.g.
+// OrderBuilder::buy(instrument_key).lots(2).limit(101.5).product(ProductType::I).build(&instruments)
+#[derive(Debug, Clone)]
+pub struct OrderBuilder {
+    instrument_key: InstrumentKey,
+    transaction_type: TransactionType,
+    quantity: Quantity,
+    product: ProductType,
+    validity: ValidityType,
+    order_type: OrderType,
+    price: f64,
+    trigger_price: Option<f64>,
+    disclosed_quantity: u32,
+    tag: Option<String>,
+    is_amo: bool,
+    slice: Option<bool>,
+}
+
+impl OrderBuilder {
+    pub fn new(instrument_key: InstrumentKey, transaction_type: TransactionType) -> Self {
+        OrderBuilder {
+            instrument_key,
+            transaction_type,
+            quantity: Quantity::Units(0),
+            product: ProductType::D,
+            validity: ValidityType::DAY,
+            order_type: OrderType::Market,
+            price: 0.0,
+            trigger_price: None,
+            disclosed_quantity: 0,
+            tag: None,
+            is_amo: false,
+            slice: None,
+        }
+    }
+
+    pub fn buy(instrument_key: InstrumentKey) -> Self {
+        Self::new(instrument_key, TransactionType::Buy)
+    }
+
+    pub fn sell(instrument_key: InstrumentKey) -> Self {
+        Self::new(instrument_key, TransactionType::Sell)
+    }
+
+    // Units for equities and F&O, lots for MCX commodities
+    pub fn qty(mut self, quantity: u32) -> Self {
+        self.quantity = Quantity::Units(quantity);
+        self
+    }
+
+    // Converted to units using the lot size of the instrument
+    pub fn lots(mut self, lots: u32) -> Self {
+        self.quantity = Quantity::Lots(lots);
+        self
+    }
+
+    pub fn market(mut self) -> Self {
+        self.order_type = OrderType::Market;
+        self.price = 0.0;
+        self.trigger_price = None;
+        self
+    }
+
+    pub fn limit(mut self, price: f64) -> Self {
+        self.order_type = OrderType::Limit;
+        self.price = price;
+        self.trigger_price = None;
+        self
+    }
+
+    pub fn stop_loss(mut self, trigger_price: f64, price: f64) -> Self {
+        self.order_type = OrderType::SL;
+        self.price = price;
+        self.trigger_price = Some(trigger_price);
+        self
+    }
+
+    pub fn stop_loss_market(mut self, trigger_price: f64) -> Self {
+        self.order_type = OrderType::SlM;
+        self.price = 0.0;
+        self.trigger_price = Some(trigger_price);
+        self
+    }
+
+    pub fn product(mut self, product: ProductType) -> Self {
+        self.product = product;
+        self
+    }
+
+    pub fn validity(mut self, validity: ValidityType) -> Self {
+        self.validity = validity;
+        self
+    }
+
+    pub fn disclosed_qty(mut self, disclosed_quantity: u32) -> Self {
+        self.disclosed_quantity = disclosed_quantity;
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    pub fn amo(mut self, is_amo: bool) -> Self {
+        self.is_amo = is_amo;
+        self
+    }
+
+    pub fn slice(mut self, slice: bool) -> Self {
+        self.slice = Some(slice);
+        self
+    }
+
+    pub fn instrument_key(&self) -> &InstrumentKey {
+        &self.instrument_key
+    }
+
+    pub fn transaction_type(&self) -> TransactionType {
+        self.transaction_type
+    }
+
+    pub fn build(
+        self,
+        instruments: &InstrumentStore,
+    ) -> Result<PlaceOrderV3Request, OrderValidationError> {
+        let instrument: &InstrumentsResponse = instruments
+            .get(&self.instrument_key)
+            .ok_or_else(|| OrderValidationError::UnknownInstrument(self.instrument_key.clone()))?;
+        self.build_for(instrument)
+    }
+
+    pub fn build_for(
+        self,
+        instrument: &InstrumentsResponse,
+    ) -> Result<PlaceOrderV3Request, OrderValidationError> {
+        let quantity: u32 = self.validate(instrument)?;
+        Ok(PlaceOrderV3Request {
+            quantity,
+            product: self.product,
+            validity: self.validity,
+            price: self.price,
+            tag: self.tag,
+            instrument_token: self.instrument_key,
+            order_type: self.order_type,
+            transaction_type: self.transaction_type,
+            disclosed_quantity: self.disclosed_quantity,
+            trigger_price: self.trigger_price,
+            is_amo: self.is_amo,
+            slice: self.slice,
+        })
+    }
+
+    pub fn build_multi(
+        self,
+        correlation_id: impl Into<String>,
+        instruments: &InstrumentStore,
+    ) -> Result<PlaceMultiOrderRequest, OrderValidationError> {
+        let request: PlaceOrderV3Request = self.build(instruments)?;
+        Ok(PlaceMultiOrderRequest {
+            correlation_id: correlation_id.into(),
+            quantity: request.quantity,
+            product: request.product,
+            validity: request.validity,
+            price: request.price,
+            tag: request.tag,
+            instrument_token: request.instrument_token,
+            order_type: request.order_type,
+            transaction_type: request.transaction_type,
+            disclosed_quantity: request.disclosed_quantity,
+            trigger_price: request.trigger_price,
+            is_amo: request.is_amo,
+            slice: request.slice,
+        })
+    }
+
+    // Returns the quantity in units to be sent
+    fn validate(&self, instrument: &InstrumentsResponse) -> Result<u32, OrderValidationError> {
+        let segment: ExchangeSegment = instrument.segment();
+        let (Some(lot_size), Some(tick_size)) = (instrument.lot_size(), instrument.tick_size())
+        else {
+            return Err(OrderValidationError::NotTradable(
+                self.instrument_key.clone(),
+            ));
+        };
+        validate_product(self.product, segment)?;
+
+        // MCX orders are placed in lots
+        let quantity: u32 = match (self.quantity, segment) {
+            (Quantity::Units(quantity), _) | (Quantity::Lots(quantity), ExchangeSegment::McxFo) => {
+                quantity
+            }
+            (Quantity::Lots(lots), _) => lots.checked_mul(lot_size).ok_or_else(|| {
+                OrderValidationError::InvalidQuantity(format!("{} lots is too large", lots))
+            })?,
+        };
+        if quantity == 0 {
+            return Err(OrderValidationError::InvalidQuantity(
+                "quantity must be greater than 0".to_string(),
+            ));
+        }
+        if segment != ExchangeSegment::McxFo && lot_size > 0 && !quantity.is_multiple_of(lot_size) {
+            return Err(OrderValidationError::InvalidQuantity(format!(
+                "{} is not a multiple of the lot size {}",
+                quantity, lot_size
+            )));
+        }
+        if self.disclosed_quantity > quantity {
+            return Err(OrderValidationError::InvalidQuantity(format!(
+                "disclosed quantity {} is more than the quantity {}",
+                self.disclosed_quantity, quantity
+            )));
+        }
+
+        // Tick sizes in the instrument master are in paise
+        let tick: f64 = tick_size / 100.0;
+        match self.order_type {
+            OrderType::Market | OrderType::SlM if self.price != 0.0 => {
+                return Err(OrderValidationError::InvalidPrice(format!(
+                    "price must be 0 for {:?} orders",
+                    self.order_type
+                )));
+            }
+            OrderType::Limit | OrderType::SL => validate_tick("price", self.price, tick)
+                .map_err(OrderValidationError::InvalidPrice)?,
+            _ => {}
+        }
+
+        match (self.order_type, self.trigger_price) {
+            (OrderType::SL | OrderType::SlM, None) => {
+                return Err(OrderValidationError::InvalidTriggerPrice(format!(
+                    "trigger price is required for {:?} orders",
+                    self.order_type
+                )));
+            }
+            (OrderType::SL | OrderType::SlM, Some(trigger_price)) => {
+                validate_tick("trigger price", trigger_price, tick)
+                    .map_err(OrderValidationError::InvalidTriggerPrice)?;
+                // A stop loss buy triggers when the price rises to the trigger and is then filled at up to the limit price
+                let valid: bool = match (self.order_type, self.transaction_type) {
+                    (OrderType::SlM, _) => true,
+                    (_, TransactionType::Buy) => trigger_price <= self.price + PRICE_EPSILON,
+                    (_, TransactionType::Sell) => trigger_price >= self.price - PRICE_EPSILON,
+                };
+                if !valid {
+                    return Err(OrderValidationError::InvalidTriggerPrice(format!(
+                        "trigger price {} must be {} the price {} for a {:?} order",
+                        trigger_price,
+                        match self.transaction_type {
+                            TransactionType::Buy => "at most",
+                            TransactionType::Sell => "at least",
+                        },
+                        self.price,
+                        self.transaction_type
+                    )));
+                }
+            }
+            (_, Some(_)) => {
+                return Err(OrderValidationError::InvalidTriggerPrice(format!(
+                    "trigger price is only accepted for SL and SL-M orders, not {:?}",
+                    self.order_type
+                )));
+            }
+            (_, None) => {}
+        }
+
+        Ok(quantity)
+    }
+}
+
+fn validate_product(
+    product: ProductType,
+    segment: ExchangeSegment,
+) -> Result<(), OrderValidationError> {
+    let allowed: bool = match product {
+        ProductType::D | ProductType::I => true,
+        ProductType::MTF => matches!(segment, ExchangeSegment::NseEq | ExchangeSegment::BseEq),
+        ProductType::CO => matches!(
+            segment,
+            ExchangeSegment::NseEq
+                | ExchangeSegment::BseEq
+                | ExchangeSegment::NseFo
+                | ExchangeSegment::BseFo
+        ),
+    };
+    match allowed {
+        true => Ok(()),
+        false => Err(OrderValidationError::ProductNotAllowed { product, segment }),
+    }
+}
+
+fn validate_tick(field: &str, price: f64, tick: f64) -> Result<(), String> {
+    if price <= 0.0 {
+        return Err(format!("{} must be greater than 0", field));
+    }
+    if tick > 0.0 {
+        let ticks: f64 = price / tick;
+        if (ticks - ticks.round()).abs() > PRICE_EPSILON * ticks.max(1.0) {
+            return Err(format!(
+                "{} {} is not a multiple of the tick size {}",
+                field, price, tick
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::models::instruments::fixtures::{derivative, equity},
+        chrono::NaiveDate,
+    };
+
+    fn reliance() -> InstrumentsResponse {
+        equity("2885", "INE002A01018", "RELIANCE", 1)
+    }
+
+    fn nifty_future() -> InstrumentsResponse {
+        derivative(
+            "52910",
+            "FUT",
+            "NSE_INDEX|Nifty 50",
+            NaiveDate::from_ymd_opt(2025, 12, 30).unwrap(),
+            false,
+        )
+    }
+
+    fn buy(instrument: &InstrumentsResponse) -> OrderBuilder {
+        OrderBuilder::buy(instrument.instrument_key().clone())
+    }
+
+    #[test]
+    fn converts_lots_to_units() {
+        let instrument: InstrumentsResponse = nifty_future();
+        let request: PlaceOrderV3Request = buy(&instrument).lots(2).build_for(&instrument).unwrap();
+        assert_eq!(request.quantity, 150);
+    }
+
+    #[test]
+    fn rejects_quantity_not_a_multiple_of_lot_size() {
+        let instrument: InstrumentsResponse = nifty_future();
+        assert!(matches!(
+            buy(&instrument).qty(100).build_for(&instrument),
+            Err(OrderValidationError::InvalidQuantity(_))
+        ));
+        assert!(matches!(
+            buy(&instrument).qty(0).build_for(&instrument),
+            Err(OrderValidationError::InvalidQuantity(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_disclosed_quantity_above_quantity() {
+        let instrument: InstrumentsResponse = reliance();
+        assert!(matches!(
+            buy(&instrument)
+                .qty(10)
+                .disclosed_qty(11)
+                .build_for(&instrument),
+            Err(OrderValidationError::InvalidQuantity(_))
+        ));
+    }
+
+    #[test]
+    fn validates_prices_against_tick_size_in_paise() {
+        let instrument: InstrumentsResponse = reliance();
+        assert!(
+            buy(&instrument)
+                .qty(1)
+                .limit(1250.1)
+                .build_for(&instrument)
+                .is_ok()
+        );
+        assert!(matches!(
+            buy(&instrument)
+                .qty(1)
+                .limit(1250.05)
+                .build_for(&instrument),
+            Err(OrderValidationError::InvalidPrice(_))
+        ));
+        assert!(matches!(
+            buy(&instrument)
+                .qty(1)
+                .stop_loss(1250.05, 1250.1)
+                .build_for(&instrument),
+            Err(OrderValidationError::InvalidTriggerPrice(_))
+        ));
+    }
+
+    #[test]
+    fn requires_stop_loss_trigger_on_the_right_side_of_the_price() {
+        let instrument: InstrumentsResponse = reliance();
+        assert!(
+            buy(&instrument)
+                .qty(1)
+                .stop_loss(1250.0, 1251.0)
+                .build_for(&instrument)
+                .is_ok()
+        );
+        assert!(matches!(
+            buy(&instrument)
+                .qty(1)
+                .stop_loss(1252.0, 1251.0)
+                .build_for(&instrument),
+            Err(OrderValidationError::InvalidTriggerPrice(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_products_not_allowed_in_segment() {
+        let instrument: InstrumentsResponse = nifty_future();
+        assert_eq!(
+            buy(&instrument)
+                .lots(1)
+                .product(ProductType::MTF)
+                .build_for(&instrument)
+                .err(),
+            Some(OrderValidationError::ProductNotAllowed {
+                product: ProductType::MTF,
+                segment: ExchangeSegment::NseFo,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_instruments() {
+        let instrument_key: InstrumentKey = "NSE_EQ|INE848E01016".parse().unwrap();
+        assert_eq!(
+            OrderBuilder::buy(instrument_key.clone())
+                .qty(1)
+                .build(&InstrumentStore::new(vec![reliance()]))
+                .err(),
+            Some(OrderValidationError::UnknownInstrument(instrument_key))
+        );
+    }
+}
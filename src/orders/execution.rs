@@ -0,0 +1,560 @@
+use {
+    crate::{
+        client::ApiClient,
+        models::{
+            ExchangeSegment, OrderStatus, TransactionType,
+            instrument_key::InstrumentKey,
+            orders::{
+                OrderType, modify_order_request::ModifyOrderRequest,
+                order_details_request::OrderDetailsRequest,
+                place_order_v3_request::PlaceOrderV3Request,
+            },
+        },
+        protos::market_data_feed_v3::{Feed, FeedResponse as MarketDataFeedV3Response, Quote},
+        rate_limiter::retry_on_rate_limit,
+    },
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex as StdMutex},
+    },
+    tokio::time::{Duration, Instant, sleep, sleep_until},
+};
+
+// Prices closer than this are treated as equal when deciding whether to modify a chased order
+const PRICE_EPSILON: f64 = 1e-6;
+// How long a cancelled child order is polled for before it is reported as still open
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub type ExecutionProgressCallback = Arc<dyn Fn(&ExecutionProgress) + Send + Sync>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionProgress {
+    pub quantity: u32,
+    pub placed_quantity: u32,
+    pub filled_quantity: u32,
+    pub average_price: f64,
+    pub order_ids: Vec<String>,
+    // Why the execution stopped before the whole quantity was placed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TwapConfig {
+    // Child orders are spread evenly over this window, the first placed right away
+    pub duration: Duration,
+    pub slices: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct IcebergConfig {
+    pub visible_quantity: u32,
+    pub poll_interval: Duration,
+    // The resting child order is cancelled and no more are placed if the order is not filled within this time
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChaseConfig {
+    // Highest price paid when buying or lowest price received when selling
+    pub price_cap: f64,
+    // Chase the opposite side of the book, i.e. the best ask when buying, instead of joining the best bid
+    pub aggressive: bool,
+    pub poll_interval: Duration,
+    // The order is cancelled if still open after this time
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BestBidAsk {
+    pub bid: f64,
+    pub ask: f64,
+}
+
+// Best bid and ask of instruments kept up to date from the market data feed. Instruments have to be subscribed in full
+// or option greeks mode for depth to be sent. Clones share the prices.
+#[derive(Debug, Clone, Default)]
+pub struct BestPrices {
+    prices: Arc<StdMutex<HashMap<String, BestBidAsk>>>,
+}
+
+impl BestPrices {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Callback for the market data feed which updates the prices and then passes the data on to callback if given
+    pub fn market_data_feed_callback(
+        &self,
+        mut callback: Option<Box<dyn FnMut(MarketDataFeedV3Response) + Send + Sync>>,
+    ) -> Box<dyn FnMut(MarketDataFeedV3Response) + Send + Sync> {
+        let best_prices: BestPrices = self.clone();
+        Box::new(move |data: MarketDataFeedV3Response| {
+            best_prices.on_market_data_feed(&data);
+            if let Some(callback) = &mut callback {
+                callback(data);
+            }
+        })
+    }
+
+    pub fn on_market_data_feed(&self, data: &MarketDataFeedV3Response) {
+        let mut prices = self.prices.lock().unwrap();
+        for (instrument_key, feed) in &data.feeds {
+            if let Some(quote) = Self::first_depth(feed)
+                && quote.bidP > 0.0
+                && quote.askP > 0.0
+            {
+                prices.insert(
+                    instrument_key.clone(),
+                    BestBidAsk {
+                        bid: quote.bidP,
+                        ask: quote.askP,
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn get(&self, instrument_key: &InstrumentKey) -> Option<BestBidAsk> {
+        self.prices
+            .lock()
+            .unwrap()
+            .get(&instrument_key.to_string())
+            .copied()
+    }
+
+    fn first_depth(feed: &Feed) -> Option<&Quote> {
+        if feed.has_fullFeed() && feed.fullFeed().has_marketFF() {
+            return feed.fullFeed().marketFF().marketLevel.bidAskQuote.first();
+        }
+        if feed.has_firstLevelWithGreeks() {
+            return feed.firstLevelWithGreeks().firstDepth.as_ref();
+        }
+        None
+    }
+}
+
+#[derive(Debug)]
+struct ChildOrder {
+    order_id: String,
+    status: Option<OrderStatus>,
+    price: f64,
+    filled_quantity: u32,
+    average_price: f64,
+}
+
+struct Execution<'a> {
+    api_client: &'a ApiClient,
+    sandbox: bool,
+    quantity: u32,
+    placed_quantity: u32,
+    children: Vec<ChildOrder>,
+    error: Option<String>,
+    on_progress: Option<ExecutionProgressCallback>,
+}
+
+impl<'a> Execution<'a> {
+    fn new(
+        api_client: &'a ApiClient,
+        sandbox: bool,
+        quantity: u32,
+        on_progress: Option<ExecutionProgressCallback>,
+    ) -> Self {
+        Execution {
+            api_client,
+            sandbox,
+            quantity,
+            placed_quantity: 0,
+            children: Vec::new(),
+            error: None,
+            on_progress,
+        }
+    }
+
+    // Places a child order, recording the error and returning None if it is not placed
+    async fn place(&mut self, order_body: PlaceOrderV3Request) -> Option<usize> {
+        let quantity: u32 = order_body.quantity;
+        let price: f64 = order_body.price;
+        match retry_on_rate_limit(|| {
+            self.api_client
+                .place_order_v3(order_body.clone(), self.sandbox)
+        })
+        .await
+        {
+            Ok(response) => {
+                self.placed_quantity += quantity;
+                for order_id in response.data.order_ids {
+                    self.children.push(ChildOrder {
+                        order_id,
+                        status: None,
+                        price,
+                        filled_quantity: 0,
+                        average_price: 0.0,
+                    });
+                }
+                self.report();
+                Some(self.children.len() - 1)
+            }
+            Err(error_response) => {
+                self.error = Some(error_response.message());
+                self.report();
+                None
+            }
+        }
+    }
+
+    // Fetches the details of child orders which are not terminal yet. Failures are retried on the next refresh.
+    async fn refresh(&mut self) {
+        for child in &mut self.children {
+            if child.status.is_some_and(|status| status.is_terminal()) {
+                continue;
+            }
+            if let Ok(response) = retry_on_rate_limit(|| {
+                self.api_client.get_order_details(OrderDetailsRequest {
+                    order_id: Some(child.order_id.clone()),
+                    tag: None,
+                })
+            })
+            .await
+            {
+                child.status = Some(response.data.status);
+                child.price = response.data.price;
+                child.filled_quantity = response.data.filled_quantity;
+                child.average_price = response.data.average_price;
+            }
+        }
+        self.report();
+    }
+
+    // Returns None if the child order is still not terminal at the deadline
+    async fn await_terminal(
+        &mut self,
+        index: usize,
+        poll_interval: Duration,
+        deadline: Instant,
+    ) -> Option<OrderStatus> {
+        loop {
+            self.refresh().await;
+            if let Some(status) = self.children[index].status
+                && status.is_terminal()
+            {
+                return Some(status);
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            sleep_until((Instant::now() + poll_interval).min(deadline)).await;
+        }
+    }
+
+    // Cancels the child order and waits for it to be terminal, recording an error if it is left open
+    async fn cancel(&mut self, index: usize, poll_interval: Duration) {
+        let order_id: String = self.children[index].order_id.clone();
+        if let Err(error_response) = retry_on_rate_limit(|| {
+            self.api_client
+                .cancel_order_v3(order_id.clone(), self.sandbox)
+        })
+        .await
+        {
+            self.error = Some(error_response.message());
+        }
+        if self
+            .await_terminal(index, poll_interval, Instant::now() + CANCEL_TIMEOUT)
+            .await
+            .is_none()
+        {
+            self.error = Some(format!(
+                "Order {} is still open after cancelling it{}",
+                order_id,
+                self.error
+                    .as_ref()
+                    .map(|error| format!(", {}", error))
+                    .unwrap_or_default()
+            ));
+        }
+    }
+
+    fn progress(&self) -> ExecutionProgress {
+        let filled_quantity: u32 = self
+            .children
+            .iter()
+            .map(|child| child.filled_quantity)
+            .sum();
+        let filled_value: f64 = self
+            .children
+            .iter()
+            .map(|child| child.average_price * child.filled_quantity as f64)
+            .sum();
+        ExecutionProgress {
+            quantity: self.quantity,
+            placed_quantity: self.placed_quantity,
+            filled_quantity,
+            average_price: match filled_quantity {
+                0 => 0.0,
+                _ => filled_value / filled_quantity as f64,
+            },
+            order_ids: self
+                .children
+                .iter()
+                .map(|child| child.order_id.clone())
+                .collect(),
+            error: self.error.clone(),
+        }
+    }
+
+    fn report(&self) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(&self.progress());
+        }
+    }
+}
+
+// Rounds the price to a tick without going past it, i.e. down when buying and up when selling
+fn round_to_tick(price: f64, tick: f64, transaction_type: TransactionType) -> f64 {
+    let ticks: f64 = price / tick;
+    let ticks: f64 = match transaction_type {
+        TransactionType::Buy => (ticks + PRICE_EPSILON).floor(),
+        TransactionType::Sell => (ticks - PRICE_EPSILON).ceil(),
+    };
+    // Removes the floating point error of multiplying by the tick
+    (ticks * tick * 1e4).round() / 1e4
+}
+
+// Splits the quantity into at most parts lot multiples, as evenly as possible
+fn split_lots(quantity: u32, lot_size: u32, parts: u32) -> Vec<u32> {
+    let lots: u32 = quantity / lot_size;
+    let parts: u32 = parts.min(lots).max(1);
+    (0..parts)
+        .map(|part| (lots / parts + u32::from(part < lots % parts)) * lot_size)
+        .collect()
+}
+
+impl ApiClient {
+    // Places the order in equal slices spread over the window. Child orders are placed with the order type and price of
+    // the order, so limit slices which have not filled by the end are left open.
+    pub async fn execute_twap(
+        &self,
+        order_body: PlaceOrderV3Request,
+        config: &TwapConfig,
+        sandbox: bool,
+        on_progress: Option<ExecutionProgressCallback>,
+    ) -> Result<ExecutionProgress, String> {
+        let lot_size: u32 = self.execution_lot_size(&order_body)?;
+        if config.slices == 0 {
+            return Err("TWAP needs at least one slice".to_string());
+        }
+        let slices: Vec<u32> = split_lots(order_body.quantity, lot_size, config.slices);
+        let interval: Duration = config.duration / slices.len() as u32;
+
+        let mut execution: Execution =
+            Execution::new(self, sandbox, order_body.quantity, on_progress);
+        let started_at: Instant = Instant::now();
+        for (index, quantity) in slices.into_iter().enumerate() {
+            sleep_until(started_at + interval * index as u32).await;
+            let child_order_body: PlaceOrderV3Request = PlaceOrderV3Request {
+                quantity,
+                ..order_body.clone()
+            };
+            if execution.place(child_order_body).await.is_none() {
+                break;
+            }
+            execution.refresh().await;
+        }
+        execution.refresh().await;
+        Ok(execution.progress())
+    }
+
+    // Places the order a visible quantity at a time, placing the next child once the previous one is filled
+    pub async fn execute_iceberg(
+        &self,
+        order_body: PlaceOrderV3Request,
+        config: &IcebergConfig,
+        sandbox: bool,
+        on_progress: Option<ExecutionProgressCallback>,
+    ) -> Result<ExecutionProgress, String> {
+        let lot_size: u32 = self.execution_lot_size(&order_body)?;
+        if config.visible_quantity == 0 || !config.visible_quantity.is_multiple_of(lot_size) {
+            return Err(format!(
+                "Visible quantity must be a multiple of the lot size {}",
+                lot_size
+            ));
+        }
+
+        let mut execution: Execution =
+            Execution::new(self, sandbox, order_body.quantity, on_progress);
+        let deadline: Instant = Instant::now() + config.timeout;
+        while execution.placed_quantity < order_body.quantity {
+            let child_order_body: PlaceOrderV3Request = PlaceOrderV3Request {
+                quantity: config
+                    .visible_quantity
+                    .min(order_body.quantity - execution.placed_quantity),
+                ..order_body.clone()
+            };
+            let Some(index) = execution.place(child_order_body).await else {
+                break;
+            };
+            let Some(status) = execution
+                .await_terminal(index, config.poll_interval, deadline)
+                .await
+            else {
+                execution.cancel(index, config.poll_interval).await;
+                if execution.error.is_none() {
+                    execution.error = Some(format!(
+                        "Order {} was not filled within the timeout",
+                        execution.children[index].order_id
+                    ));
+                }
+                break;
+            };
+            if status != OrderStatus::Complete {
+                execution.error = Some(format!(
+                    "Order {} was not filled, {}",
+                    execution.children[index].order_id, status
+                ));
+                break;
+            }
+        }
+        execution.report();
+        Ok(execution.progress())
+    }
+
+    // Places a limit order at the best price on the order's side of the book and keeps modifying it to follow the best
+    // price until it fills, never going past the price cap
+    pub async fn execute_chase(
+        &self,
+        order_body: PlaceOrderV3Request,
+        best_prices: &BestPrices,
+        config: &ChaseConfig,
+        sandbox: bool,
+        on_progress: Option<ExecutionProgressCallback>,
+    ) -> Result<ExecutionProgress, String> {
+        // Tick sizes in the instrument master are in paise
+        let tick: Option<f64> = self
+            .instruments
+            .as_ref()
+            .and_then(|instruments| instruments.get(&order_body.instrument_token))
+            .and_then(|instrument| instrument.tick_size())
+            .filter(|&tick_size| tick_size > 0.0)
+            .map(|tick_size| tick_size / 100.0);
+        let chase_price = |best_bid_ask: BestBidAsk| -> f64 {
+            let price: f64 = match (order_body.transaction_type, config.aggressive) {
+                (TransactionType::Buy, false) => best_bid_ask.bid.min(config.price_cap),
+                (TransactionType::Buy, true) => best_bid_ask.ask.min(config.price_cap),
+                (TransactionType::Sell, false) => best_bid_ask.ask.max(config.price_cap),
+                (TransactionType::Sell, true) => best_bid_ask.bid.max(config.price_cap),
+            };
+            match tick {
+                Some(tick) => round_to_tick(price, tick, order_body.transaction_type),
+                None => price,
+            }
+        };
+        let best_bid_ask: BestBidAsk = best_prices
+            .get(&order_body.instrument_token)
+            .ok_or_else(|| format!("No best price for {}", order_body.instrument_token))?;
+
+        let mut execution: Execution =
+            Execution::new(self, sandbox, order_body.quantity, on_progress);
+        let Some(index) = execution
+            .place(PlaceOrderV3Request {
+                price: chase_price(best_bid_ask),
+                order_type: OrderType::Limit,
+                trigger_price: None,
+                slice: None,
+                ..order_body.clone()
+            })
+            .await
+        else {
+            return Ok(execution.progress());
+        };
+
+        let deadline: Option<Instant> = config.timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            sleep(config.poll_interval).await;
+            execution.refresh().await;
+            let child: &ChildOrder = &execution.children[index];
+            if child.status.is_some_and(|status| status.is_terminal()) {
+                break;
+            }
+            let order_id: String = child.order_id.clone();
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                execution.cancel(index, config.poll_interval).await;
+                break;
+            }
+
+            let Some(best_bid_ask) = best_prices.get(&order_body.instrument_token) else {
+                continue;
+            };
+            let price: f64 = chase_price(best_bid_ask);
+            if (price - child.price).abs() < PRICE_EPSILON {
+                continue;
+            }
+            // Rejected modifications, e.g. of an order filled in the meantime, are picked up on the next refresh
+            if retry_on_rate_limit(|| {
+                self.modify_order_v3(
+                    ModifyOrderRequest {
+                        quantity: Some(order_body.quantity),
+                        validity: order_body.validity,
+                        price,
+                        order_id: order_id.clone(),
+                        order_type: OrderType::Limit,
+                        disclosed_quantity: None,
+                        trigger_price: None,
+                    },
+                    sandbox,
+                )
+            })
+            .await
+            .is_ok()
+            {
+                execution.children[index].price = price;
+            }
+        }
+        execution.report();
+        Ok(execution.progress())
+    }
+
+    // Lot size in the units of the order quantity, which is lots for MCX
+    fn execution_lot_size(&self, order_body: &PlaceOrderV3Request) -> Result<u32, String> {
+        let lot_size: u32 = match order_body.instrument_token.segment() {
+            ExchangeSegment::McxFo => 1,
+            _ => self
+                .instruments
+                .as_ref()
+                .and_then(|instruments| instruments.get(&order_body.instrument_token))
+                .and_then(|instrument| instrument.lot_size())
+                .unwrap_or(1),
+        };
+        match order_body.quantity.is_multiple_of(lot_size) {
+            true => Ok(lot_size),
+            false => Err(format!(
+                "Quantity {} is not a multiple of the lot size {}",
+                order_body.quantity, lot_size
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_lots_evenly_with_larger_parts_first() {
+        assert_eq!(split_lots(500, 50, 3), vec![200, 150, 150]);
+        assert_eq!(split_lots(300, 50, 3), vec![100, 100, 100]);
+    }
+
+    #[test]
+    fn splits_into_at_most_one_part_per_lot() {
+        assert_eq!(split_lots(100, 50, 5), vec![50, 50]);
+        assert_eq!(split_lots(50, 50, 0), vec![50]);
+    }
+
+    #[test]
+    fn rounds_to_tick_without_going_past_price() {
+        assert_eq!(round_to_tick(101.07, 0.05, TransactionType::Buy), 101.05);
+        assert_eq!(round_to_tick(101.07, 0.05, TransactionType::Sell), 101.1);
+        assert_eq!(round_to_tick(101.05, 0.05, TransactionType::Buy), 101.05);
+        assert_eq!(round_to_tick(101.05, 0.05, TransactionType::Sell), 101.05);
+    }
+}
@@ -0,0 +1,178 @@
+use {
+    crate::{
+        client::ApiClient,
+        models::{
+            error_response::ErrorResponse,
+            orders::{
+                order_details_response::OrderDetailsResponse, orders_response::OrdersResponse,
+                place_order_v3_request::PlaceOrderV3Request,
+            },
+            success_response::SuccessResponse,
+        },
+        rate_limiter::retry_on_rate_limit,
+    },
+    rand::{Rng, distr::Alphanumeric},
+    tokio::time::{Duration, Instant, sleep},
+    tracing::warn,
+};
+
+const TAG_LENGTH: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyConfig {
+    // Placement requests not answered within this time fail with a transport error and are treated as having an unknown
+    // outcome. Time spent waiting on the rate limit and risk checks is not included.
+    pub request_timeout: Duration,
+    pub max_attempts: u32,
+    // How long the order book is checked for the tag after a failed request before the order is sent again
+    pub confirmation_timeout: Duration,
+    pub confirmation_interval: Duration,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        IdempotencyConfig {
+            request_timeout: Duration::from_secs(5),
+            max_attempts: 3,
+            confirmation_timeout: Duration::from_secs(5),
+            confirmation_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlacementOutcome {
+    Placed { tag: String, order_ids: Vec<String> },
+    NotPlaced { tag: String, reason: String },
+}
+
+impl PlacementOutcome {
+    pub fn tag(&self) -> &str {
+        match self {
+            PlacementOutcome::Placed { tag, .. } | PlacementOutcome::NotPlaced { tag, .. } => tag,
+        }
+    }
+
+    pub fn is_placed(&self) -> bool {
+        matches!(self, PlacementOutcome::Placed { .. })
+    }
+}
+
+pub fn generate_tag() -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(TAG_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+impl ApiClient {
+    // Places the order stamped with a unique tag. When the outcome of a placement is unknown the order book is checked
+    // for the tag before the order is sent again, so it is never placed twice. A tag already on the order is kept, which
+    // lets an order be placed again with the same tag after a restart without duplicating it.
+    pub async fn place_order_idempotent(
+        &self,
+        place_order_body: PlaceOrderV3Request,
+        sandbox: bool,
+        config: &IdempotencyConfig,
+    ) -> Result<PlacementOutcome, String> {
+        let tag: String = match &place_order_body.tag {
+            Some(tag) => {
+                let order_ids: Vec<String> = self.order_ids_by_tag(tag).await?;
+                if !order_ids.is_empty() {
+                    return Ok(PlacementOutcome::Placed {
+                        tag: tag.clone(),
+                        order_ids,
+                    });
+                }
+                tag.clone()
+            }
+            None => generate_tag(),
+        };
+        let place_order_body: PlaceOrderV3Request = PlaceOrderV3Request {
+            tag: Some(tag.clone()),
+            ..place_order_body
+        };
+
+        let mut reason: String = "No placement attempts were made".to_string();
+        for attempt in 1..=config.max_attempts {
+            let result: Result<SuccessResponse<OrdersResponse>, ErrorResponse> =
+                retry_on_rate_limit(|| {
+                    self.place_order_v3_with_timeout(
+                        place_order_body.clone(),
+                        sandbox,
+                        Some(config.request_timeout),
+                    )
+                })
+                .await;
+
+            match result {
+                Ok(response) => {
+                    return Ok(PlacementOutcome::Placed {
+                        tag,
+                        order_ids: response.data.order_ids,
+                    });
+                }
+                // The request was sent, or timed out, without a response being read. It has finished on this side, and
+                // the order is sent again only if its tag does not show up in the order book in the meantime.
+                Err(error_response) if error_response.is_transport_error() => {
+                    warn!(
+                        "Placing order tagged {} failed on attempt {}, checking order book: {}",
+                        tag,
+                        attempt,
+                        error_response.message()
+                    );
+                    let order_ids: Vec<String> = self.await_order_ids_by_tag(&tag, config).await?;
+                    if !order_ids.is_empty() {
+                        return Ok(PlacementOutcome::Placed { tag, order_ids });
+                    }
+                    reason = format!(
+                        "Placing order failed {} times: {}",
+                        attempt,
+                        error_response.message()
+                    );
+                }
+                // Server errors may still have placed the order
+                Err(error_response) => {
+                    let order_ids: Vec<String> = self.order_ids_by_tag(&tag).await?;
+                    return Ok(match order_ids.is_empty() {
+                        true => PlacementOutcome::NotPlaced {
+                            tag,
+                            reason: error_response.message(),
+                        },
+                        false => PlacementOutcome::Placed { tag, order_ids },
+                    });
+                }
+            }
+        }
+        Ok(PlacementOutcome::NotPlaced { tag, reason })
+    }
+
+    pub async fn order_ids_by_tag(&self, tag: &str) -> Result<Vec<String>, String> {
+        let order_book: Vec<OrderDetailsResponse> = retry_on_rate_limit(|| self.get_order_book())
+            .await
+            .map(|response| response.data)
+            .map_err(|error_response| error_response.message())?;
+        Ok(order_book
+            .into_iter()
+            .filter(|order| order.tag.as_deref() == Some(tag))
+            .map(|order| order.order_id)
+            .collect())
+    }
+
+    // Orders can show up in the order book some time after being placed
+    async fn await_order_ids_by_tag(
+        &self,
+        tag: &str,
+        config: &IdempotencyConfig,
+    ) -> Result<Vec<String>, String> {
+        let deadline: Instant = Instant::now() + config.confirmation_timeout;
+        loop {
+            let order_ids: Vec<String> = self.order_ids_by_tag(tag).await?;
+            if !order_ids.is_empty() || Instant::now() >= deadline {
+                return Ok(order_ids);
+            }
+            sleep(config.confirmation_interval).await;
+        }
+    }
+}
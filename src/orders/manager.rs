@@ -0,0 +1,449 @@
+use {
+    crate::{
+        client::ApiClient,
+        models::{
+            OrderStatus, ProductType, TransactionType,
+            error_response::ErrorResponse,
+            instrument_key::InstrumentKey,
+            orders::{
+                order_details_request::OrderDetailsRequest,
+                order_details_response::OrderDetailsResponse, orders_response::OrdersResponse,
+                place_order_v3_request::PlaceOrderV3Request,
+            },
+            success_response::SuccessResponse,
+            ws::portfolio_feed_response::PortfolioFeedResponse,
+        },
+        rate_limiter::retry_on_rate_limit,
+    },
+    std::{
+        collections::{HashMap, HashSet},
+        sync::{Arc, Mutex as StdMutex},
+    },
+    tokio::{
+        sync::{Mutex, Notify},
+        time::{Duration, Instant, sleep_until},
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct TrackedOrder {
+    pub order_id: String,
+    pub instrument_key: InstrumentKey,
+    pub transaction_type: TransactionType,
+    pub product: ProductType,
+    pub status: OrderStatus,
+    pub quantity: u32,
+    pub filled_quantity: u32,
+    pub pending_quantity: u32,
+    pub price: f64,
+    pub average_price: f64,
+    pub tag: Option<String>,
+    pub status_message: Option<String>,
+    // Statuses the order went through, oldest first
+    pub transitions: Vec<OrderStatus>,
+    // Placed through this manager or marked with mark_placed_locally, as opposed to only seen on the feed or order book
+    pub placed_locally: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub order_id: String,
+    pub instrument_key: InstrumentKey,
+    pub transaction_type: TransactionType,
+    pub quantity: u32,
+    pub price: f64,
+}
+
+#[derive(Debug, Clone)]
+struct OrderUpdate {
+    order_id: String,
+    instrument_key: InstrumentKey,
+    transaction_type: TransactionType,
+    product: ProductType,
+    status: OrderStatus,
+    quantity: u32,
+    filled_quantity: u32,
+    pending_quantity: u32,
+    price: f64,
+    average_price: f64,
+    tag: Option<String>,
+    status_message: Option<String>,
+}
+
+impl From<&OrderDetailsResponse> for OrderUpdate {
+    fn from(order_details: &OrderDetailsResponse) -> Self {
+        OrderUpdate {
+            order_id: order_details.order_id.clone(),
+            instrument_key: order_details.instrument_token.clone(),
+            transaction_type: order_details.transaction_type,
+            product: order_details.product,
+            status: order_details.status,
+            quantity: order_details.quantity,
+            filled_quantity: order_details.filled_quantity,
+            pending_quantity: order_details.pending_quantity,
+            price: order_details.price,
+            average_price: order_details.average_price,
+            tag: order_details.tag.clone(),
+            status_message: order_details.status_message.clone(),
+        }
+    }
+}
+
+impl OrderUpdate {
+    fn from_portfolio_feed(data: &PortfolioFeedResponse) -> Option<Self> {
+        match data {
+            PortfolioFeedResponse::OrderData {
+                order_id,
+                instrument_key,
+                transaction_type,
+                product,
+                status,
+                quantity,
+                filled_quantity,
+                pending_quantity,
+                price,
+                average_price,
+                tag,
+                status_message,
+                ..
+            } => Some(OrderUpdate {
+                order_id: order_id.clone(),
+                instrument_key: instrument_key.clone(),
+                transaction_type: *transaction_type,
+                product: *product,
+                status: *status,
+                quantity: *quantity,
+                filled_quantity: *filled_quantity,
+                pending_quantity: *pending_quantity,
+                price: *price,
+                average_price: *average_price,
+                tag: tag.clone(),
+                status_message: Some(status_message.clone()).filter(|message| !message.is_empty()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct OrderBook {
+    orders: HashMap<String, TrackedOrder>,
+    fills: Vec<Fill>,
+    locally_placed_ids: HashSet<String>,
+}
+
+impl OrderBook {
+    fn apply(&mut self, update: OrderUpdate) {
+        let order: &mut TrackedOrder =
+            self.orders
+                .entry(update.order_id.clone())
+                .or_insert_with(|| TrackedOrder {
+                    order_id: update.order_id.clone(),
+                    instrument_key: update.instrument_key.clone(),
+                    transaction_type: update.transaction_type,
+                    product: update.product,
+                    status: update.status,
+                    quantity: update.quantity,
+                    filled_quantity: 0,
+                    pending_quantity: update.quantity,
+                    price: update.price,
+                    average_price: 0.0,
+                    tag: update.tag.clone(),
+                    status_message: None,
+                    transitions: Vec::new(),
+                    placed_locally: self.locally_placed_ids.contains(&update.order_id),
+                });
+
+        // Feed and polled updates can arrive out of order, older ones are dropped
+        if update.filled_quantity < order.filled_quantity
+            || (order.status.is_terminal() && !update.status.is_terminal())
+        {
+            return;
+        }
+
+        if update.filled_quantity > order.filled_quantity {
+            let quantity: u32 = update.filled_quantity - order.filled_quantity;
+            let price: f64 = (update.average_price * update.filled_quantity as f64
+                - order.average_price * order.filled_quantity as f64)
+                / quantity as f64;
+            self.fills.push(Fill {
+                order_id: order.order_id.clone(),
+                instrument_key: order.instrument_key.clone(),
+                transaction_type: order.transaction_type,
+                quantity,
+                price,
+            });
+        }
+
+        if order.transitions.last() != Some(&update.status) {
+            order.transitions.push(update.status);
+        }
+        order.status = update.status;
+        order.quantity = update.quantity;
+        order.filled_quantity = update.filled_quantity;
+        order.pending_quantity = update.pending_quantity;
+        order.price = update.price;
+        order.average_price = update.average_price;
+        order.tag = update.tag;
+        order.status_message = update.status_message;
+    }
+}
+
+// Tracks orders from placement until they complete, are rejected or cancelled. Updates come from the portfolio feed
+// when its callback is connected and orders are polled while awaited in case the feed misses them.
+#[derive(Debug, Clone)]
+pub struct OrderManager {
+    book: Arc<StdMutex<OrderBook>>,
+    updated: Arc<Notify>,
+    poll_interval: Duration,
+}
+
+impl OrderManager {
+    pub fn new(poll_interval: Duration) -> Self {
+        OrderManager {
+            book: Arc::new(StdMutex::new(OrderBook::default())),
+            updated: Arc::new(Notify::new()),
+            poll_interval,
+        }
+    }
+
+    // Callback for the portfolio feed which updates the tracked orders and then passes the data on to callback if given
+    pub fn portfolio_feed_callback(
+        &self,
+        mut callback: Option<Box<dyn FnMut(PortfolioFeedResponse) + Send + Sync>>,
+    ) -> Box<dyn FnMut(PortfolioFeedResponse) + Send + Sync> {
+        let order_manager: OrderManager = self.clone();
+        Box::new(move |data: PortfolioFeedResponse| {
+            order_manager.on_portfolio_feed(&data);
+            if let Some(callback) = &mut callback {
+                callback(data);
+            }
+        })
+    }
+
+    pub fn on_portfolio_feed(&self, data: &PortfolioFeedResponse) {
+        if let Some(update) = OrderUpdate::from_portfolio_feed(data) {
+            self.apply(update);
+        }
+    }
+
+    pub async fn place_order(
+        &self,
+        api_client: &ApiClient,
+        place_order_body: PlaceOrderV3Request,
+        sandbox: bool,
+    ) -> Result<Vec<String>, String> {
+        let result: Result<SuccessResponse<OrdersResponse>, ErrorResponse> =
+            retry_on_rate_limit(|| api_client.place_order_v3(place_order_body.clone(), sandbox))
+                .await;
+        let order_ids: Vec<String> = result
+            .map(|response| response.data.order_ids)
+            .map_err(|error_response| error_response.message())?;
+
+        let mut book = self.book.lock().unwrap();
+        for order_id in &order_ids {
+            book.locally_placed_ids.insert(order_id.clone());
+            // The feed may have already reported the order
+            book.orders
+                .entry(order_id.clone())
+                .and_modify(|order| order.placed_locally = true)
+                .or_insert_with(|| TrackedOrder {
+                    order_id: order_id.clone(),
+                    instrument_key: place_order_body.instrument_token.clone(),
+                    transaction_type: place_order_body.transaction_type,
+                    product: place_order_body.product,
+                    status: OrderStatus::ValidationPending,
+                    quantity: place_order_body.quantity,
+                    filled_quantity: 0,
+                    pending_quantity: place_order_body.quantity,
+                    price: place_order_body.price,
+                    average_price: 0.0,
+                    tag: place_order_body.tag.clone(),
+                    status_message: None,
+                    transitions: vec![OrderStatus::ValidationPending],
+                    placed_locally: true,
+                });
+        }
+        Ok(order_ids)
+    }
+
+    // For orders placed without the manager, e.g. basket and bracket legs, so reconciliation counts them as the app's own.
+    // Orders which are not tracked yet are marked once they are.
+    pub fn mark_placed_locally(&self, order_ids: &[String]) {
+        let mut book = self.book.lock().unwrap();
+        for order_id in order_ids {
+            book.locally_placed_ids.insert(order_id.clone());
+            if let Some(order) = book.orders.get_mut(order_id) {
+                order.placed_locally = true;
+            }
+        }
+    }
+
+    // Fetches the order over REST and starts tracking it if it is not tracked already
+    pub async fn refresh_order(
+        &self,
+        api_client: &ApiClient,
+        order_id: &str,
+    ) -> Result<TrackedOrder, String> {
+        let order_details: OrderDetailsResponse = retry_on_rate_limit(|| {
+            api_client.get_order_details(OrderDetailsRequest {
+                order_id: Some(order_id.to_string()),
+                tag: None,
+            })
+        })
+        .await
+        .map(|response| response.data)
+        .map_err(|error_response| error_response.message())?;
+        self.apply(OrderUpdate::from(&order_details));
+        self.order(order_id)
+            .ok_or_else(|| format!("Order {} is not tracked", order_id))
+    }
+
+    // Updates every order of the day from the order book
+    pub async fn sync(&self, api_client: &ApiClient) -> Result<(), String> {
+        let order_book: Vec<OrderDetailsResponse> =
+            retry_on_rate_limit(|| api_client.get_order_book())
+                .await
+                .map(|response| response.data)
+                .map_err(|error_response| error_response.message())?;
+        for order_details in &order_book {
+            self.apply(OrderUpdate::from(order_details));
+        }
+        Ok(())
+    }
+
+    // Waits until the order is complete, rejected or cancelled, polling it every poll interval in between
+    pub async fn await_terminal(
+        &self,
+        api_client: &Mutex<ApiClient>,
+        order_id: &str,
+        timeout: Option<Duration>,
+    ) -> Result<TrackedOrder, String> {
+        let deadline: Option<Instant> = timeout.map(|timeout| Instant::now() + timeout);
+        let mut next_poll_at: Instant = Instant::now();
+        loop {
+            let updated = self.updated.notified();
+            tokio::pin!(updated);
+            updated.as_mut().enable();
+
+            if let Some(order) = self.order(order_id)
+                && order.status.is_terminal()
+            {
+                return Ok(order);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(format!("Timed out waiting for order {}", order_id));
+            }
+
+            if Instant::now() >= next_poll_at {
+                // Errors are retried on the next poll since the order may not be queryable right after placement
+                if let Ok(Ok(response)) = api_client
+                    .lock()
+                    .await
+                    .get_order_details(OrderDetailsRequest {
+                        order_id: Some(order_id.to_string()),
+                        tag: None,
+                    })
+                    .await
+                {
+                    self.apply(OrderUpdate::from(&response.data));
+                }
+                next_poll_at = Instant::now() + self.poll_interval;
+                continue;
+            }
+
+            let wake_at: Instant =
+                deadline.map_or(next_poll_at, |deadline| deadline.min(next_poll_at));
+            tokio::select! {
+                _ = updated => {}
+                _ = sleep_until(wake_at) => {}
+            }
+        }
+    }
+
+    // Waits until the order is terminal and fails unless it was completely filled
+    pub async fn await_filled(
+        &self,
+        api_client: &Mutex<ApiClient>,
+        order_id: &str,
+        timeout: Option<Duration>,
+    ) -> Result<TrackedOrder, String> {
+        let order: TrackedOrder = self.await_terminal(api_client, order_id, timeout).await?;
+        match order.status {
+            OrderStatus::Complete => Ok(order),
+            status => Err(format!(
+                "Order {} was not filled, {}: {}",
+                order_id,
+                status,
+                order
+                    .status_message
+                    .as_deref()
+                    .unwrap_or("no status message")
+            )),
+        }
+    }
+
+    pub fn order(&self, order_id: &str) -> Option<TrackedOrder> {
+        self.book.lock().unwrap().orders.get(order_id).cloned()
+    }
+
+    pub fn orders(&self) -> Vec<TrackedOrder> {
+        self.book.lock().unwrap().orders.values().cloned().collect()
+    }
+
+    pub fn open_orders(&self) -> Vec<TrackedOrder> {
+        self.book
+            .lock()
+            .unwrap()
+            .orders
+            .values()
+            .filter(|order| !order.status.is_terminal())
+            .cloned()
+            .collect()
+    }
+
+    pub fn fills(&self) -> Vec<Fill> {
+        self.book.lock().unwrap().fills.clone()
+    }
+
+    pub fn order_fills(&self, order_id: &str) -> Vec<Fill> {
+        self.book
+            .lock()
+            .unwrap()
+            .fills
+            .iter()
+            .filter(|fill| fill.order_id == order_id)
+            .cloned()
+            .collect()
+    }
+
+    // Average price of all fills of the instrument on the given side
+    pub fn average_price(
+        &self,
+        instrument_key: &InstrumentKey,
+        transaction_type: TransactionType,
+    ) -> Option<f64> {
+        let book = self.book.lock().unwrap();
+        let (quantity, value): (u32, f64) = book
+            .fills
+            .iter()
+            .filter(|fill| {
+                &fill.instrument_key == instrument_key && fill.transaction_type == transaction_type
+            })
+            .fold((0, 0.0), |(quantity, value), fill| {
+                (
+                    quantity + fill.quantity,
+                    value + fill.price * fill.quantity as f64,
+                )
+            });
+        match quantity {
+            0 => None,
+            _ => Some(value / quantity as f64),
+        }
+    }
+
+    fn apply(&self, update: OrderUpdate) {
+        self.book.lock().unwrap().apply(update);
+        self.updated.notify_waiters();
+    }
+}
@@ -0,0 +1,9 @@
+pub mod basket;
+pub mod bracket;
+pub mod builder;
+pub mod execution;
+pub mod idempotency;
+pub mod manager;
+pub mod reconciliation;
+pub mod risk;
+pub mod slicer;
@@ -0,0 +1,419 @@
+use {
+    crate::{
+        client::ApiClient,
+        models::{
+            OrderStatus, ProductType, TransactionType,
+            instrument_key::InstrumentKey,
+            orders::{
+                order_details_response::OrderDetailsResponse,
+                trade_details_response::TradeDetailsResponse,
+            },
+            portfolio::{
+                holdings_response::HoldingsResponse, positions_response::PositionsResponse,
+            },
+        },
+        orders::manager::{Fill, OrderManager, TrackedOrder},
+        rate_limiter::retry_on_rate_limit,
+        utils::write_value_to_file,
+    },
+    chrono::Utc,
+    std::{
+        collections::{BTreeMap, HashMap, HashSet},
+        fmt::Display,
+        path::Path,
+    },
+};
+
+const CSV_HEADER: &str = "kind,order_id,instrument_key,product,local,broker";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+    // In the broker's order book but not placed locally, e.g. placed from another app
+    UnknownOrder {
+        order_id: String,
+        instrument_key: InstrumentKey,
+        status: OrderStatus,
+    },
+    // Placed locally but not in the broker's order book
+    MissingOrder {
+        order_id: String,
+        instrument_key: InstrumentKey,
+    },
+    StatusMismatch {
+        order_id: String,
+        local: OrderStatus,
+        broker: OrderStatus,
+    },
+    FilledQuantityMismatch {
+        order_id: String,
+        local: u32,
+        broker: u32,
+    },
+    // Trades of the order which were not recorded as fills
+    MissingFills {
+        order_id: String,
+        filled_quantity: u32,
+        traded_quantity: u32,
+    },
+    // Fills recorded beyond the trades of the order
+    ExcessFills {
+        order_id: String,
+        filled_quantity: u32,
+        traded_quantity: u32,
+    },
+    // Net quantity bought during the day by orders placed locally, per instrument and product
+    PositionMismatch {
+        instrument_key: InstrumentKey,
+        product: ProductType,
+        local: i64,
+        broker: i64,
+    },
+    // Delivery sells of an instrument which is not in the holdings, beyond the day's delivery buys of it
+    UnheldDeliverySell {
+        instrument_key: InstrumentKey,
+        quantity: u32,
+    },
+}
+
+impl Mismatch {
+    fn csv_row(&self) -> String {
+        let row: [String; 6] = match self {
+            Mismatch::UnknownOrder {
+                order_id,
+                instrument_key,
+                status,
+            } => [
+                "unknown_order".to_string(),
+                order_id.clone(),
+                instrument_key.to_string(),
+                String::new(),
+                String::new(),
+                status.to_string(),
+            ],
+            Mismatch::MissingOrder {
+                order_id,
+                instrument_key,
+            } => [
+                "missing_order".to_string(),
+                order_id.clone(),
+                instrument_key.to_string(),
+                String::new(),
+                String::new(),
+                String::new(),
+            ],
+            Mismatch::StatusMismatch {
+                order_id,
+                local,
+                broker,
+            } => Self::order_row("status_mismatch", order_id, local, broker),
+            Mismatch::FilledQuantityMismatch {
+                order_id,
+                local,
+                broker,
+            } => Self::order_row("filled_quantity_mismatch", order_id, local, broker),
+            Mismatch::MissingFills {
+                order_id,
+                filled_quantity,
+                traded_quantity,
+            } => Self::order_row("missing_fills", order_id, filled_quantity, traded_quantity),
+            Mismatch::ExcessFills {
+                order_id,
+                filled_quantity,
+                traded_quantity,
+            } => Self::order_row("excess_fills", order_id, filled_quantity, traded_quantity),
+            Mismatch::PositionMismatch {
+                instrument_key,
+                product,
+                local,
+                broker,
+            } => [
+                "position_mismatch".to_string(),
+                String::new(),
+                instrument_key.to_string(),
+                format!("{:?}", product),
+                local.to_string(),
+                broker.to_string(),
+            ],
+            Mismatch::UnheldDeliverySell {
+                instrument_key,
+                quantity,
+            } => [
+                "unheld_delivery_sell".to_string(),
+                String::new(),
+                instrument_key.to_string(),
+                format!("{:?}", ProductType::D),
+                quantity.to_string(),
+                String::new(),
+            ],
+        };
+        row.iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    fn order_row(
+        kind: &str,
+        order_id: &str,
+        local: impl Display,
+        broker: impl Display,
+    ) -> [String; 6] {
+        [
+            kind.to_string(),
+            order_id.to_string(),
+            String::new(),
+            String::new(),
+            local.to_string(),
+            broker.to_string(),
+        ]
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconciliationReport {
+    // RFC 3339 in UTC
+    pub generated_at: String,
+    pub orders_checked: usize,
+    pub trades_checked: usize,
+    pub positions_checked: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl ReconciliationReport {
+    pub fn is_clean(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv: String = format!("{}\n", CSV_HEADER);
+        for mismatch in &self.mismatches {
+            csv.push_str(&mismatch.csv_row());
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path: &Path = path.as_ref();
+        write_value_to_file(path, &self.to_csv()).map_err(|err| {
+            format!(
+                "Failed to write reconciliation report to {:?}: {}",
+                path, err
+            )
+        })
+    }
+}
+
+impl OrderManager {
+    // Compares the orders placed locally and their fills with the broker's order book, trades, positions and holdings of
+    // the day. Orders only seen on the portfolio feed or order book are treated as placed outside the app.
+    pub async fn reconcile(&self, api_client: &ApiClient) -> Result<ReconciliationReport, String> {
+        let order_book: Vec<OrderDetailsResponse> =
+            retry_on_rate_limit(|| api_client.get_order_book())
+                .await
+                .map_err(|error_response| error_response.message())?
+                .data;
+        let trades: Vec<TradeDetailsResponse> = retry_on_rate_limit(|| api_client.get_trades())
+            .await
+            .map_err(|error_response| error_response.message())?
+            .data;
+        let positions: Vec<PositionsResponse> = retry_on_rate_limit(|| api_client.get_positions())
+            .await
+            .map_err(|error_response| error_response.message())?
+            .data;
+        let holdings: Vec<HoldingsResponse> = retry_on_rate_limit(|| api_client.get_holdings())
+            .await
+            .map_err(|error_response| error_response.message())?
+            .data;
+
+        let orders: HashMap<String, TrackedOrder> = self
+            .orders()
+            .into_iter()
+            .filter(|order| order.placed_locally)
+            .map(|order| (order.order_id.clone(), order))
+            .collect();
+        let fills: Vec<Fill> = self
+            .fills()
+            .into_iter()
+            .filter(|fill| orders.contains_key(&fill.order_id))
+            .collect();
+        let mut mismatches: Vec<Mismatch> = Vec::new();
+
+        let broker_order_ids: HashSet<&str> = order_book
+            .iter()
+            .map(|order| order.order_id.as_str())
+            .collect();
+        for broker_order in &order_book {
+            let Some(order) = orders.get(&broker_order.order_id) else {
+                mismatches.push(Mismatch::UnknownOrder {
+                    order_id: broker_order.order_id.clone(),
+                    instrument_key: broker_order.instrument_token.clone(),
+                    status: broker_order.status,
+                });
+                continue;
+            };
+            if order.status != broker_order.status {
+                mismatches.push(Mismatch::StatusMismatch {
+                    order_id: order.order_id.clone(),
+                    local: order.status,
+                    broker: broker_order.status,
+                });
+            }
+            if order.filled_quantity != broker_order.filled_quantity {
+                mismatches.push(Mismatch::FilledQuantityMismatch {
+                    order_id: order.order_id.clone(),
+                    local: order.filled_quantity,
+                    broker: broker_order.filled_quantity,
+                });
+            }
+        }
+        let mut missing_orders: Vec<&TrackedOrder> = orders
+            .values()
+            .filter(|order| !broker_order_ids.contains(order.order_id.as_str()))
+            .collect();
+        missing_orders.sort_by(|a, b| a.order_id.cmp(&b.order_id));
+        mismatches.extend(
+            missing_orders
+                .into_iter()
+                .map(|order| Mismatch::MissingOrder {
+                    order_id: order.order_id.clone(),
+                    instrument_key: order.instrument_key.clone(),
+                }),
+        );
+
+        // Fills of local orders against their trades
+        let mut quantities: BTreeMap<&str, (u32, u32)> = BTreeMap::new();
+        for fill in &fills {
+            quantities.entry(fill.order_id.as_str()).or_default().0 += fill.quantity;
+        }
+        for trade in trades
+            .iter()
+            .filter(|trade| orders.contains_key(&trade.order_id))
+        {
+            quantities.entry(trade.order_id.as_str()).or_default().1 += trade.quantity;
+        }
+        for (order_id, (filled_quantity, traded_quantity)) in quantities {
+            if filled_quantity < traded_quantity {
+                mismatches.push(Mismatch::MissingFills {
+                    order_id: order_id.to_string(),
+                    filled_quantity,
+                    traded_quantity,
+                });
+            } else if filled_quantity > traded_quantity {
+                mismatches.push(Mismatch::ExcessFills {
+                    order_id: order_id.to_string(),
+                    filled_quantity,
+                    traded_quantity,
+                });
+            }
+        }
+
+        // Net quantities of the day from the fills against the positions
+        let mut net_quantities: HashMap<(InstrumentKey, ProductType), (i64, i64)> = HashMap::new();
+        for fill in &fills {
+            let Some(order) = orders.get(&fill.order_id) else {
+                continue;
+            };
+            let quantity: i64 = match fill.transaction_type {
+                TransactionType::Buy => fill.quantity as i64,
+                TransactionType::Sell => -(fill.quantity as i64),
+            };
+            net_quantities
+                .entry((fill.instrument_key.clone(), order.product))
+                .or_default()
+                .0 += quantity;
+        }
+        for position in &positions {
+            net_quantities
+                .entry((position.instrument_token.clone(), position.product))
+                .or_default()
+                .1 += position.day_buy_quantity as i64 - position.day_sell_quantity as i64;
+        }
+        // Trades of orders placed outside the app are in the positions but have no local fills, they are reported as
+        // unknown orders instead
+        for trade in trades
+            .iter()
+            .filter(|trade| !orders.contains_key(&trade.order_id))
+        {
+            let quantity: i64 = match trade.transaction_type {
+                TransactionType::Buy => trade.quantity as i64,
+                TransactionType::Sell => -(trade.quantity as i64),
+            };
+            net_quantities
+                .entry((trade.instrument_token.clone(), trade.product))
+                .or_default()
+                .1 -= quantity;
+        }
+        let mut position_mismatches: Vec<Mismatch> = net_quantities
+            .iter()
+            .filter(|(_, (local, broker))| local != broker)
+            .map(
+                |((instrument_key, product), (local, broker))| Mismatch::PositionMismatch {
+                    instrument_key: instrument_key.clone(),
+                    product: *product,
+                    local: *local,
+                    broker: *broker,
+                },
+            )
+            .collect();
+        position_mismatches.sort_by_key(|mismatch| match mismatch {
+            Mismatch::PositionMismatch { instrument_key, .. } => instrument_key.to_string(),
+            _ => String::new(),
+        });
+        mismatches.extend(position_mismatches);
+
+        // Delivery sells need the instrument to be held or bought for delivery during the day, as the day's buys are not
+        // in the holdings yet
+        let held: HashSet<&InstrumentKey> = holdings
+            .iter()
+            .map(|holding| &holding.instrument_token)
+            .collect();
+        let mut unheld_sells: BTreeMap<String, (InstrumentKey, i64)> = BTreeMap::new();
+        for fill in fills
+            .iter()
+            .filter(|fill| fill.transaction_type == TransactionType::Sell)
+            .filter(|fill| !held.contains(&fill.instrument_key))
+        {
+            if orders
+                .get(&fill.order_id)
+                .is_some_and(|order| order.product == ProductType::D)
+            {
+                unheld_sells
+                    .entry(fill.instrument_key.to_string())
+                    .or_insert_with(|| (fill.instrument_key.clone(), 0))
+                    .1 += fill.quantity as i64;
+            }
+        }
+        for trade in trades.iter().filter(|trade| {
+            trade.transaction_type == TransactionType::Buy && trade.product == ProductType::D
+        }) {
+            if let Some((_, quantity)) = unheld_sells.get_mut(&trade.instrument_token.to_string()) {
+                *quantity -= trade.quantity as i64;
+            }
+        }
+        mismatches.extend(
+            unheld_sells
+                .into_values()
+                .filter(|(_, quantity)| *quantity > 0)
+                .map(|(instrument_key, quantity)| Mismatch::UnheldDeliverySell {
+                    instrument_key,
+                    quantity: quantity as u32,
+                }),
+        );
+
+        Ok(ReconciliationReport {
+            generated_at: Utc::now().to_rfc3339(),
+            orders_checked: order_book.len(),
+            trades_checked: trades.len(),
+            positions_checked: positions.len(),
+            mismatches,
+        })
+    }
+}
@@ -0,0 +1,663 @@
+use {
+    crate::{
+        client::ApiClient,
+        models::{
+            ExchangeSegment, ProductType, TransactionType,
+            error_response::{ErrorResponse, Errors},
+            gtt_orders::{GTTRuleStrategy, place_gtt_order_request::PlaceGTTOrderRequest},
+            instrument_key::InstrumentKey,
+            market_quote::{
+                full_market_quotes_request::FullMarketQuotesRequest,
+                full_market_quotes_response::FullMarketQuotesResponse,
+            },
+            orders::{
+                exit_all_positions_request::ExitAllPositionsRequest,
+                modify_order_request::ModifyOrderRequest,
+                order_details_request::OrderDetailsRequest,
+                order_details_response::OrderDetailsResponse,
+                place_multi_order_request::PlaceMultiOrderRequest,
+                place_order_v3_request::PlaceOrderV3Request,
+            },
+            portfolio::positions_response::PositionsResponse,
+        },
+        rate_limiter::retry_on_rate_limit,
+    },
+    std::{
+        collections::HashMap,
+        fmt,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+    },
+    tracing::warn,
+};
+
+pub const RISK_CHECK_ERROR_CODE: &str = "RISK_CHECK_FAILED";
+
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    // Quantity times price, using the last traded price for market orders
+    pub max_order_value: Option<f64>,
+    pub max_quantity: Option<u32>,
+    // Overrides max_quantity for the instrument
+    pub max_quantity_per_instrument: HashMap<InstrumentKey, u32>,
+    pub max_open_orders: Option<usize>,
+    // Positive amount of realised and unrealised loss of the day's positions after which new orders are blocked
+    pub max_daily_loss: Option<f64>,
+    // Reject limit prices and triggers outside the circuit limits of the instrument
+    pub check_circuit_limits: bool,
+    pub exit_positions_on_kill: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskViolation {
+    KillSwitchEngaged,
+    MaxOrderValue {
+        instrument_key: InstrumentKey,
+        value: f64,
+        limit: f64,
+    },
+    MaxQuantity {
+        instrument_key: InstrumentKey,
+        quantity: u32,
+        limit: u32,
+    },
+    MaxOpenOrders {
+        open_orders: usize,
+        limit: usize,
+    },
+    MaxDailyLoss {
+        loss: f64,
+        limit: f64,
+    },
+    OutsideCircuitLimits {
+        instrument_key: InstrumentKey,
+        price: f64,
+        lower_circuit_limit: f64,
+        upper_circuit_limit: f64,
+    },
+    // Data needed for a check could not be fetched, orders are rejected rather than sent unchecked
+    CheckFailed(String),
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskViolation::KillSwitchEngaged => write!(f, "Kill switch is engaged"),
+            RiskViolation::MaxOrderValue {
+                instrument_key,
+                value,
+                limit,
+            } => write!(
+                f,
+                "Order value {:.2} for {} exceeds limit {:.2}",
+                value, instrument_key, limit
+            ),
+            RiskViolation::MaxQuantity {
+                instrument_key,
+                quantity,
+                limit,
+            } => write!(
+                f,
+                "Quantity {} for {} exceeds limit {}",
+                quantity, instrument_key, limit
+            ),
+            RiskViolation::MaxOpenOrders { open_orders, limit } => write!(
+                f,
+                "{} open orders would exceed limit {}",
+                open_orders, limit
+            ),
+            RiskViolation::MaxDailyLoss { loss, limit } => {
+                write!(f, "Daily loss {:.2} reached limit {:.2}", loss, limit)
+            }
+            RiskViolation::OutsideCircuitLimits {
+                instrument_key,
+                price,
+                lower_circuit_limit,
+                upper_circuit_limit,
+            } => write!(
+                f,
+                "Price {} for {} is outside circuit limits {} - {}",
+                price, instrument_key, lower_circuit_limit, upper_circuit_limit
+            ),
+            RiskViolation::CheckFailed(reason) => write!(f, "Risk check failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for RiskViolation {}
+
+// Returned by the order APIs in place of the exchange's response when an order is blocked
+impl From<RiskViolation> for ErrorResponse {
+    fn from(risk_violation: RiskViolation) -> Self {
+        ErrorResponse {
+            status: "error".to_string(),
+            data: None,
+            errors: vec![Errors {
+                error_code: RISK_CHECK_ERROR_CODE.to_string(),
+                message: risk_violation.to_string(),
+                property_path: None,
+                invalid_value: None,
+                order_id: None,
+                instrument_key: match &risk_violation {
+                    RiskViolation::MaxOrderValue { instrument_key, .. }
+                    | RiskViolation::MaxQuantity { instrument_key, .. }
+                    | RiskViolation::OutsideCircuitLimits { instrument_key, .. } => {
+                        Some(instrument_key.to_string())
+                    }
+                    _ => None,
+                },
+                correlation_id: None,
+            }],
+            summary: None,
+        }
+    }
+}
+
+// Order as seen by the risk checks. Price is None for market orders.
+#[derive(Debug, Clone)]
+struct OrderRisk {
+    instrument_key: InstrumentKey,
+    product: ProductType,
+    transaction_type: TransactionType,
+    quantity: u32,
+    price: Option<f64>,
+    trigger_price: Option<f64>,
+}
+
+// Net quantity of each instrument and product which orders can still close, i.e. the position less the pending quantity of
+// open orders already closing it. Positive for long positions and negative for short ones.
+#[derive(Debug, Default)]
+struct ReduciblePositions {
+    quantities: HashMap<(InstrumentKey, ProductType), i64>,
+}
+
+impl ReduciblePositions {
+    fn new(positions: &[PositionsResponse], open_orders: &[OrderDetailsResponse]) -> Self {
+        let mut reducible: ReduciblePositions = ReduciblePositions::default();
+        for position in positions {
+            *reducible
+                .quantities
+                .entry((position.instrument_token.clone(), position.product))
+                .or_default() += (position.overnight_buy_quantity + position.day_buy_quantity)
+                as i64
+                - (position.overnight_sell_quantity + position.day_sell_quantity) as i64;
+        }
+        for order in open_orders
+            .iter()
+            .filter(|order| !order.status.is_terminal())
+        {
+            reducible.take_pending(
+                &order.instrument_token,
+                order.product,
+                order.transaction_type,
+                order.pending_quantity,
+            );
+        }
+        reducible
+    }
+
+    // Takes the part of the quantity which closes the position
+    fn take_pending(
+        &mut self,
+        instrument_key: &InstrumentKey,
+        product: ProductType,
+        transaction_type: TransactionType,
+        quantity: u32,
+    ) {
+        let Some(net_quantity) = self.quantities.get_mut(&(instrument_key.clone(), product)) else {
+            return;
+        };
+        *net_quantity = match transaction_type {
+            TransactionType::Buy if *net_quantity < 0 => (*net_quantity + quantity as i64).min(0),
+            TransactionType::Sell if *net_quantity > 0 => (*net_quantity - quantity as i64).max(0),
+            _ => *net_quantity,
+        };
+    }
+
+    // Orders which at most close what is left of the position of the instrument and product, such as stop losses, targets
+    // and rollbacks, reduce exposure. Their quantity is taken so that the orders after them cannot close it again.
+    fn reduce(&mut self, order: &OrderRisk) -> bool {
+        let Some(&net_quantity) = self
+            .quantities
+            .get(&(order.instrument_key.clone(), order.product))
+        else {
+            return false;
+        };
+        let reduces: bool = match order.transaction_type {
+            TransactionType::Buy => net_quantity < 0 && order.quantity as i64 <= -net_quantity,
+            TransactionType::Sell => net_quantity > 0 && order.quantity as i64 <= net_quantity,
+        };
+        if reduces {
+            self.take_pending(
+                &order.instrument_key,
+                order.product,
+                order.transaction_type,
+                order.quantity,
+            );
+        }
+        reduces
+    }
+}
+
+impl From<&PlaceOrderV3Request> for OrderRisk {
+    fn from(order: &PlaceOrderV3Request) -> Self {
+        OrderRisk {
+            instrument_key: order.instrument_token.clone(),
+            product: order.product,
+            transaction_type: order.transaction_type,
+            quantity: order.quantity,
+            price: Some(order.price).filter(|price| *price > 0.0),
+            trigger_price: order.trigger_price.filter(|price| *price > 0.0),
+        }
+    }
+}
+
+impl From<&PlaceMultiOrderRequest> for OrderRisk {
+    fn from(order: &PlaceMultiOrderRequest) -> Self {
+        OrderRisk {
+            instrument_key: order.instrument_token.clone(),
+            product: order.product,
+            transaction_type: order.transaction_type,
+            quantity: order.quantity,
+            price: Some(order.price).filter(|price| *price > 0.0),
+            trigger_price: order.trigger_price.filter(|price| *price > 0.0),
+        }
+    }
+}
+
+impl From<&PlaceGTTOrderRequest> for OrderRisk {
+    fn from(order: &PlaceGTTOrderRequest) -> Self {
+        OrderRisk {
+            instrument_key: order.instrument_token.clone(),
+            product: order.product,
+            transaction_type: order.transaction_type,
+            quantity: order.quantity,
+            price: order
+                .rules
+                .iter()
+                .find(|rule| matches!(rule.strategy, GTTRuleStrategy::Entry))
+                .map(|rule| rule.trigger_price),
+            trigger_price: None,
+        }
+    }
+}
+
+// Checks orders against the limits before they are sent. Clones share the kill switch.
+#[derive(Debug, Clone)]
+pub struct RiskManager {
+    pub limits: RiskLimits,
+    kill_switch: Arc<AtomicBool>,
+}
+
+impl RiskManager {
+    pub fn new(limits: RiskLimits) -> Self {
+        RiskManager {
+            limits,
+            kill_switch: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_killed(&self) -> bool {
+        self.kill_switch.load(Ordering::SeqCst)
+    }
+
+    // Blocks all new orders and modifications which do not reduce a position until released. Use ApiClient::kill to also
+    // exit positions.
+    pub fn engage_kill_switch(&self) {
+        self.kill_switch.store(true, Ordering::SeqCst);
+    }
+
+    pub fn release_kill_switch(&self) {
+        self.kill_switch.store(false, Ordering::SeqCst);
+    }
+
+    // The kill switch, open order and daily loss limits only apply to orders adding exposure, so positions can still be
+    // protected and closed once they are reached
+    async fn check_new_orders(
+        &self,
+        api_client: &ApiClient,
+        orders: &[OrderRisk],
+    ) -> Result<(), RiskViolation> {
+        if self.is_killed()
+            || self.limits.max_open_orders.is_some()
+            || self.limits.max_daily_loss.is_some()
+        {
+            // Fetched once for all the checks below
+            let positions: Vec<PositionsResponse> = Self::positions(api_client).await?;
+            let order_book: Vec<OrderDetailsResponse> = Self::order_book(api_client).await?;
+            let mut reducible: ReduciblePositions =
+                ReduciblePositions::new(&positions, &order_book);
+            let exposure_orders: usize = orders
+                .iter()
+                .filter(|order| !reducible.reduce(order))
+                .count();
+            if exposure_orders > 0 {
+                if self.is_killed() {
+                    return Err(RiskViolation::KillSwitchEngaged);
+                }
+                self.check_open_orders(&order_book, exposure_orders)?;
+                self.check_daily_loss(&positions)?;
+            }
+        }
+        self.check_orders(api_client, orders).await
+    }
+
+    async fn check_orders(
+        &self,
+        api_client: &ApiClient,
+        orders: &[OrderRisk],
+    ) -> Result<(), RiskViolation> {
+        for order in orders {
+            let limit: Option<u32> = self
+                .limits
+                .max_quantity_per_instrument
+                .get(&order.instrument_key)
+                .copied()
+                .or(self.limits.max_quantity);
+            if let Some(limit) = limit
+                && order.quantity > limit
+            {
+                return Err(RiskViolation::MaxQuantity {
+                    instrument_key: order.instrument_key.clone(),
+                    quantity: order.quantity,
+                    limit,
+                });
+            }
+        }
+
+        if self.limits.max_order_value.is_none() && !self.limits.check_circuit_limits {
+            return Ok(());
+        }
+        let quotes: Vec<FullMarketQuotesResponse> = retry_on_rate_limit(|| {
+            api_client.get_full_market_quotes(FullMarketQuotesRequest {
+                instrument_key: orders
+                    .iter()
+                    .map(|order| order.instrument_key.clone())
+                    .collect(),
+            })
+        })
+        .await
+        .map(|response| response.data.into_values().collect())
+        .map_err(|error_response| RiskViolation::CheckFailed(error_response.message()))?;
+
+        for order in orders {
+            let quote: &FullMarketQuotesResponse = quotes
+                .iter()
+                .find(|quote| quote.instrument_token == order.instrument_key)
+                .ok_or_else(|| {
+                    RiskViolation::CheckFailed(format!("No quote for {}", order.instrument_key))
+                })?;
+
+            if let Some(limit) = self.limits.max_order_value {
+                let value: f64 = order.price.unwrap_or(quote.last_price)
+                    * order.quantity as f64
+                    * Self::quantity_multiplier(api_client, &order.instrument_key) as f64;
+                if value > limit {
+                    return Err(RiskViolation::MaxOrderValue {
+                        instrument_key: order.instrument_key.clone(),
+                        value,
+                        limit,
+                    });
+                }
+            }
+
+            if self.limits.check_circuit_limits {
+                for price in [order.price, order.trigger_price].into_iter().flatten() {
+                    if price < quote.lower_circuit_limit || price > quote.upper_circuit_limit {
+                        return Err(RiskViolation::OutsideCircuitLimits {
+                            instrument_key: order.instrument_key.clone(),
+                            price,
+                            lower_circuit_limit: quote.lower_circuit_limit,
+                            upper_circuit_limit: quote.upper_circuit_limit,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_open_orders(
+        &self,
+        order_book: &[OrderDetailsResponse],
+        new_orders: usize,
+    ) -> Result<(), RiskViolation> {
+        let Some(limit) = self.limits.max_open_orders else {
+            return Ok(());
+        };
+        let open_orders: usize = order_book
+            .iter()
+            .filter(|order| !order.status.is_terminal())
+            .count()
+            + new_orders;
+        match open_orders > limit {
+            true => Err(RiskViolation::MaxOpenOrders { open_orders, limit }),
+            false => Ok(()),
+        }
+    }
+
+    fn check_daily_loss(&self, positions: &[PositionsResponse]) -> Result<(), RiskViolation> {
+        let Some(limit) = self.limits.max_daily_loss else {
+            return Ok(());
+        };
+        let loss: f64 = -positions
+            .iter()
+            .map(|position| position.realised + position.unrealised)
+            .sum::<f64>();
+        match loss >= limit {
+            true => Err(RiskViolation::MaxDailyLoss { loss, limit }),
+            false => Ok(()),
+        }
+    }
+
+    async fn positions(api_client: &ApiClient) -> Result<Vec<PositionsResponse>, RiskViolation> {
+        retry_on_rate_limit(|| api_client.get_positions())
+            .await
+            .map(|response| response.data)
+            .map_err(|error_response| RiskViolation::CheckFailed(error_response.message()))
+    }
+
+    async fn order_book(
+        api_client: &ApiClient,
+    ) -> Result<Vec<OrderDetailsResponse>, RiskViolation> {
+        retry_on_rate_limit(|| api_client.get_order_book())
+            .await
+            .map(|response| response.data)
+            .map_err(|error_response| RiskViolation::CheckFailed(error_response.message()))
+    }
+
+    // MCX quantities are in lots
+    fn quantity_multiplier(api_client: &ApiClient, instrument_key: &InstrumentKey) -> u32 {
+        match instrument_key.segment() {
+            ExchangeSegment::McxFo => api_client
+                .instruments
+                .as_ref()
+                .and_then(|instruments| instruments.get(instrument_key))
+                .and_then(|instrument| instrument.lot_size())
+                .unwrap_or(1),
+            _ => 1,
+        }
+    }
+}
+
+impl ApiClient {
+    pub fn set_risk_manager(&mut self, risk_manager: Option<RiskManager>) {
+        self.risk_manager = risk_manager;
+    }
+
+    pub fn risk_manager(&self) -> Option<&RiskManager> {
+        self.risk_manager.as_ref()
+    }
+
+    // Engages the kill switch and exits all positions if configured
+    pub async fn kill(&self) -> Result<(), String> {
+        let risk_manager: &RiskManager = self
+            .risk_manager
+            .as_ref()
+            .ok_or_else(|| "No risk manager is set".to_string())?;
+        risk_manager.engage_kill_switch();
+        warn!("Kill switch engaged");
+        if risk_manager.limits.exit_positions_on_kill {
+            retry_on_rate_limit(|| {
+                self.exit_all_positions(ExitAllPositionsRequest {
+                    segment: None,
+                    taget: None,
+                })
+            })
+            .await
+            .map_err(|error_response| error_response.message())?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn check_place_order_risk(
+        &self,
+        place_order_body: &PlaceOrderV3Request,
+    ) -> Result<(), RiskViolation> {
+        match &self.risk_manager {
+            Some(risk_manager) => {
+                risk_manager
+                    .check_new_orders(self, &[OrderRisk::from(place_order_body)])
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) async fn check_place_multi_order_risk(
+        &self,
+        place_multi_order_body: &[PlaceMultiOrderRequest],
+    ) -> Result<(), RiskViolation> {
+        match &self.risk_manager {
+            Some(risk_manager) => {
+                let orders: Vec<OrderRisk> =
+                    place_multi_order_body.iter().map(OrderRisk::from).collect();
+                risk_manager.check_new_orders(self, &orders).await
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) async fn check_place_gtt_order_risk(
+        &self,
+        place_gtt_order_body: &PlaceGTTOrderRequest,
+    ) -> Result<(), RiskViolation> {
+        match &self.risk_manager {
+            Some(risk_manager) => {
+                risk_manager
+                    .check_new_orders(self, &[OrderRisk::from(place_gtt_order_body)])
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Modifications are not new orders, so only the quantity, value and price checks apply, along with the kill switch when
+    // the order does not reduce a position
+    pub(crate) async fn check_modify_order_risk(
+        &self,
+        modify_order_body: &ModifyOrderRequest,
+    ) -> Result<(), RiskViolation> {
+        let Some(risk_manager) = &self.risk_manager else {
+            return Ok(());
+        };
+        let order_details: OrderDetailsResponse = retry_on_rate_limit(|| {
+            self.get_order_details(OrderDetailsRequest {
+                order_id: Some(modify_order_body.order_id.clone()),
+                tag: None,
+            })
+        })
+        .await
+        .map(|response| response.data)
+        .map_err(|error_response| RiskViolation::CheckFailed(error_response.message()))?;
+        let order: OrderRisk = OrderRisk {
+            instrument_key: order_details.instrument_token,
+            product: order_details.product,
+            transaction_type: order_details.transaction_type,
+            quantity: modify_order_body.quantity.unwrap_or(order_details.quantity),
+            price: Some(modify_order_body.price).filter(|price| *price > 0.0),
+            trigger_price: modify_order_body.trigger_price.filter(|price| *price > 0.0),
+        };
+        if risk_manager.is_killed() {
+            // The filled part of the order is already in the position
+            let pending_order: OrderRisk = OrderRisk {
+                quantity: order.quantity.saturating_sub(order_details.filled_quantity),
+                ..order.clone()
+            };
+            let positions: Vec<PositionsResponse> = RiskManager::positions(self).await?;
+            // The order being modified does not close the position on top of its own modification
+            let open_orders: Vec<OrderDetailsResponse> = RiskManager::order_book(self)
+                .await?
+                .into_iter()
+                .filter(|open_order| open_order.order_id != order_details.order_id)
+                .collect();
+            if !ReduciblePositions::new(&positions, &open_orders).reduce(&pending_order) {
+                return Err(RiskViolation::KillSwitchEngaged);
+            }
+        }
+        risk_manager.check_orders(self, &[order]).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sell(quantity: u32) -> OrderRisk {
+        OrderRisk {
+            instrument_key: "NSE_EQ|INE002A01018".parse().unwrap(),
+            product: ProductType::D,
+            transaction_type: TransactionType::Sell,
+            quantity,
+            price: None,
+            trigger_price: None,
+        }
+    }
+
+    fn long(quantity: i64) -> ReduciblePositions {
+        let order: OrderRisk = sell(0);
+        ReduciblePositions {
+            quantities: HashMap::from([((order.instrument_key, order.product), quantity)]),
+        }
+    }
+
+    #[test]
+    fn orders_of_a_batch_close_a_position_only_once() {
+        let mut reducible: ReduciblePositions = long(100);
+        let reducing: Vec<bool> = (0..5).map(|_| reducible.reduce(&sell(100))).collect();
+        assert_eq!(reducing, vec![true, false, false, false, false]);
+    }
+
+    #[test]
+    fn orders_close_what_is_left_of_a_position() {
+        let mut reducible: ReduciblePositions = long(100);
+        assert!(reducible.reduce(&sell(60)));
+        assert!(!reducible.reduce(&sell(60)));
+        assert!(reducible.reduce(&sell(40)));
+    }
+
+    #[test]
+    fn open_closing_orders_are_not_reducible_again() {
+        let mut reducible: ReduciblePositions = long(100);
+        let stop_loss: OrderRisk = sell(100);
+        reducible.take_pending(
+            &stop_loss.instrument_key,
+            stop_loss.product,
+            stop_loss.transaction_type,
+            stop_loss.quantity,
+        );
+        assert!(!reducible.reduce(&sell(100)));
+    }
+
+    #[test]
+    fn buys_do_not_reduce_long_positions() {
+        let mut reducible: ReduciblePositions = long(100);
+        let buy: OrderRisk = OrderRisk {
+            transaction_type: TransactionType::Buy,
+            ..sell(100)
+        };
+        assert!(!reducible.reduce(&buy));
+    }
+}
@@ -0,0 +1,283 @@
+use {
+    crate::{
+        client::ApiClient,
+        constants::{MULTI_ORDER_MAX_CORRELATION_ID_LENGTH, MULTI_ORDER_MAX_ORDERS},
+        models::{
+            ExchangeSegment,
+            error_response::ErrorResponse,
+            instrument_key::InstrumentKey,
+            instruments::instruments_response::InstrumentsResponse,
+            orders::{
+                order_details_request::OrderDetailsRequest,
+                order_details_response::OrderDetailsResponse, orders_response::OrdersResponse,
+                place_multi_order_request::PlaceMultiOrderRequest,
+                place_multi_order_response::PlaceMultiOrderResponse,
+                place_order_v3_request::PlaceOrderV3Request,
+            },
+            success_response::SuccessResponse,
+        },
+        rate_limiter::retry_on_rate_limit,
+    },
+    std::collections::HashMap,
+};
+
+#[derive(Debug, Clone)]
+pub struct OrderSlice {
+    pub quantity: u32,
+    pub correlation_id: Option<String>,
+    pub result: Result<Vec<String>, String>, // Order ids, or the reason the slice was not placed
+}
+
+// Orders placed for the slices of one order
+#[derive(Debug, Clone, Default)]
+pub struct SlicedOrder {
+    pub slices: Vec<OrderSlice>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SlicedOrderFills {
+    pub quantity: u32,
+    pub filled_quantity: u32,
+    pub pending_quantity: u32,
+    pub average_price: f64,
+}
+
+impl SlicedOrder {
+    pub fn order_ids(&self) -> Vec<&str> {
+        self.slices
+            .iter()
+            .filter_map(|slice| slice.result.as_ref().ok())
+            .flatten()
+            .map(String::as_str)
+            .collect()
+    }
+
+    pub fn is_fully_placed(&self) -> bool {
+        self.slices.iter().all(|slice| slice.result.is_ok())
+    }
+
+    pub fn placed_quantity(&self) -> u32 {
+        self.slices
+            .iter()
+            .filter(|slice| slice.result.is_ok())
+            .map(|slice| slice.quantity)
+            .sum()
+    }
+
+    // Fetches the details of every placed slice and aggregates their fills
+    pub async fn fills(&self, api_client: &ApiClient) -> Result<SlicedOrderFills, String> {
+        let mut fills: SlicedOrderFills = SlicedOrderFills::default();
+        let mut filled_value: f64 = 0.0;
+        for order_id in self.order_ids() {
+            let order_details: OrderDetailsResponse = retry_on_rate_limit(|| {
+                api_client.get_order_details(OrderDetailsRequest {
+                    order_id: Some(order_id.to_string()),
+                    tag: None,
+                })
+            })
+            .await
+            .map(|response| response.data)
+            .map_err(|error_response| error_response.message())?;
+            fills.quantity += order_details.quantity;
+            fills.filled_quantity += order_details.filled_quantity;
+            fills.pending_quantity += order_details.pending_quantity;
+            filled_value += order_details.average_price * order_details.filled_quantity as f64;
+        }
+        if fills.filled_quantity > 0 {
+            fills.average_price = filled_value / fills.filled_quantity as f64;
+        }
+        Ok(fills)
+    }
+}
+
+// Largest lot aligned quantity accepted in one order. Exchanges reject orders of freeze quantity or more.
+pub fn max_slice_quantity(lot_size: u32, freeze_quantity: f64) -> Option<u32> {
+    if lot_size == 0 || freeze_quantity <= 0.0 {
+        return None;
+    }
+    let below_freeze: u32 = (freeze_quantity.ceil() as u32).saturating_sub(1);
+    match (below_freeze / lot_size) * lot_size {
+        0 => None,
+        max_slice => Some(max_slice),
+    }
+}
+
+// Splits the quantity into slices of at most the largest quantity below the freeze quantity, keeping each a multiple of
+// the lot size when the quantity is
+pub fn slice_quantity(quantity: u32, lot_size: u32, freeze_quantity: f64) -> Vec<u32> {
+    let Some(max_slice) = max_slice_quantity(lot_size, freeze_quantity) else {
+        return vec![quantity];
+    };
+    let mut slices: Vec<u32> = vec![max_slice; (quantity / max_slice) as usize];
+    if !quantity.is_multiple_of(max_slice) {
+        slices.push(quantity % max_slice);
+    }
+    slices
+}
+
+impl ApiClient {
+    // Places the order in slices below the freeze quantity of the instrument, waiting out order rate limits between them
+    pub async fn place_sliced_order(
+        &self,
+        place_order_body: PlaceOrderV3Request,
+        sandbox: bool,
+    ) -> Result<SlicedOrder, String> {
+        let slices: Vec<u32> = self.slices_for(
+            &place_order_body.instrument_token,
+            place_order_body.quantity,
+        )?;
+
+        let mut sliced_order: SlicedOrder = SlicedOrder::default();
+        for quantity in slices {
+            let slice_body: PlaceOrderV3Request = PlaceOrderV3Request {
+                quantity,
+                ..place_order_body.clone()
+            };
+            let result: Result<SuccessResponse<OrdersResponse>, ErrorResponse> =
+                retry_on_rate_limit(|| self.place_order_v3(slice_body.clone(), sandbox)).await;
+            sliced_order.slices.push(OrderSlice {
+                quantity,
+                correlation_id: None,
+                result: result
+                    .map(|response| response.data.order_ids)
+                    .map_err(|error_response| error_response.message()),
+            });
+        }
+        Ok(sliced_order)
+    }
+
+    // Slices every order above its freeze quantity and places them in batches accepted by the multi order API. Slices get
+    // the correlation id of their order suffixed with their position, e.g. 1-0 and 1-1.
+    pub async fn place_sliced_multi_order(
+        &self,
+        place_multi_order_body: Vec<PlaceMultiOrderRequest>,
+        sandbox: bool,
+    ) -> Result<HashMap<String, SlicedOrder>, String> {
+        let mut slice_bodies: Vec<(String, PlaceMultiOrderRequest)> = Vec::new();
+        for order_body in &place_multi_order_body {
+            let slices: Vec<u32> =
+                self.slices_for(&order_body.instrument_token, order_body.quantity)?;
+            let sliced: bool = slices.len() > 1;
+            // Checked before any batch is placed, the longest suffix is that of the last slice
+            let suffix_length: usize = format!("-{}", slices.len() - 1).len();
+            if sliced
+                && order_body.correlation_id.len() + suffix_length
+                    > MULTI_ORDER_MAX_CORRELATION_ID_LENGTH
+            {
+                return Err(format!(
+                    "Correlation id {} is too long to be suffixed for {} slices, it can have at most {} characters",
+                    order_body.correlation_id,
+                    slices.len(),
+                    MULTI_ORDER_MAX_CORRELATION_ID_LENGTH - suffix_length
+                ));
+            }
+            for (position, quantity) in slices.into_iter().enumerate() {
+                let correlation_id: String = match sliced {
+                    true => format!("{}-{}", order_body.correlation_id, position),
+                    false => order_body.correlation_id.clone(),
+                };
+                slice_bodies.push((
+                    order_body.correlation_id.clone(),
+                    PlaceMultiOrderRequest {
+                        correlation_id,
+                        quantity,
+                        ..order_body.clone()
+                    },
+                ));
+            }
+        }
+
+        let mut sliced_orders: HashMap<String, SlicedOrder> = HashMap::new();
+        for batch in slice_bodies.chunks(MULTI_ORDER_MAX_ORDERS) {
+            let batch_body: Vec<PlaceMultiOrderRequest> =
+                batch.iter().map(|(_, body)| body.clone()).collect();
+            let result: Result<SuccessResponse<PlaceMultiOrderResponse>, ErrorResponse> =
+                retry_on_rate_limit(|| self.place_multi_order(batch_body.clone(), sandbox)).await;
+
+            for (parent_correlation_id, body) in batch {
+                let slice_result: Result<Vec<String>, String> = match &result {
+                    Ok(response) => response
+                        .data
+                        .order_ids
+                        .iter()
+                        .find(|order| order.correlation_id == body.correlation_id)
+                        .map(|order| vec![order.order_id.clone()])
+                        .ok_or_else(|| {
+                            response
+                                .errors
+                                .iter()
+                                .flatten()
+                                .find(|error| {
+                                    error.correlation_id.as_ref() == Some(&body.correlation_id)
+                                })
+                                .map(|error| error.message.clone())
+                                .unwrap_or_else(|| "Order was not placed".to_string())
+                        }),
+                    Err(error_response) => Err(error_response.message()),
+                };
+                sliced_orders
+                    .entry(parent_correlation_id.clone())
+                    .or_default()
+                    .slices
+                    .push(OrderSlice {
+                        quantity: body.quantity,
+                        correlation_id: Some(body.correlation_id.clone()),
+                        result: slice_result,
+                    });
+            }
+        }
+        Ok(sliced_orders)
+    }
+
+    fn slices_for(
+        &self,
+        instrument_key: &InstrumentKey,
+        quantity: u32,
+    ) -> Result<Vec<u32>, String> {
+        let instrument: &InstrumentsResponse = self
+            .instruments
+            .as_ref()
+            .and_then(|instruments| instruments.get(instrument_key))
+            .ok_or_else(|| format!("Instrument {} not found in instruments", instrument_key))?;
+        let Some(freeze_quantity) = instrument.freeze_quantity() else {
+            return Ok(vec![quantity]);
+        };
+        // MCX quantities are in lots
+        let lot_size: u32 = match instrument.segment() {
+            ExchangeSegment::McxFo => 1,
+            _ => instrument.lot_size().unwrap_or(1),
+        };
+        Ok(slice_quantity(quantity, lot_size, freeze_quantity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_slice_stays_below_freeze_quantity() {
+        assert_eq!(max_slice_quantity(75, 1800.0), Some(1725));
+        assert_eq!(max_slice_quantity(1, 1800.0), Some(1799));
+        assert_eq!(max_slice_quantity(25, 1800.5), Some(1800));
+    }
+
+    #[test]
+    fn no_max_slice_without_lot_or_freeze_quantity() {
+        assert_eq!(max_slice_quantity(0, 1800.0), None);
+        assert_eq!(max_slice_quantity(75, 0.0), None);
+        assert_eq!(max_slice_quantity(2000, 1800.0), None);
+    }
+
+    #[test]
+    fn slices_lot_multiples_with_remainder_last() {
+        assert_eq!(slice_quantity(3600, 75, 1800.0), vec![1725, 1725, 150]);
+        assert_eq!(slice_quantity(3450, 75, 1800.0), vec![1725, 1725]);
+        assert_eq!(slice_quantity(75, 75, 1800.0), vec![75]);
+    }
+
+    #[test]
+    fn does_not_slice_without_max_slice() {
+        assert_eq!(slice_quantity(5000, 75, 0.0), vec![5000]);
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tokio::time::{Duration, Instant};
+use tokio::time::{Duration, Instant, sleep_until};
 
 #[derive(Debug, Clone)]
 pub enum RateLimitExceeded {
@@ -10,6 +10,30 @@ pub enum RateLimitExceeded {
     PerThirtyMinutes { next_allowed_at: Instant },
 }
 
+impl RateLimitExceeded {
+    pub fn next_allowed_at(&self) -> Instant {
+        match self {
+            RateLimitExceeded::PerSecond { next_allowed_at }
+            | RateLimitExceeded::PerMinute { next_allowed_at }
+            | RateLimitExceeded::PerThirtyMinutes { next_allowed_at } => *next_allowed_at,
+        }
+    }
+}
+
+// Calls the API again once allowed, for as long as the rate limit is exceeded
+pub async fn retry_on_rate_limit<T, F, Fut>(mut call: F) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, RateLimitExceeded>>,
+{
+    loop {
+        match call().await {
+            Ok(result) => return result,
+            Err(rate_limit_exceeded) => sleep_until(rate_limit_exceeded.next_allowed_at()).await,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RateLimiter {
     per_second: usize,
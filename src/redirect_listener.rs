@@ -0,0 +1,256 @@
+use {
+    rand::{Rng, distr::Alphanumeric},
+    reqwest::Url,
+    std::{collections::HashMap, fmt, io, net::SocketAddr},
+    tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        time::{Duration, timeout},
+    },
+    tracing::debug,
+};
+
+const MAX_REQUEST_SIZE: usize = 64 * 1024;
+const MAX_HEADERS: usize = 64;
+const STATE_LENGTH: usize = 32;
+// Browsers open speculative connections which may never carry a request, so they must not hold up the listener
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum RedirectError {
+    Bind(io::Error),
+    Io(io::Error),
+    Timeout,
+    MalformedRequest(String),
+    StateMismatch,
+    AuthorizationDenied {
+        error: String,
+        description: Option<String>,
+    },
+}
+
+impl fmt::Display for RedirectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedirectError::Bind(err) => write!(f, "Failed to bind redirect listener: {}", err),
+            RedirectError::Io(err) => write!(f, "Redirect listener I/O error: {}", err),
+            RedirectError::Timeout => write!(f, "Timed out waiting for redirect"),
+            RedirectError::MalformedRequest(reason) => {
+                write!(f, "Malformed redirect request: {}", reason)
+            }
+            RedirectError::StateMismatch => {
+                write!(f, "Redirect state does not match the one sent")
+            }
+            RedirectError::AuthorizationDenied { error, description } => match description {
+                Some(description) => write!(f, "Authorization denied: {} ({})", error, description),
+                None => write!(f, "Authorization denied: {}", error),
+            },
+        }
+    }
+}
+
+impl std::error::Error for RedirectError {}
+
+#[derive(Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>, // Names are lowercased
+    pub body: Vec<u8>,
+}
+
+// Local HTTP server on the redirect port, used for OAuth redirects and the Upstox notifier webhook
+pub struct RedirectListener {
+    listener: TcpListener,
+}
+
+impl RedirectListener {
+    pub async fn bind(port: u16) -> Result<Self, RedirectError> {
+        let addr: SocketAddr = SocketAddr::from(([127, 0, 0, 1], port));
+        TcpListener::bind(addr)
+            .await
+            .map(|listener| Self { listener })
+            .map_err(RedirectError::Bind)
+    }
+
+    // Waits for the redirect carrying the authorization code. Requests without a code or error, such as favicon and preflight
+    // requests, are answered and ignored.
+    pub async fn await_code(
+        &self,
+        expected_state: &str,
+        wait: Duration,
+    ) -> Result<String, RedirectError> {
+        timeout(wait, async {
+            loop {
+                let (mut socket, request) = match self.next_request().await {
+                    Ok(accepted) => accepted,
+                    Err(RedirectError::MalformedRequest(reason)) => {
+                        debug!(
+                            "Ignoring malformed request on redirect listener: {}",
+                            reason
+                        );
+                        continue;
+                    }
+                    Err(err) => return Err(err),
+                };
+
+                if request.method == "OPTIONS" {
+                    Self::respond(&mut socket, "204 No Content", "").await;
+                    continue;
+                }
+                if request.method != "GET"
+                    || request.path != "/"
+                    || !(request.query.contains_key("code") || request.query.contains_key("error"))
+                {
+                    debug!(
+                        "Ignoring {} {} on redirect listener",
+                        request.method, request.path
+                    );
+                    Self::respond(&mut socket, "404 Not Found", "").await;
+                    continue;
+                }
+
+                if request.query.get("state").map(String::as_str) != Some(expected_state) {
+                    Self::respond(
+                        &mut socket,
+                        "400 Bad Request",
+                        &Self::page("Login failed", "The response could not be verified."),
+                    )
+                    .await;
+                    return Err(RedirectError::StateMismatch);
+                }
+
+                if let Some(error) = request.query.get("error") {
+                    Self::respond(
+                        &mut socket,
+                        "200 OK",
+                        &Self::page("Login failed", "Authorization was not granted."),
+                    )
+                    .await;
+                    return Err(RedirectError::AuthorizationDenied {
+                        error: error.clone(),
+                        description: request.query.get("error_description").cloned(),
+                    });
+                }
+
+                Self::respond(
+                    &mut socket,
+                    "200 OK",
+                    &Self::page("Login successful", "You can now close this tab!"),
+                )
+                .await;
+                return Ok(request.query["code"].clone());
+            }
+        })
+        .await
+        .map_err(|_| RedirectError::Timeout)?
+    }
+
+    // Accepts a connection and reads one request including its body as given by Content-Length. Connections which do not
+    // send a complete request within REQUEST_READ_TIMEOUT are dropped as malformed. The caller must answer the request
+    // using respond.
+    pub async fn next_request(&self) -> Result<(TcpStream, HttpRequest), RedirectError> {
+        let (mut socket, _) = self.listener.accept().await.map_err(RedirectError::Io)?;
+        let request: HttpRequest = timeout(REQUEST_READ_TIMEOUT, Self::read_request(&mut socket))
+            .await
+            .map_err(|_| {
+                RedirectError::MalformedRequest("Timed out reading request".to_string())
+            })??;
+        Ok((socket, request))
+    }
+
+    async fn read_request(socket: &mut TcpStream) -> Result<HttpRequest, RedirectError> {
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut chunk: [u8; 1024] = [0; 1024];
+
+        loop {
+            let bytes_read: usize = socket.read(&mut chunk).await.map_err(RedirectError::Io)?;
+            if bytes_read == 0 {
+                return Err(RedirectError::MalformedRequest(
+                    "Connection closed before request was complete".to_string(),
+                ));
+            }
+            buffer.extend_from_slice(&chunk[..bytes_read]);
+            if buffer.len() > MAX_REQUEST_SIZE {
+                Self::respond(socket, "413 Payload Too Large", "").await;
+                return Err(RedirectError::MalformedRequest(
+                    "Request too large".to_string(),
+                ));
+            }
+
+            let mut headers: [httparse::Header; MAX_HEADERS] =
+                [httparse::EMPTY_HEADER; MAX_HEADERS];
+            let mut parsed: httparse::Request = httparse::Request::new(&mut headers);
+            let header_length: usize = match parsed.parse(&buffer) {
+                Ok(httparse::Status::Complete(header_length)) => header_length,
+                Ok(httparse::Status::Partial) => continue,
+                Err(err) => {
+                    Self::respond(socket, "400 Bad Request", "").await;
+                    return Err(RedirectError::MalformedRequest(err.to_string()));
+                }
+            };
+
+            let headers: HashMap<String, String> = parsed
+                .headers
+                .iter()
+                .map(|header| {
+                    (
+                        header.name.to_ascii_lowercase(),
+                        String::from_utf8_lossy(header.value).trim().to_string(),
+                    )
+                })
+                .collect();
+            let content_length: usize = headers
+                .get("content-length")
+                .and_then(|value| value.parse::<usize>().ok())
+                .unwrap_or(0);
+            if buffer.len() < header_length + content_length {
+                continue;
+            }
+
+            let target: Url = Url::parse("http://127.0.0.1")
+                .unwrap()
+                .join(parsed.path.unwrap_or("/"))
+                .map_err(|err| RedirectError::MalformedRequest(err.to_string()))?;
+            let request: HttpRequest = HttpRequest {
+                method: parsed.method.unwrap_or_default().to_string(),
+                path: target.path().to_string(),
+                query: target.query_pairs().into_owned().collect(),
+                headers,
+                body: buffer[header_length..header_length + content_length].to_vec(),
+            };
+            return Ok(request);
+        }
+    }
+
+    pub async fn respond(socket: &mut TcpStream, status: &str, html: &str) {
+        let response: String = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            html.len(),
+            html
+        );
+        // The browser or webhook sender may already have gone away, which does not affect the result
+        if let Err(err) = socket.write_all(response.as_bytes()).await {
+            debug!("Failed to write redirect listener response: {}", err);
+        }
+        let _ = socket.shutdown().await;
+    }
+
+    fn page(title: &str, message: &str) -> String {
+        format!(
+            "<!DOCTYPE html><html><head><title>{}</title></head><body><h3>{}</h3><p>{}</p></body></html>",
+            title, title, message
+        )
+    }
+}
+
+// Random value sent as the OAuth state parameter and verified on redirect
+pub fn generate_state() -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(STATE_LENGTH)
+        .map(char::from)
+        .collect()
+}
@@ -0,0 +1,285 @@
+use {
+    crate::utils::{read_value_from_file, write_value_to_file},
+    aes_gcm::{
+        Aes256Gcm, Key, KeyInit, Nonce,
+        aead::{Aead, AeadCore, OsRng},
+    },
+    async_trait::async_trait,
+    std::{
+        collections::HashMap,
+        env,
+        fs::{self, File},
+        io::{ErrorKind, Read, Write},
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
+    tokio::sync::Mutex,
+};
+
+// Storage backend for the tokens obtained while logging in. Keys used by the SDK are listed in constants.
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+    async fn load(&self, key: &str) -> Result<Option<String>, String>;
+    async fn save(&self, key: &str, value: &str) -> Result<(), String>;
+    async fn invalidate(&self, key: &str) -> Result<(), String>;
+}
+
+// Stores each token as plain text in "<directory>/<key>.txt".
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    directory: PathBuf,
+}
+
+impl FileTokenStore {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{}.txt", key))
+    }
+}
+
+impl Default for FileTokenStore {
+    // Current working directory, which is where tokens were always stored by the SDK.
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+#[async_trait]
+impl TokenStore for FileTokenStore {
+    async fn load(&self, key: &str) -> Result<Option<String>, String> {
+        let path: PathBuf = self.path(key);
+        match read_value_from_file(&path) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(format!("Failed to read token from {:?}: {}", path, err)),
+        }
+    }
+
+    async fn save(&self, key: &str, value: &str) -> Result<(), String> {
+        let path: PathBuf = self.path(key);
+        fs::create_dir_all(&self.directory)
+            .map_err(|err| format!("Failed to create {:?}: {}", self.directory, err))?;
+        write_value_to_file(&path, value)
+            .map_err(|err| format!("Failed to write token to {:?}: {}", path, err))?;
+        restrict_permissions(&path)
+    }
+
+    async fn invalidate(&self, key: &str) -> Result<(), String> {
+        remove_if_exists(&self.path(key))
+    }
+}
+
+// Keeps tokens only for the lifetime of the process.
+#[derive(Debug, Default)]
+pub struct InMemoryTokenStore {
+    tokens: Mutex<HashMap<String, String>>,
+}
+
+impl InMemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TokenStore for InMemoryTokenStore {
+    async fn load(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.tokens.lock().await.get(key).cloned())
+    }
+
+    async fn save(&self, key: &str, value: &str) -> Result<(), String> {
+        self.tokens
+            .lock()
+            .await
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    async fn invalidate(&self, key: &str) -> Result<(), String> {
+        self.tokens.lock().await.remove(key);
+        Ok(())
+    }
+}
+
+// Reads tokens from "<prefix><KEY>" environment variables, e.g. UPSTOX_ACCESS_TOKEN for prefix "UPSTOX_". Characters of the
+// key not valid in variable names, such as the dashes of an API key scope, are replaced with underscores.
+// The process environment is never modified, saved and invalidated tokens only shadow it in memory.
+#[derive(Debug)]
+pub struct EnvTokenStore {
+    prefix: String,
+    overrides: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl EnvTokenStore {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            overrides: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn variable_name(&self, key: &str) -> String {
+        let key: String = key
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c.to_ascii_uppercase(),
+                false => '_',
+            })
+            .collect();
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+#[async_trait]
+impl TokenStore for EnvTokenStore {
+    async fn load(&self, key: &str) -> Result<Option<String>, String> {
+        if let Some(value) = self.overrides.lock().await.get(key) {
+            return Ok(value.clone());
+        }
+        Ok(env::var(self.variable_name(key))
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()))
+    }
+
+    async fn save(&self, key: &str, value: &str) -> Result<(), String> {
+        self.overrides
+            .lock()
+            .await
+            .insert(key.to_string(), Some(value.to_string()));
+        Ok(())
+    }
+
+    async fn invalidate(&self, key: &str) -> Result<(), String> {
+        self.overrides.lock().await.insert(key.to_string(), None);
+        Ok(())
+    }
+}
+
+// Stores each token in "<directory>/<key>.enc" encrypted with AES-256-GCM. The file holds the random nonce followed by the ciphertext.
+pub struct EncryptedFileTokenStore {
+    directory: PathBuf,
+    cipher: Aes256Gcm,
+}
+
+impl EncryptedFileTokenStore {
+    pub fn new(directory: impl Into<PathBuf>, key: [u8; 32]) -> Self {
+        Self {
+            directory: directory.into(),
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{}.enc", key))
+    }
+}
+
+#[async_trait]
+impl TokenStore for EncryptedFileTokenStore {
+    async fn load(&self, key: &str) -> Result<Option<String>, String> {
+        let path: PathBuf = self.path(key);
+        let mut content: Vec<u8> = Vec::new();
+        match File::open(&path) {
+            Ok(mut file) => file
+                .read_to_end(&mut content)
+                .map_err(|err| format!("Failed to read token from {:?}: {}", path, err))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(format!("Failed to open {:?}: {}", path, err)),
+        };
+
+        if content.len() < 12 {
+            return Err(format!("Encrypted token file {:?} is corrupted", path));
+        }
+        let (nonce, ciphertext) = content.split_at(12);
+        let plaintext: Vec<u8> = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| format!("Failed to decrypt token from {:?}", path))?;
+        String::from_utf8(plaintext)
+            .map(Some)
+            .map_err(|_| format!("Decrypted token from {:?} is not valid UTF-8", path))
+    }
+
+    async fn save(&self, key: &str, value: &str) -> Result<(), String> {
+        let path: PathBuf = self.path(key);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext: Vec<u8> = self
+            .cipher
+            .encrypt(&nonce, value.as_bytes())
+            .map_err(|_| "Failed to encrypt token".to_string())?;
+
+        fs::create_dir_all(&self.directory)
+            .map_err(|err| format!("Failed to create {:?}: {}", self.directory, err))?;
+        let mut file: File =
+            File::create(&path).map_err(|err| format!("Failed to create {:?}: {}", path, err))?;
+        file.write_all(&nonce)
+            .and_then(|_| file.write_all(&ciphertext))
+            .map_err(|err| format!("Failed to write token to {:?}: {}", path, err))?;
+        restrict_permissions(&path)
+    }
+
+    async fn invalidate(&self, key: &str) -> Result<(), String> {
+        remove_if_exists(&self.path(key))
+    }
+}
+
+// Prefixes every key with "<scope>_" so that the tokens of several accounts can share one store.
+pub struct ScopedTokenStore {
+    inner: Arc<dyn TokenStore>,
+    scope: String,
+}
+
+impl ScopedTokenStore {
+    pub fn new(inner: Arc<dyn TokenStore>, scope: &str) -> Self {
+        Self {
+            inner,
+            scope: scope.to_string(),
+        }
+    }
+
+    fn scoped_key(&self, key: &str) -> String {
+        format!("{}_{}", self.scope, key)
+    }
+}
+
+#[async_trait]
+impl TokenStore for ScopedTokenStore {
+    async fn load(&self, key: &str) -> Result<Option<String>, String> {
+        self.inner.load(&self.scoped_key(key)).await
+    }
+
+    async fn save(&self, key: &str, value: &str) -> Result<(), String> {
+        self.inner.save(&self.scoped_key(key), value).await
+    }
+
+    async fn invalidate(&self, key: &str) -> Result<(), String> {
+        self.inner.invalidate(&self.scoped_key(key)).await
+    }
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(format!("Failed to delete {:?}: {}", path, err)),
+    }
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+        .map_err(|err| format!("Failed to restrict permissions of {:?}: {}", path, err))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_: &Path) -> Result<(), String> {
+    Ok(())
+}
@@ -1,12 +1,22 @@
+pub mod serde_comma_separated;
 pub mod serde_spaced_lowercase;
 
 use {
-    crate::constants::{APIVersion, BaseUrlType},
+    crate::{
+        constants::{
+            APIVersion, BaseUrlType, IST_UTC_OFFSET_SECONDS, UPSTOX_TOKEN_EXPIRY_HOUR,
+            UPSTOX_TOKEN_EXPIRY_MINUTE,
+        },
+        models::error_response::{ErrorResponse, TRANSPORT_ERROR_STATUS},
+    },
+    chrono::{DateTime, Days, FixedOffset, NaiveDate, NaiveTime, Utc},
+    reqwest::{Response, header::CONTENT_TYPE},
     serde::Serialize,
     serde_json::Value,
     std::{
         fs::File,
         io::{Read, Write},
+        path::Path,
     },
 };
 
@@ -33,13 +43,13 @@ impl<T: Serialize + Send> ToKeyValueTuples for T {
     }
 }
 
-pub fn write_value_to_file(filename: &str, value: &str) -> std::io::Result<()> {
+pub fn write_value_to_file(filename: impl AsRef<Path>, value: &str) -> std::io::Result<()> {
     let mut file: File = File::create(filename)?;
     file.write_all(value.as_bytes())?;
     Ok(())
 }
 
-pub fn read_value_from_file(filename: &str) -> std::io::Result<String> {
+pub fn read_value_from_file(filename: impl AsRef<Path>) -> std::io::Result<String> {
     let mut file: File = File::open(filename)?;
     let mut value: String = String::new();
     file.read_to_string(&mut value)?;
@@ -61,3 +71,49 @@ pub fn create_url(base_url_type: BaseUrlType, api_version: APIVersion, endpoint:
         endpoint
     )
 }
+
+// Response carrying an ErrorResponse for a request which could not be sent, so that the APIs return it like any other
+// error instead of failing
+pub(crate) fn transport_error_response(err: &reqwest::Error) -> Response {
+    let body: String = serde_json::to_string(&ErrorResponse::transport_error(err.to_string()))
+        .expect("Failed to serialize");
+    http::Response::builder()
+        .status(TRANSPORT_ERROR_STATUS)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body)
+        .expect("Failed to build response")
+        .into()
+}
+
+pub fn ist_offset() -> FixedOffset {
+    FixedOffset::east_opt(IST_UTC_OFFSET_SECONDS).unwrap()
+}
+
+// First 3:30 AM IST strictly after the token was issued
+pub fn token_expiry_after(issued_at: DateTime<Utc>) -> DateTime<FixedOffset> {
+    let issued_at: DateTime<FixedOffset> = issued_at.with_timezone(&ist_offset());
+    let expiry_time: NaiveTime =
+        NaiveTime::from_hms_opt(UPSTOX_TOKEN_EXPIRY_HOUR, UPSTOX_TOKEN_EXPIRY_MINUTE, 0).unwrap();
+    let expiry_date: NaiveDate = if issued_at.time() < expiry_time {
+        issued_at.date_naive()
+    } else {
+        issued_at.date_naive().checked_add_days(Days::new(1)).unwrap()
+    };
+    expiry_date
+        .and_time(expiry_time)
+        .and_local_timezone(ist_offset())
+        .unwrap()
+}
+
+// Latest occurrence of the given IST time of day at or before now
+pub fn latest_ist_time(now: DateTime<Utc>, hour: u32, minute: u32) -> DateTime<FixedOffset> {
+    let now: DateTime<FixedOffset> = now.with_timezone(&ist_offset());
+    let time: NaiveTime = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+    let date: NaiveDate = match now.time() >= time {
+        true => now.date_naive(),
+        false => now.date_naive().checked_sub_days(Days::new(1)).unwrap(),
+    };
+    date.and_time(time)
+        .and_local_timezone(ist_offset())
+        .unwrap()
+}
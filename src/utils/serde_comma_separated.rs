@@ -0,0 +1,14 @@
+use {serde::Serializer, std::fmt};
+
+pub fn serialize<S, T>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: fmt::Display,
+{
+    let joined: String = values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(",");
+    serializer.serialize_str(&joined)
+}